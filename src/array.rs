@@ -0,0 +1,190 @@
+//! Trees over the rows of `ndarray` matrices.
+//!
+//! ```rust
+//! extern crate ndarray;
+//! extern crate vptree;
+//!
+//! use ndarray::{arr1, arr2};
+//! use vptree::array::{ArrayVPTree, Norm};
+//!
+//! # fn main() {
+//! let data = arr2(&[[0.0, 0.0], [1.0, 1.0], [5.0, 5.0]]);
+//! let tree = ArrayVPTree::new(data.view(), Norm::L1).unwrap();
+//! assert_eq!(tree.nearest_neighbor(arr1(&[4.0, 3.0]).view()), Some(2));
+//! assert_eq!(tree.within_radius(arr1(&[0.5, 0.5]).view(), 1.5, true), vec![0, 1]);
+//! # }
+//! ```
+
+use ndarray::{Array2, ArrayView1, ArrayView2, Axis, CowArray, Ix1};
+
+use error::BuildError;
+use vptree::{MetricItem, VPTree, VPTreeBuilder};
+
+/// The Lp norm a tree measures the difference of two rows by.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Norm {
+    /// The Manhattan distance, the sum of the absolute differences.
+    L1,
+    /// The Euclidean distance.
+    L2,
+    /// The Chebyshev distance, the largest absolute difference.
+    LInf,
+    /// The Minkowski distance for the given `p`, which must be at
+    /// least 1.
+    Lp(f64),
+}
+
+impl Norm {
+    fn distance(self, a: ArrayView1<f64>, b: ArrayView1<f64>) -> f64 {
+        let ds = a.iter().zip(b.iter()).map(|(x, y)| (x - y).abs());
+        match self {
+            Norm::L1 => ds.sum(),
+            Norm::L2 => ds.map(|d| d * d).sum::<f64>().sqrt(),
+            Norm::LInf => ds.fold(0.0, f64::max),
+            Norm::Lp(p) => ds.map(|d| d.powf(p)).sum::<f64>().powf(p.recip()),
+        }
+    }
+}
+
+/// A row of the matrix a tree is built over, or a query.
+///
+/// Rows of a borrowed matrix are borrowed from it, while queries and
+/// the rows of an owned matrix are copied, so that they need not
+/// outlive the tree.
+#[derive(Debug, Clone)]
+pub struct Row<'a> {
+    index: usize,
+    data: CowArray<'a, f64, Ix1>,
+    norm: Norm,
+}
+
+impl<'a> Row<'a> {
+    /// Return the index of the row in the matrix, or `usize::MAX` for
+    /// query rows.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Return the contents of the row.
+    pub fn data(&self) -> ArrayView1<'_, f64> {
+        self.data.view()
+    }
+}
+
+impl<'a> MetricItem<f64> for Row<'a> {
+    fn distance(&self, b: &Self) -> f64 {
+        self.norm.distance(self.data.view(), b.data.view())
+    }
+}
+
+/// A vantage point tree over the rows of a matrix.
+///
+/// The tree borrows the matrix rather than copying its rows, unless
+/// it is built from an owned matrix with `from_array`. Queries return
+/// the indices of the matching rows.
+pub struct ArrayVPTree<'a> {
+    tree: VPTree<f64, Row<'a>>,
+    norm: Norm,
+    dim: usize,
+}
+
+impl<'a> ArrayVPTree<'a> {
+    /// Build a tree over the rows of `data` with the default options,
+    /// measuring distances with `norm`.
+    ///
+    /// Returns `BuildError::InvalidParameter` if the `p` of
+    /// `Norm::Lp` is below 1, and otherwise fails as `try_build` does,
+    /// except that a matrix without rows is allowed.
+    pub fn new(data: ArrayView2<'a, f64>, norm: Norm) -> Result<Self, BuildError> {
+        ArrayVPTree::with_builder(&VPTreeBuilder::new(), data, norm)
+    }
+
+    /// Build a tree over the rows of `data` with the options of
+    /// `builder`, measuring distances with `norm`.
+    ///
+    /// Fails as `new` does.
+    pub fn with_builder(builder: &VPTreeBuilder<f64, Row<'a>>, data: ArrayView2<'a, f64>, norm: Norm)
+                        -> Result<Self, BuildError> {
+        let dim = data.ncols();
+        let rows = (0..data.nrows()).map(|index| data.index_axis_move(Axis(0), index).into());
+        ArrayVPTree::from_rows(builder, rows, norm, dim)
+    }
+
+    /// Build a tree over `rows`, each `dim` long, as `with_builder`
+    /// does.
+    fn from_rows<I>(builder: &VPTreeBuilder<f64, Row<'a>>, rows: I, norm: Norm, dim: usize) -> Result<Self, BuildError>
+        where I: Iterator<Item = CowArray<'a, f64, Ix1>> {
+        if let Norm::Lp(p) = norm {
+            if !(p >= 1.0 && p.is_finite()) {
+                return Err(BuildError::InvalidParameter("Lp norms need a finite p of at least 1"));
+            }
+        }
+        let rows: Vec<Row<'a>> = rows.enumerate().map(|(index, data)| Row { index, data, norm }).collect();
+        let tree = builder.try_build_allowing_empty(rows)?;
+        Ok(ArrayVPTree { tree, norm, dim })
+    }
+
+    /// Return the underlying tree.
+    pub fn tree(&self) -> &VPTree<f64, Row<'a>> {
+        &self.tree
+    }
+
+    /// Return the number of columns of the matrix.
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    /// Return the number of rows in the tree.
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Return true if the tree holds no rows.
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// Wrap `query` as a row, to query the tree with.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `query` doesn't have one element per column.
+    fn query(&self, query: ArrayView1<f64>) -> Row<'a> {
+        assert_eq!(query.len(), self.dim, "query length must match the number of columns");
+        Row { index: usize::MAX, data: query.to_owned().into(), norm: self.norm }
+    }
+
+    /// Return the index of the row closest to `query`.
+    ///
+    /// Returns `None` if the tree is empty.
+    pub fn nearest_neighbor(&self, query: ArrayView1<f64>) -> Option<usize> {
+        self.tree.nearest_neighbor(&self.query(query)).map(Row::index)
+    }
+
+    /// Return the indices of the `k` rows closest to `query`.
+    ///
+    /// Otherwise behaves like `VPTree::nearest_neighbors`.
+    pub fn nearest_neighbors(&self, query: ArrayView1<f64>, k: usize, sorted: bool) -> Vec<usize> {
+        self.tree.nearest_neighbors(&self.query(query), k, sorted).into_iter().map(Row::index).collect()
+    }
+
+    /// Return the indices of all rows within a given radius of
+    /// `query`.
+    ///
+    /// Otherwise behaves like `VPTree::within_radius`.
+    pub fn within_radius(&self, query: ArrayView1<f64>, radius: f64, sorted: bool) -> Vec<usize> {
+        self.tree.within_radius(&self.query(query), radius, sorted).into_iter().map(Row::index).collect()
+    }
+}
+
+impl ArrayVPTree<'static> {
+    /// Build a tree over the rows of an owned matrix with the options
+    /// of `builder`, copying them so that the tree borrows nothing.
+    ///
+    /// Fails as `new` does.
+    pub fn from_array(builder: &VPTreeBuilder<f64, Row<'static>>, data: Array2<f64>, norm: Norm)
+                      -> Result<Self, BuildError> {
+        let dim = data.ncols();
+        ArrayVPTree::from_rows(builder, data.outer_iter().map(|row| row.to_owned().into()), norm, dim)
+    }
+}
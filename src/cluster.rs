@@ -0,0 +1,435 @@
+//! Clustering of the items of a tree.
+//!
+//! Items are identified by their position in the vector the tree was
+//! built from, and each routine returns one entry per item, in that
+//! order.
+//!
+//! ```rust
+//! use vptree::{MetricItem, VPTree};
+//! use vptree::cluster::{dbscan, ClusterLabel};
+//!
+//! struct Point(f32);
+//!
+//! impl MetricItem<f32> for Point {
+//!     fn distance(&self, q: &Self) -> f32 {
+//!         (self.0 - q.0).abs()
+//!     }
+//! }
+//!
+//! let points = vec![Point(0.0), Point(0.5), Point(1.0), Point(5.0), Point(10.0), Point(10.5)];
+//! let tree = VPTree::new(points);
+//! let labels = dbscan(&tree, 0.75, 2);
+//! assert_eq!(labels, vec![
+//!     ClusterLabel::Cluster(0), ClusterLabel::Cluster(0), ClusterLabel::Cluster(0),
+//!     ClusterLabel::Noise,
+//!     ClusterLabel::Cluster(1), ClusterLabel::Cluster(1),
+//! ]);
+//! ```
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+use rand::Rng;
+
+use distance::Distance;
+use vptree::{MetricItem, VPTree};
+
+/// The cluster an item belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ClusterLabel {
+    /// The item belongs to no cluster.
+    Noise,
+    /// The item belongs to the cluster with this number. Clusters are
+    /// numbered from 0, in the order of the first item of each.
+    Cluster(usize),
+}
+
+impl ClusterLabel {
+    /// Return the number of the item's cluster, or `None` for noise.
+    pub fn cluster(&self) -> Option<usize> {
+        match *self {
+            ClusterLabel::Noise => None,
+            ClusterLabel::Cluster(c) => Some(c),
+        }
+    }
+
+    /// Return true if the item belongs to no cluster.
+    pub fn is_noise(&self) -> bool {
+        *self == ClusterLabel::Noise
+    }
+}
+
+/// Return the items within `eps` of each item, with their distances,
+/// as found by a single self-join of the tree.
+fn neighborhoods<F: Distance, T: MetricItem<F>>(tree: &VPTree<F, T>, eps: F) -> Vec<Vec<(usize, F)>> {
+    let mut neighbors = vec![Vec::new(); tree.len()];
+    for (i, j, d) in tree.pairs_within(eps) {
+        neighbors[i].push((j, d));
+        neighbors[j].push((i, d));
+    }
+    neighbors
+}
+
+/// Cluster the items of `tree` with DBSCAN.
+///
+/// An item is a core item if at least `min_pts` items, counting
+/// itself, lie within `eps` of it. Clusters are the sets of core items
+/// connected through neighbors within `eps`, along with the other
+/// items within `eps` of any of them. An item within `eps` of core
+/// items from several clusters joins the lowest numbered one. Items
+/// near no core item are noise.
+///
+/// As with `VPTree::within_radius`, items exactly `eps` apart are not
+/// neighbors. In a tree that collapses duplicates, each item counts
+/// for as many input items as it stands for.
+///
+/// The neighborhoods come from a single `pairs_within` self-join, so
+/// memory grows with the number of pairs within `eps`.
+pub fn dbscan<F: Distance, T: MetricItem<F>>(tree: &VPTree<F, T>, eps: F, min_pts: usize) -> Vec<ClusterLabel> {
+    let neighbors = neighborhoods(tree, eps);
+    let counts = tree.item_counts();
+    let core: Vec<bool> = neighbors.iter().zip(&counts).map(|(ns, &c)| {
+        c + ns.iter().map(|&(j, _)| counts[j]).sum::<usize>() >= min_pts
+    }).collect();
+
+    let mut labels = vec![ClusterLabel::Noise; neighbors.len()];
+    let mut next = 0;
+    let mut stack = Vec::new();
+    for i in 0..neighbors.len() {
+        if !labels[i].is_noise() || !core[i] {
+            continue;
+        }
+
+        // Grow a new cluster from `i`, only expanding from core items.
+        let label = ClusterLabel::Cluster(next);
+        next += 1;
+        labels[i] = label;
+        stack.push(i);
+        while let Some(p) = stack.pop() {
+            for &(q, _) in &neighbors[p] {
+                if labels[q].is_noise() {
+                    labels[q] = label;
+                    if core[q] {
+                        stack.push(q);
+                    }
+                }
+            }
+        }
+    }
+    labels
+}
+
+/// An OPTICS ordering of the items of a tree, as returned by `optics`.
+///
+/// `reachability` and `core_distances` have one entry per item, by
+/// position in the vector the tree was built from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Optics<F> {
+    /// Positions of the items, in the order OPTICS visited them.
+    pub ordering: Vec<usize>,
+    /// Reachability distance of each item from the items visited
+    /// before it, or `None` if it is not within `eps` of any earlier
+    /// core item.
+    pub reachability: Vec<Option<F>>,
+    /// Distance from each item to its `min_pts`th nearest item,
+    /// counting itself, or `None` if fewer than `min_pts` items lie
+    /// within `eps` of it.
+    pub core_distances: Vec<Option<F>>,
+}
+
+impl<F: Distance> Optics<F> {
+    /// Return the reachability distances in visiting order.
+    ///
+    /// Plotted as bars, valleys are clusters, and deeper valleys are
+    /// denser clusters.
+    pub fn reachability_plot(&self) -> Vec<Option<F>> {
+        self.ordering.iter().map(|&i| self.reachability[i]).collect()
+    }
+
+    /// Extract the clusters DBSCAN would find with radius `eps`, for
+    /// any `eps` no larger than the one the ordering was computed
+    /// with.
+    ///
+    /// Core items are clustered as DBSCAN would, but an item that is
+    /// not a core item may be labelled noise here where DBSCAN puts it
+    /// in a cluster, if it was visited before any core item near it.
+    pub fn extract_dbscan(&self, eps: F) -> Vec<ClusterLabel> {
+        let mut labels = vec![ClusterLabel::Noise; self.reachability.len()];
+        let mut current = None;
+        let mut next = 0;
+        for &i in &self.ordering {
+            if self.reachability[i].is_some_and(|r| r < eps) {
+                if let Some(c) = current {
+                    labels[i] = ClusterLabel::Cluster(c);
+                }
+            } else if self.core_distances[i].is_some_and(|d| d < eps) {
+                current = Some(next);
+                labels[i] = ClusterLabel::Cluster(next);
+                next += 1;
+            } else {
+                current = None;
+            }
+        }
+        labels
+    }
+}
+
+/// An item waiting to be visited by OPTICS, ordered so that the
+/// smallest reachability distance comes first out of a `BinaryHeap`.
+struct Seed<F> {
+    reachability: F,
+    index: usize,
+}
+
+impl<F: Distance> PartialEq for Seed<F> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<F: Distance> Eq for Seed<F> {}
+
+impl<F: Distance> PartialOrd for Seed<F> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<F: Distance> Ord for Seed<F> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Break ties by position, so that the ordering is
+        // deterministic.
+        other.reachability.partial_cmp(&self.reachability).expect("distances must not be NaN")
+            .then(other.index.cmp(&self.index))
+    }
+}
+
+/// Order the items of `tree` with OPTICS, for neighborhoods of radius
+/// `eps` and at least `min_pts` items.
+///
+/// Unlike `dbscan`, the result describes the clusters for every radius
+/// up to `eps` at once, which suits data whose clusters have different
+/// densities. Items are visited from the first unvisited one in input
+/// order, and then always the unvisited item with the smallest
+/// reachability distance.
+///
+/// Neighborhoods are as in `dbscan`: items exactly `eps` apart are not
+/// neighbors, and collapsed duplicates count for as many input items
+/// as they stand for.
+pub fn optics<F: Distance, T: MetricItem<F>>(tree: &VPTree<F, T>, eps: F, min_pts: usize) -> Optics<F> {
+    let mut neighbors = neighborhoods(tree, eps);
+    let counts = tree.item_counts();
+    let core_distances: Vec<Option<F>> = neighbors.iter_mut().enumerate().map(|(i, ns)| {
+        ns.sort_by(|a, b| a.1.partial_cmp(&b.1).expect("distances must not be NaN"));
+        let mut seen = counts[i];
+        if seen >= min_pts {
+            return Some(F::zero());
+        }
+        ns.iter().find(|&&(j, _)| {
+            seen += counts[j];
+            seen >= min_pts
+        }).map(|&(_, d)| d)
+    }).collect();
+
+    let n = neighbors.len();
+    let mut result = Optics { ordering: Vec::with_capacity(n), reachability: vec![None; n], core_distances };
+    let mut visited = vec![false; n];
+    let mut seeds = BinaryHeap::new();
+    for start in 0..n {
+        if visited[start] {
+            continue;
+        }
+        seeds.push(Seed { reachability: F::zero(), index: start });
+        while let Some(Seed { index: p, .. }) = seeds.pop() {
+            // Items are pushed again whenever their reachability
+            // improves, so skip the stale entries.
+            if visited[p] {
+                continue;
+            }
+            visited[p] = true;
+            result.ordering.push(p);
+
+            if let Some(core) = result.core_distances[p] {
+                for &(q, d) in &neighbors[p] {
+                    let r = core.larger(d);
+                    if !visited[q] && result.reachability[q].is_none_or(|old| r < old) {
+                        result.reachability[q] = Some(r);
+                        seeds.push(Seed { reachability: r, index: q });
+                    }
+                }
+            }
+        }
+    }
+    result
+}
+
+/// Cluster the items of `tree` by single linkage into `clusters`
+/// clusters, or one per item if there are fewer items.
+///
+/// Single linkage starts from every item in its own cluster, and
+/// repeatedly merges the two closest clusters, where the distance
+/// between clusters is that of their closest items. This amounts to
+/// cutting the longest edges of `VPTree::minimum_spanning_tree`.
+///
+/// # Panics
+///
+/// Panics if `clusters` is 0.
+pub fn single_linkage<F: Distance, T: MetricItem<F>>(tree: &VPTree<F, T>, clusters: usize) -> Vec<ClusterLabel> {
+    assert!(clusters > 0, "single linkage needs at least one cluster");
+    let mut edges = tree.minimum_spanning_tree();
+    edges.truncate(tree.len().saturating_sub(clusters));
+
+    let mut neighbors = vec![Vec::new(); tree.len()];
+    for (i, j, _) in edges {
+        neighbors[i].push(j);
+        neighbors[j].push(i);
+    }
+
+    let mut labels = vec![ClusterLabel::Noise; neighbors.len()];
+    let mut next = 0;
+    let mut stack = Vec::new();
+    for i in 0..neighbors.len() {
+        if !labels[i].is_noise() {
+            continue;
+        }
+        let label = ClusterLabel::Cluster(next);
+        next += 1;
+        labels[i] = label;
+        stack.push(i);
+        while let Some(p) = stack.pop() {
+            for &q in &neighbors[p] {
+                if labels[q].is_noise() {
+                    labels[q] = label;
+                    stack.push(q);
+                }
+            }
+        }
+    }
+    labels
+}
+
+/// A borrowed item, so that a tree of cluster centers needs no clones.
+struct Borrowed<'a, T: 'a>(&'a T);
+
+impl<'a, F: Distance, T: MetricItem<F>> MetricItem<F> for Borrowed<'a, T> {
+    fn distance(&self, b: &Self) -> F {
+        self.0.distance(b.0)
+    }
+}
+
+/// A clustering of the items of a tree around medoids, as returned by
+/// `k_medoids`.
+///
+/// Items are identified by their position in the vector the tree was
+/// built from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KMedoids<F> {
+    /// Positions of the medoids, the items at the centers of the
+    /// clusters.
+    pub medoids: Vec<usize>,
+    /// For each item, the cluster it belongs to, as a position in
+    /// `medoids`.
+    pub assignments: Vec<usize>,
+    /// Distance from each item to the medoid of its cluster.
+    pub distances: Vec<F>,
+    /// Number of rounds of medoid updates run.
+    pub iterations: usize,
+}
+
+impl<F: Distance> KMedoids<F> {
+    /// Return the sum of the distances from the items to their medoids.
+    pub fn cost(&self) -> f64 {
+        self.distances.iter().map(|d| d.to_f64()).sum()
+    }
+
+    /// Return the cluster of each item, as labels.
+    pub fn labels(&self) -> Vec<ClusterLabel> {
+        self.assignments.iter().map(|&c| ClusterLabel::Cluster(c)).collect()
+    }
+}
+
+/// Cluster the items of `tree` around `k` medoids, with up to `iters`
+/// rounds of updates, from medoids drawn at random from `rng`.
+///
+/// Each round assigns every item to its nearest medoid, by a search of
+/// a small tree built over the medoids, and then moves each medoid to
+/// the item of its cluster with the smallest total distance to the
+/// rest. Rounds stop early once no medoid moves. Unlike k-means, this
+/// needs nothing but the metric, and the centers are always items.
+///
+/// The distances between items of the same cluster are cached across
+/// rounds, so a round in which few items change clusters costs few new
+/// distance evaluations. The cache holds up to the sum of the squared
+/// cluster sizes, which is at most quadratic in the number of items.
+/// In a tree that collapses duplicates, each item weighs as much as
+/// the input items it stands for.
+///
+/// There are `k` clusters, or one per item if the tree holds fewer
+/// than `k` items.
+///
+/// # Panics
+///
+/// Panics if `k` is 0.
+pub fn k_medoids<F, T, R>(tree: &VPTree<F, T>, k: usize, iters: usize, rng: &mut R) -> KMedoids<F>
+    where F: Distance, T: MetricItem<F>, R: Rng {
+    assert!(k > 0, "k-medoids needs at least one cluster");
+    let items = tree.items_by_position();
+    let counts = tree.item_counts();
+    let mut medoids: Vec<usize> = (0..items.len()).collect();
+    rng.shuffle(&mut medoids);
+    medoids.truncate(k);
+    let mut cache = HashMap::new();
+    let mut distance = |i: usize, j: usize| -> F {
+        if i == j {
+            return F::zero();
+        }
+        *cache.entry((i.min(j), i.max(j))).or_insert_with(|| items[i].distance(items[j]))
+    };
+
+    let mut result = KMedoids { medoids, assignments: Vec::new(), distances: Vec::new(), iterations: 0 };
+    loop {
+        let centers = VPTree::new_with_rng(result.medoids.iter().map(|&m| Borrowed(items[m])).collect(), rng);
+        let (assignments, distances) = items.iter().map(|&item| {
+            centers.nearest_positions(&Borrowed(item), 1).pop().expect("there is at least one medoid")
+        }).unzip();
+        result.assignments = assignments;
+        result.distances = distances;
+        if result.iterations == iters {
+            break;
+        }
+
+        let mut members = vec![Vec::new(); result.medoids.len()];
+        for (i, &c) in result.assignments.iter().enumerate() {
+            members[c].push(i);
+        }
+        let mut moved = false;
+        for (medoid, members) in result.medoids.iter_mut().zip(&members) {
+            let mut cost_from = |c: usize, limit: Option<f64>| {
+                let mut total = 0.0;
+                for &j in members {
+                    total += counts[j] as f64 * distance(c, j).to_f64();
+                    // Stop once a candidate can't beat the best so far.
+                    if limit.is_some_and(|l| total >= l) {
+                        return None;
+                    }
+                }
+                Some(total)
+            };
+            let mut best = cost_from(*medoid, None);
+            for &c in members {
+                if c != *medoid {
+                    if let Some(cost) = cost_from(c, best) {
+                        best = Some(cost);
+                        *medoid = c;
+                        moved = true;
+                    }
+                }
+            }
+        }
+        result.iterations += 1;
+        if !moved {
+            break;
+        }
+    }
+    result
+}
@@ -0,0 +1,236 @@
+//! A tree shared between threads, with concurrent reads and
+//! serialized writes.
+//!
+//! Readers work on immutable snapshots of the tree, and writers
+//! modify a private copy that replaces the current tree when they are
+//! done. A rebuild after a batch of inserts therefore happens on the
+//! writer's copy, while readers keep querying the old tree, and can
+//! be moved onto a background thread entirely.
+//!
+//! Rather than copying the current tree for every write, writers keep
+//! the tree it replaced, and catch that up with the inserts it missed.
+
+use std::mem;
+use std::sync::{Arc, Mutex, MutexGuard, RwLock};
+use std::thread::{self, JoinHandle};
+use distance::Distance;
+
+use vptree::{MetricItem, VPTree};
+
+/// A `VPTree` that many threads can query while others modify it.
+///
+/// Queries take a snapshot of the current tree, which costs no more
+/// than cloning an `Arc`, and are never blocked by writers for longer
+/// than it takes to swap in a new tree. Writers are serialized.
+///
+/// Two copies of the tree are kept, so the items take twice the
+/// memory. An insert goes into the copy that is not current, after
+/// replaying the inserts that copy missed, so it costs about as much
+/// as two calls to `VPTree::insert`. The whole tree is copied instead,
+/// in time linear in its size, when a reader still holds a snapshot of
+/// the other copy, and on the first write after an `update` or
+/// `retain`, whose changes cannot be replayed. Batches of changes
+/// other than inserts should therefore go through a single `update`.
+///
+/// ```rust
+/// use std::sync::Arc;
+/// use std::thread;
+/// use vptree::{ConcurrentVPTree, MetricItem};
+///
+/// #[derive(Clone)]
+/// struct Point(f32);
+///
+/// impl MetricItem<f32> for Point {
+///     fn distance(&self, q: &Self) -> f32 {
+///         (self.0 - q.0).abs()
+///     }
+/// }
+///
+/// let index = Arc::new(ConcurrentVPTree::new((0..100).map(|x| Point(x as f32)).collect()));
+/// let writer = {
+///     let index = index.clone();
+///     thread::spawn(move || index.update(|tree| tree.extend((100..200).map(|x| Point(x as f32)))))
+/// };
+/// let n = index.read(|tree| tree.nearest_neighbors(&Point(50.2), 3, true).len());
+/// assert_eq!(n, 3);
+///
+/// writer.join().unwrap();
+/// assert_eq!(index.len(), 200);
+/// ```
+pub struct ConcurrentVPTree<F: Distance, T: MetricItem<F>> {
+    current: RwLock<Arc<VPTree<F, T>>>,
+    writer: Mutex<Spare<F, T>>,
+}
+
+/// The tree most recently replaced, which the next write goes to if
+/// no reader still holds it.
+struct Spare<F: Distance, T: MetricItem<F>> {
+    tree: Option<Arc<VPTree<F, T>>>,
+    /// Items inserted into the current tree but not into `tree`.
+    missing: Vec<T>,
+    /// Items inserted since each running background rebuild took its
+    /// snapshot, by rebuild, or `None` once a write that cannot be
+    /// replayed was made.
+    journals: Vec<(u64, Option<Vec<T>>)>,
+    next_journal: u64,
+}
+
+impl<F: Distance, T: MetricItem<F> + Clone> Spare<F, T> {
+    /// Return a tree holding the same items as `current`, for a writer
+    /// to modify: the spare tree caught up, if it is free, and
+    /// otherwise a copy of `current`.
+    fn take(&mut self, current: &VPTree<F, T>) -> VPTree<F, T> {
+        let missing = mem::take(&mut self.missing);
+        match self.tree.take().map(Arc::try_unwrap) {
+            Some(Ok(mut tree)) => {
+                tree.extend(missing);
+                tree
+            },
+            _ => current.clone()
+        }
+    }
+}
+
+impl<F: Distance, T: MetricItem<F> + Clone> ConcurrentVPTree<F, T> {
+    /// Construct a shared tree from a set of elements with the
+    /// default options.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as `VPTree::new`.
+    pub fn new(items: Vec<T>) -> Self {
+        ConcurrentVPTree::from_tree(VPTree::new(items))
+    }
+
+    /// Share an existing tree.
+    pub fn from_tree(tree: VPTree<F, T>) -> Self {
+        ConcurrentVPTree {
+            current: RwLock::new(Arc::new(tree)),
+            writer: Mutex::new(Spare { tree: None, missing: Vec::new(), journals: Vec::new(), next_journal: 0 }),
+        }
+    }
+
+    /// Return the current tree.
+    ///
+    /// The snapshot is unaffected by later writes, so a series of
+    /// queries against it sees a consistent set of items.
+    pub fn snapshot(&self) -> Arc<VPTree<F, T>> {
+        self.current.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Call `f` with a snapshot of the current tree.
+    pub fn read<R, Q: FnOnce(&VPTree<F, T>) -> R>(&self, f: Q) -> R {
+        f(&self.snapshot())
+    }
+
+    /// Return the number of items in the current tree.
+    pub fn len(&self) -> usize {
+        self.snapshot().len()
+    }
+
+    /// Return true if the current tree holds no items.
+    pub fn is_empty(&self) -> bool {
+        self.snapshot().is_empty()
+    }
+
+    /// Apply `f` to a copy of the current tree, and then make the
+    /// copy the current tree.
+    ///
+    /// Writes are serialized, so `f` sees every earlier write. Readers
+    /// keep using the previous tree until `f` returns, even if it
+    /// rebuilds the tree.
+    pub fn update<R, W: FnOnce(&mut VPTree<F, T>) -> R>(&self, f: W) -> R {
+        let mut spare = self.lock_writer();
+        let mut tree = spare.take(&self.snapshot());
+        let result = f(&mut tree);
+        // The previous tree can't be caught up with `f`, so it is
+        // dropped, and the next write copies the new tree.
+        self.replace(tree);
+        for journal in &mut spare.journals {
+            journal.1 = None;
+        }
+        result
+    }
+
+    /// Insert `item`, as with `VPTree::insert`, rebuilding the tree if
+    /// it has become badly unbalanced.
+    pub fn insert(&self, item: T) {
+        let mut spare = self.lock_writer();
+        let mut tree = spare.take(&self.snapshot());
+        tree.extend(Some(item.clone()));
+        spare.tree = Some(self.replace(tree));
+        for inserted in spare.journals.iter_mut().filter_map(|j| j.1.as_mut()) {
+            inserted.push(item.clone());
+        }
+        spare.missing.push(item);
+    }
+
+    /// Remove every item for which `f` returns false, as with
+    /// `VPTree::retain`.
+    pub fn retain<P: Fn(&T) -> bool>(&self, f: P) {
+        self.update(|tree| tree.retain(f));
+    }
+
+    fn lock_writer(&self) -> MutexGuard<'_, Spare<F, T>> {
+        self.writer.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Make `tree` the current tree, returning the tree it replaced.
+    fn replace(&self, tree: VPTree<F, T>) -> Arc<VPTree<F, T>> {
+        mem::replace(&mut *self.current.write().unwrap_or_else(|e| e.into_inner()), Arc::new(tree))
+    }
+
+    /// Rebuild the tree on a new thread, swapping in the balanced tree
+    /// once it is complete.
+    ///
+    /// The rebuild works on a snapshot, so readers keep querying the
+    /// previous tree and writers carry on throughout. Items inserted
+    /// in the meantime are then inserted into the rebuilt tree before
+    /// it replaces the current one. The changes of an `update` or
+    /// `retain` made in the meantime cannot be replayed, so if there
+    /// were any, the current tree is rebuilt again instead, holding up
+    /// writers until it is done.
+    pub fn rebuild_in_background(self: &Arc<Self>) -> JoinHandle<()>
+        where F: Send + Sync + 'static, T: Send + Sync + 'static {
+        let this = self.clone();
+        thread::spawn(move || {
+            let (id, snapshot) = {
+                let mut spare = this.lock_writer();
+                let id = spare.next_journal;
+                spare.next_journal += 1;
+                spare.journals.push((id, Some(Vec::new())));
+                (id, this.snapshot())
+            };
+            let mut tree = VPTree::clone(&snapshot);
+            drop(snapshot);
+            tree.rebuild();
+
+            let mut spare = this.lock_writer();
+            let pos = spare.journals.iter().position(|j| j.0 == id).expect("rebuilds keep their journal until done");
+            match spare.journals.swap_remove(pos).1 {
+                Some(inserted) => tree.extend(inserted),
+                None => {
+                    tree = spare.take(&this.snapshot());
+                    tree.rebuild();
+                }
+            }
+            // The previous tree is unbalanced, so let the next write
+            // copy the rebuilt one instead of catching it up.
+            this.replace(tree);
+            spare.tree = None;
+            spare.missing.clear();
+        })
+    }
+
+    /// Rebuild the tree on a new thread, as with
+    /// `rebuild_in_background`, if it is likely to be badly
+    /// unbalanced.
+    pub fn rebuild_in_background_if_needed(self: &Arc<Self>) -> Option<JoinHandle<()>>
+        where F: Send + Sync + 'static, T: Send + Sync + 'static {
+        if self.snapshot().needs_rebuild() {
+            Some(self.rebuild_in_background())
+        } else {
+            None
+        }
+    }
+}
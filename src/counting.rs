@@ -0,0 +1,78 @@
+//! Instrumentation for counting metric evaluations.
+//!
+//! The cost of a query against a metric tree is dominated by the
+//! number of times the metric is evaluated. Wrapping items in a
+//! `CountingMetric` makes that number observable, which is useful
+//! for comparing tree configurations.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use distance::Distance;
+
+use vptree::MetricItem;
+
+/// A shared counter of distance evaluations.
+///
+/// Clones of a counter share the same count.
+#[derive(Debug, Clone, Default)]
+pub struct DistanceCounter(Arc<AtomicUsize>);
+
+impl DistanceCounter {
+    /// Create a new counter, starting at zero.
+    pub fn new() -> Self {
+        DistanceCounter::default()
+    }
+
+    /// Wrap `item` so that its distance evaluations are counted by
+    /// this counter.
+    pub fn wrap<T>(&self, item: T) -> CountingMetric<T> {
+        CountingMetric { item, counter: self.clone() }
+    }
+
+    /// Return the number of distance evaluations so far.
+    pub fn count(&self) -> usize {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Reset the count to zero, returning the previous count.
+    pub fn reset(&self) -> usize {
+        self.0.swap(0, Ordering::Relaxed)
+    }
+}
+
+/// A `MetricItem` wrapper that counts calls to `distance`.
+///
+/// Each call on a pair of wrapped items increments the counter of the
+/// item `distance` is called on. Items are usually wrapped with the
+/// same `DistanceCounter`, including query items, so that the counter
+/// sees every evaluation.
+#[derive(Debug, Clone)]
+pub struct CountingMetric<T> {
+    item: T,
+    counter: DistanceCounter,
+}
+
+impl<T> CountingMetric<T> {
+    /// Return a reference to the wrapped item.
+    pub fn item(&self) -> &T {
+        &self.item
+    }
+
+    /// Unwrap the item.
+    pub fn into_inner(self) -> T {
+        self.item
+    }
+}
+
+impl<T: PartialEq> PartialEq for CountingMetric<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.item == other.item
+    }
+}
+
+impl<F: Distance, T: MetricItem<F>> MetricItem<F> for CountingMetric<T> {
+    fn distance(&self, b: &Self) -> F {
+        self.counter.0.fetch_add(1, Ordering::Relaxed);
+        self.item.distance(&b.item)
+    }
+}
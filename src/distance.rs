@@ -0,0 +1,170 @@
+//! Scalar types that distances can be measured in.
+//!
+//! Trees only need to compare, add and subtract distances, so besides
+//! the floating point types, integer types can be used directly for
+//! discrete metrics such as Hamming or edit distance.
+//!
+//! With the `ordered-float` feature, `OrderedFloat` and `NotNan`
+//! floats are distances too. Every comparison between them is
+//! defined, so queries over them cannot fail the way NaN floats do.
+//! `OrderedFloat` orders NaN after every other value, so a metric
+//! returning NaN puts items infinitely far away instead of failing.
+//! Bounds that come out as NaN from arithmetic on infinite distances
+//! are widened to the infinity on the safe side, so pruning stays
+//! exact. Plain floats pay nothing for any of this.
+
+use num::{Float, Zero};
+#[cfg(feature = "ordered-float")]
+use ordered_float::{NotNan, OrderedFloat};
+
+/// A scalar distance between two items.
+///
+/// Distances are compared with `PartialOrd`. A distance that is not
+/// comparable with zero, like a floating point NaN, is reported as
+/// invalid when building, inserting into or querying a tree.
+///
+/// Pruning subtracts distances from each other, which for unsigned
+/// integers could go below zero, so the arithmetic the tree relies on
+/// saturates instead of overflowing.
+pub trait Distance: Copy + PartialOrd + Zero {
+    /// Return a distance at least as large as any other.
+    fn infinity() -> Self;
+
+    /// Return the larger of two distances.
+    fn larger(self, other: Self) -> Self {
+        if other > self { other } else { self }
+    }
+
+    /// Return the smaller of two distances.
+    fn smaller(self, other: Self) -> Self {
+        if other < self { other } else { self }
+    }
+
+    /// Return `self + other`, saturating at the largest value of the
+    /// type.
+    fn saturating_add(self, other: Self) -> Self;
+
+    /// Return `self - other`, saturating at the smallest value of the
+    /// type.
+    fn saturating_sub(self, other: Self) -> Self;
+
+    /// Return the absolute difference between two distances.
+    fn abs_diff(self, other: Self) -> Self {
+        if self > other { self.saturating_sub(other) } else { other.saturating_sub(self) }
+    }
+
+    /// Convert the distance to an `f64`, for statistics over many
+    /// distances.
+    fn to_f64(self) -> f64;
+}
+
+macro_rules! float_distance {
+    ($($t:ty),*) => {$(
+        impl Distance for $t {
+            fn infinity() -> Self {
+                <$t as Float>::infinity()
+            }
+
+            fn larger(self, other: Self) -> Self {
+                <$t as Float>::max(self, other)
+            }
+
+            fn smaller(self, other: Self) -> Self {
+                <$t as Float>::min(self, other)
+            }
+
+            fn saturating_add(self, other: Self) -> Self {
+                self + other
+            }
+
+            fn saturating_sub(self, other: Self) -> Self {
+                self - other
+            }
+
+            fn abs_diff(self, other: Self) -> Self {
+                (self - other).abs()
+            }
+
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+        }
+    )*}
+}
+
+macro_rules! int_distance {
+    ($($t:ty),*) => {$(
+        impl Distance for $t {
+            fn infinity() -> Self {
+                <$t>::MAX
+            }
+
+            fn saturating_add(self, other: Self) -> Self {
+                <$t>::saturating_add(self, other)
+            }
+
+            fn saturating_sub(self, other: Self) -> Self {
+                <$t>::saturating_sub(self, other)
+            }
+
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+        }
+    )*}
+}
+
+float_distance!(f32, f64);
+int_distance!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+/// Return `x`, or `fallback` if `x` is NaN.
+#[cfg(feature = "ordered-float")]
+fn or_if_nan<F: Float>(x: F, fallback: F) -> F {
+    if x.is_nan() { fallback } else { x }
+}
+
+#[cfg(feature = "ordered-float")]
+macro_rules! ordered_distance {
+    ($($t:ty),*) => {$(
+        impl Distance for OrderedFloat<$t> {
+            fn infinity() -> Self {
+                OrderedFloat(<$t>::INFINITY)
+            }
+
+            fn saturating_add(self, other: Self) -> Self {
+                OrderedFloat(or_if_nan(self.0 + other.0, <$t>::INFINITY))
+            }
+
+            fn saturating_sub(self, other: Self) -> Self {
+                OrderedFloat(or_if_nan(self.0 - other.0, <$t>::NEG_INFINITY))
+            }
+
+            fn to_f64(self) -> f64 {
+                self.0 as f64
+            }
+        }
+
+        impl Distance for NotNan<$t> {
+            fn infinity() -> Self {
+                NotNan::new(<$t>::INFINITY).expect("infinity is not NaN")
+            }
+
+            fn saturating_add(self, other: Self) -> Self {
+                let sum = or_if_nan(self.into_inner() + other.into_inner(), <$t>::INFINITY);
+                NotNan::new(sum).expect("NaN sums were replaced")
+            }
+
+            fn saturating_sub(self, other: Self) -> Self {
+                let diff = or_if_nan(self.into_inner() - other.into_inner(), <$t>::NEG_INFINITY);
+                NotNan::new(diff).expect("NaN differences were replaced")
+            }
+
+            fn to_f64(self) -> f64 {
+                self.into_inner() as f64
+            }
+        }
+    )*}
+}
+
+#[cfg(feature = "ordered-float")]
+ordered_distance!(f32, f64);
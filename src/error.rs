@@ -0,0 +1,48 @@
+//! Errors reported while building or querying a tree.
+
+use std::error::Error;
+use std::fmt;
+
+/// An error encountered while constructing a `VPTree`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum BuildError {
+    /// No items were given to build the tree from.
+    Empty,
+    /// The metric returned a negative or NaN distance between two
+    /// items.
+    InvalidDistance,
+    /// A construction parameter was out of range.
+    InvalidParameter(&'static str),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BuildError::Empty => write!(f, "no items to build the tree from"),
+            BuildError::InvalidDistance => write!(f, "metric returned a negative or NaN distance"),
+            BuildError::InvalidParameter(msg) => write!(f, "invalid parameter: {}", msg),
+        }
+    }
+}
+
+impl Error for BuildError {}
+
+/// An error encountered while querying a `VPTree`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum QueryError {
+    /// The metric returned a negative or NaN distance between the
+    /// query and an item.
+    InvalidDistance,
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            QueryError::InvalidDistance => write!(f, "metric returned a negative or NaN distance to the query"),
+        }
+    }
+}
+
+impl Error for QueryError {}
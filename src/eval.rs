@@ -0,0 +1,116 @@
+//! Measuring the accuracy and cost of nearest neighbor queries.
+//!
+//! `evaluate` runs a set of queries against an index and against a
+//! `BruteForce` reference over the same items, and summarizes how
+//! closely the index's answers match and how many distances it
+//! evaluated to get them. Exact indices like `VPTree` always have a
+//! recall of 1, so the cost is what differs between their
+//! configurations.
+//!
+//! ```rust
+//! use vptree::{BruteForce, DistanceCounter, MetricItem, VPTree};
+//! use vptree::eval::evaluate;
+//!
+//! struct Point(f32);
+//!
+//! impl MetricItem<f32> for Point {
+//!     fn distance(&self, q: &Self) -> f32 {
+//!         (self.0 - q.0).abs()
+//!     }
+//! }
+//!
+//! let counter = DistanceCounter::new();
+//! let points = || (0..1000).map(|x| counter.wrap(Point(x as f32))).collect();
+//! let tree = VPTree::new(points());
+//! let reference = BruteForce::new(points());
+//! let queries: Vec<_> = (0..20).map(|x| counter.wrap(Point(x as f32 * 50.3))).collect();
+//!
+//! let result = evaluate(&tree, &reference, &queries, 5, Some(&counter));
+//! assert_eq!(result.recall, 1.0);
+//! assert!(result.mean_distance_evaluations.unwrap() < 1000.0);
+//! ```
+
+use counting::DistanceCounter;
+use distance::Distance;
+use index::{BruteForce, NearestNeighborIndex};
+use vptree::MetricItem;
+
+/// The accuracy and cost of the queries of an index, as returned by
+/// `evaluate`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Evaluation {
+    /// Number of queries run.
+    pub queries: usize,
+    /// Number of neighbors asked for by each query.
+    pub k: usize,
+    /// Mean fraction of the true `k` nearest neighbors that were
+    /// found. An item at the same distance as the furthest true
+    /// neighbor counts as one of them.
+    pub recall: f64,
+    /// Mean difference between the distance of the `i`th neighbor
+    /// found and that of the true `i`th nearest neighbor, over every
+    /// rank of every query.
+    pub mean_distance_error: f64,
+    /// Largest difference between the distance of a neighbor found and
+    /// that of the true neighbor of the same rank.
+    pub max_distance_error: f64,
+    /// Mean number of distance evaluations per query, if a counter was
+    /// given.
+    pub mean_distance_evaluations: Option<f64>,
+}
+
+/// Run the `k` nearest neighbor query for each of `queries` against
+/// `index` and `reference`, and compare the results.
+///
+/// `reference` must hold the same items as `index`. To count distance
+/// evaluations, wrap the items of both and the queries with
+/// `counter`; only the evaluations made by `index` are counted.
+///
+/// # Panics
+///
+/// Panics if the metric returns NaN.
+pub fn evaluate<F, T, I>(index: &I, reference: &BruteForce<T>, queries: &[T], k: usize,
+                         counter: Option<&DistanceCounter>) -> Evaluation
+    where F: Distance, T: MetricItem<F>, I: NearestNeighborIndex<F, T> {
+    let mut found_total = 0.0;
+    let (mut error_total, mut max_error, mut ranks) = (0.0, 0.0f64, 0);
+    let mut evaluations = 0;
+
+    for q in queries {
+        let expected: Vec<f64> = reference.nearest_neighbors(q, k, true).into_iter()
+            .map(|item| q.distance(item).to_f64())
+            .collect();
+
+        if let Some(counter) = counter {
+            counter.reset();
+        }
+        let found = index.nearest_neighbors(q, k, true);
+        if let Some(counter) = counter {
+            evaluations += counter.count();
+        }
+        let mut found: Vec<f64> = found.into_iter().map(|item| q.distance(item).to_f64()).collect();
+        found.sort_by(|a, b| a.partial_cmp(b).expect("distances must not be NaN"));
+
+        if let Some(&furthest) = expected.last() {
+            let hits = found.iter().filter(|&&d| d <= furthest).count().min(expected.len());
+            found_total += hits as f64 / expected.len() as f64;
+        } else {
+            found_total += 1.0;
+        }
+        for (d, e) in found.iter().zip(&expected) {
+            error_total += d - e;
+            max_error = max_error.max(d - e);
+            ranks += 1;
+        }
+    }
+
+    let n = queries.len();
+    Evaluation {
+        queries: n,
+        k,
+        recall: if n == 0 { 1.0 } else { found_total / n as f64 },
+        mean_distance_error: if ranks == 0 { 0.0 } else { error_total / ranks as f64 },
+        max_distance_error: max_error,
+        mean_distance_evaluations: counter.map(|_| if n == 0 { 0.0 } else { evaluations as f64 / n as f64 }),
+    }
+}
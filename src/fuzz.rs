@@ -0,0 +1,144 @@
+//! A ready-made harness for fuzzing trees.
+//!
+//! `FuzzInput` implements `Arbitrary`, decoding a point set, a few
+//! queries, and the options to build a tree with from raw fuzzer
+//! input. `fuzz_check` builds the tree and panics if any of its
+//! answers differs from a linear scan. Coordinates are drawn from a
+//! coarse grid, so that inputs are full of ties and duplicates.
+//!
+//! To fuzz the crate with `cargo fuzz`, enable the `arbitrary` feature
+//! in the fuzzing crate and add a target like the following:
+//!
+//! ```text
+//! #![no_main]
+//! use libfuzzer_sys::fuzz_target;
+//!
+//! fuzz_target!(|input: vptree::fuzz::FuzzInput| {
+//!     vptree::fuzz::fuzz_check(&input);
+//! });
+//! ```
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+use index::{assert_same_answers, BruteForce};
+use metrics::Euclidean;
+use vptree::{CompactVPTree, NodeLayout, VPTreeBuilder, VantageStrategy};
+
+/// Largest number of coordinates of each point.
+const MAX_DIM: usize = 4;
+
+/// Largest number of queries run against each tree.
+const MAX_QUERIES: usize = 4;
+
+/// A set of points in Euclidean space, the queries to run against a
+/// tree over them, and the options to build the tree with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FuzzInput {
+    /// The points to build the tree from, all of the same dimension.
+    pub points: Vec<Vec<f64>>,
+    /// The query points, of the same dimension as `points`.
+    pub queries: Vec<Vec<f64>>,
+    /// Number of nearest neighbors to find for each query.
+    pub k: usize,
+    /// Radius of the radius query for each query.
+    pub radius: f64,
+    /// Strategy for choosing vantage points.
+    pub strategy: VantageStrategy,
+    /// Maximum number of items in each leaf.
+    pub leaf_size: usize,
+    /// Sample size for estimating split distances, if any.
+    pub median_sample_size: Option<usize>,
+    /// Depth at which splitting stops, if any.
+    pub max_depth: Option<usize>,
+    /// Order of the nodes in memory.
+    pub layout: NodeLayout,
+    /// Seed for the random choices made during construction.
+    pub seed: u64,
+}
+
+/// Decode a coordinate, a multiple of 1/4 between -32 and 32.
+fn coordinate(u: &mut Unstructured) -> Result<f64> {
+    Ok(f64::from(i8::arbitrary(u)?) / 4.0)
+}
+
+fn point(u: &mut Unstructured, dim: usize) -> Result<Vec<f64>> {
+    (0..dim).map(|_| coordinate(u)).collect()
+}
+
+impl<'a> Arbitrary<'a> for FuzzInput {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let strategy = match u.int_in_range(0..=4)? {
+            0 => VantageStrategy::Random,
+            1 => VantageStrategy::FurthestFromRandom,
+            2 => VantageStrategy::MaxSpread,
+            3 => VantageStrategy::Sampled(u.int_in_range(1..=8)?),
+            _ => VantageStrategy::Yianilos {
+                candidates: u.int_in_range(1..=8)?,
+                sample_size: u.int_in_range(1..=8)?,
+            },
+        };
+        let layout = match u.int_in_range(0..=2)? {
+            0 => NodeLayout::PreOrder,
+            1 => NodeLayout::BreadthFirst,
+            _ => NodeLayout::VanEmdeBoas,
+        };
+        let leaf_size = u.int_in_range(1..=8)?;
+        let median_sample_size = if bool::arbitrary(u)? { Some(u.int_in_range(1..=16)?) } else { None };
+        let max_depth = if bool::arbitrary(u)? { Some(u.int_in_range(1..=8)?) } else { None };
+        let seed = u64::arbitrary(u)?;
+        let k = u.int_in_range(0..=16)?;
+        let radius = f64::from(u8::arbitrary(u)?) / 4.0;
+
+        let dim = u.int_in_range(1..=MAX_DIM)?;
+        let n_queries = u.int_in_range(1..=MAX_QUERIES)?;
+        let queries = (0..n_queries).map(|_| point(u, dim)).collect::<Result<_>>()?;
+        // The points take up the rest of the input.
+        let n_points = u.arbitrary_len::<i8>()? / dim;
+        let points = (0..n_points).map(|_| point(u, dim)).collect::<Result<_>>()?;
+
+        Ok(FuzzInput {
+            points, queries, k, radius, strategy, leaf_size, median_sample_size, max_depth, layout, seed
+        })
+    }
+}
+
+impl FuzzInput {
+    /// Return a builder with the options of this input.
+    pub fn builder(&self) -> VPTreeBuilder<f64, Euclidean<Vec<f64>>> {
+        let mut builder = VPTreeBuilder::new()
+            .vantage_strategy(self.strategy)
+            .leaf_size(self.leaf_size)
+            .layout(self.layout)
+            .seed(self.seed);
+        if let Some(sample_size) = self.median_sample_size {
+            builder = builder.median_sample_size(sample_size);
+        }
+        if let Some(max_depth) = self.max_depth {
+            builder = builder.max_depth(max_depth);
+        }
+        builder
+    }
+}
+
+/// Build a tree over the points of `input`, and check that it and its
+/// compact form answer every query as a linear scan does.
+///
+/// Inputs without points build an empty tree, which must answer every
+/// query with nothing.
+///
+/// # Panics
+///
+/// Panics if construction fails, or if any answer differs.
+pub fn fuzz_check(input: &FuzzInput) {
+    let points: Vec<_> = input.points.iter().cloned().map(Euclidean).collect();
+    let queries: Vec<_> = input.queries.iter().cloned().map(Euclidean).collect();
+    let tree = match input.builder().try_build_allowing_empty(points.clone()) {
+        Ok(tree) => tree,
+        Err(e) => panic!("failed to build a tree from {:?}: {}", input, e),
+    };
+    assert_eq!(tree.len(), points.len());
+
+    let reference = BruteForce::new(points);
+    assert_same_answers(&tree, &reference, &queries, input.k, input.radius);
+    assert_same_answers(&CompactVPTree::from(tree), &reference, &queries, input.k, input.radius);
+}
@@ -0,0 +1,203 @@
+//! A common interface to nearest neighbor indices.
+
+#[cfg(any(feature = "arbitrary", feature = "testing"))]
+use std::fmt::Debug;
+
+use distance::Distance;
+use vptree::{CompactVPTree, MetricItem, PersistentVPTree, VPTree};
+
+/// An index answering nearest neighbor and radius queries over items
+/// of a metric space.
+///
+/// Code generic over the index can switch between a `VPTree`, its
+/// variants, and the `BruteForce` baseline without other changes.
+///
+/// ```rust
+/// use vptree::{BruteForce, MetricItem, NearestNeighborIndex, VPTree};
+///
+/// struct Point(f32);
+///
+/// impl MetricItem<f32> for Point {
+///     fn distance(&self, q: &Self) -> f32 {
+///         (self.0 - q.0).abs()
+///     }
+/// }
+///
+/// fn closest<I: NearestNeighborIndex<f32, Point>>(xs: &[f32], x: f32) -> f32 {
+///     let index = I::build(xs.iter().map(|&x| Point(x)).collect());
+///     index.nearest_neighbor(&Point(x)).unwrap().0
+/// }
+///
+/// assert_eq!(closest::<VPTree<_, _>>(&[1.0, 5.0, 9.0], 6.0), 5.0);
+/// assert_eq!(closest::<BruteForce<_>>(&[1.0, 5.0, 9.0], 6.0), 5.0);
+/// ```
+pub trait NearestNeighborIndex<F: Distance, T: MetricItem<F>> {
+    /// Build an index over `items` with the default options.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the metric returns a negative or NaN distance.
+    fn build(items: Vec<T>) -> Self where Self: Sized;
+
+    /// Return the number of items in the index.
+    fn len(&self) -> usize;
+
+    /// Return true if the index holds no items.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Find the `k` items closest to `query`, sorted by distance if
+    /// `sorted` is true, as `VPTree::nearest_neighbors` does.
+    fn nearest_neighbors(&self, query: &T, k: usize, sorted: bool) -> Vec<&T>;
+
+    /// Return every item closer than `radius` to `query`, sorted by
+    /// distance if `sorted` is true, as `VPTree::within_radius` does.
+    fn within_radius(&self, query: &T, radius: F, sorted: bool) -> Vec<&T>;
+
+    /// Find the item closest to `query`.
+    ///
+    /// Returns `None` if the index is empty.
+    fn nearest_neighbor(&self, query: &T) -> Option<&T> {
+        self.nearest_neighbors(query, 1, false).pop()
+    }
+}
+
+/// An index that answers every query by measuring the distance to
+/// every item.
+///
+/// Queries cost a distance evaluation per item, but the results are
+/// trivially correct, which makes it the reference to check other
+/// indices against.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BruteForce<T> {
+    items: Vec<T>,
+}
+
+impl<T> BruteForce<T> {
+    /// Create an index over `items`.
+    pub fn new(items: Vec<T>) -> Self {
+        BruteForce { items }
+    }
+
+    /// Return the items, in the order they were given.
+    pub fn items(&self) -> &[T] {
+        &self.items
+    }
+
+    /// Return every item with its distance to `query`, closest first
+    /// if `sorted` is true.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the metric returns NaN and `sorted` is true.
+    fn distances<F: Distance>(&self, query: &T, sorted: bool) -> Vec<(F, &T)> where T: MetricItem<F> {
+        let mut ds: Vec<(F, &T)> = self.items.iter().map(|item| (query.distance(item), item)).collect();
+        if sorted {
+            ds.sort_by(|a, b| a.0.partial_cmp(&b.0).expect("distances must not be NaN"));
+        }
+        ds
+    }
+}
+
+impl<F: Distance, T: MetricItem<F>> NearestNeighborIndex<F, T> for BruteForce<T> {
+    fn build(items: Vec<T>) -> Self {
+        BruteForce::new(items)
+    }
+
+    fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    fn nearest_neighbors(&self, query: &T, k: usize, sorted: bool) -> Vec<&T> {
+        let mut ds = self.distances(query, false);
+        if k < ds.len() {
+            ds.select_nth_unstable_by(k, |a, b| a.0.partial_cmp(&b.0).expect("distances must not be NaN"));
+            ds.truncate(k);
+        }
+        if sorted {
+            ds.sort_by(|a, b| a.0.partial_cmp(&b.0).expect("distances must not be NaN"));
+        }
+        ds.into_iter().map(|(_, item)| item).collect()
+    }
+
+    fn within_radius(&self, query: &T, radius: F, sorted: bool) -> Vec<&T> {
+        self.distances(query, sorted).into_iter().filter(|&(d, _)| d < radius).map(|(_, item)| item).collect()
+    }
+}
+
+impl<F: Distance, T: MetricItem<F>> NearestNeighborIndex<F, T> for VPTree<F, T> {
+    fn build(items: Vec<T>) -> Self {
+        VPTree::new(items)
+    }
+
+    fn len(&self) -> usize {
+        VPTree::len(self)
+    }
+
+    fn nearest_neighbors(&self, query: &T, k: usize, sorted: bool) -> Vec<&T> {
+        VPTree::nearest_neighbors(self, query, k, sorted)
+    }
+
+    fn within_radius(&self, query: &T, radius: F, sorted: bool) -> Vec<&T> {
+        VPTree::within_radius(self, query, radius, sorted)
+    }
+}
+
+impl<F: Distance, T: MetricItem<F>> NearestNeighborIndex<F, T> for PersistentVPTree<F, T> {
+    fn build(items: Vec<T>) -> Self {
+        PersistentVPTree::new(items)
+    }
+
+    fn len(&self) -> usize {
+        PersistentVPTree::len(self)
+    }
+
+    fn nearest_neighbors(&self, query: &T, k: usize, sorted: bool) -> Vec<&T> {
+        PersistentVPTree::nearest_neighbors(self, query, k, sorted)
+    }
+
+    fn within_radius(&self, query: &T, radius: F, sorted: bool) -> Vec<&T> {
+        PersistentVPTree::within_radius(self, query, radius, sorted)
+    }
+}
+
+impl<T: MetricItem<f64>> NearestNeighborIndex<f64, T> for CompactVPTree<T> {
+    fn build(items: Vec<T>) -> Self {
+        CompactVPTree::new(items)
+    }
+
+    fn len(&self) -> usize {
+        CompactVPTree::len(self)
+    }
+
+    fn nearest_neighbors(&self, query: &T, k: usize, sorted: bool) -> Vec<&T> {
+        CompactVPTree::nearest_neighbors(self, query, k, sorted)
+    }
+
+    fn within_radius(&self, query: &T, radius: f64, sorted: bool) -> Vec<&T> {
+        CompactVPTree::within_radius(self, query, radius, sorted)
+    }
+}
+
+/// Assert that the `k` nearest neighbors and the items within `radius`
+/// of each of `queries` are at the same distances in `index` as in
+/// `reference`.
+#[cfg(any(feature = "arbitrary", feature = "testing"))]
+pub(crate) fn assert_same_answers<F, T, I>(index: &I, reference: &BruteForce<T>, queries: &[T], k: usize, radius: F)
+    where F: Distance + Debug, T: MetricItem<F>, I: NearestNeighborIndex<F, T> {
+    let distances = |q: &T, found: Vec<&T>| -> Vec<F> {
+        let mut ds: Vec<F> = found.into_iter().map(|item| q.distance(item)).collect();
+        ds.sort_by(|a, b| a.partial_cmp(b).expect("distances must not be NaN"));
+        ds
+    };
+
+    for (i, q) in queries.iter().enumerate() {
+        assert_eq!(distances(q, index.nearest_neighbors(q, k, true)),
+                   distances(q, reference.nearest_neighbors(q, k, true)),
+                   "nearest neighbors of query {} differ", i);
+        assert_eq!(distances(q, index.within_radius(q, radius, true)),
+                   distances(q, reference.within_radius(q, radius, true)),
+                   "items within the radius of query {} differ", i);
+    }
+}
@@ -12,13 +12,22 @@
 //! - f(x, z) <= f(x, y) + f(y, z)
 //!
 //! `VPTree`s in the `vptree-rs` crate support k-nearest neighbor and
-//! radius queries.
+//! radius queries. Distances may be floating point or integer valued.
 //!
 //! VP-trees work by recursively splitting the data set in two, based on
 //! how far each point is from a selected _vantage point_. When searching
 //! through the VP-tree, we use the distance from query point to a
 //! subtree's vantage point to potentially cull a subtree.
 //!
+//! # WebAssembly
+//!
+//! Trees can be built and searched on `wasm32-unknown-unknown`, which
+//! has no entropy source, threads or clock. Builders without a seed
+//! use a fixed one there, as described under `VPTreeBuilder::seed`,
+//! and `ShardedVPTree::new` builds its shards one after another.
+//! `TtlVPTree` and the `concurrent` feature rely on the clock and on
+//! threads, and are not usable there.
+//!
 //! # Examples
 //!
 //! ```rust
@@ -31,7 +40,7 @@
 //! }
 //! impl Point {
 //!     fn new(x: f32, y: f32) -> Self {
-//!         Point { x: x, y: y }
+//!         Point { x, y }
 //!     }
 //! }
 //!
@@ -51,11 +60,10 @@
 //!     }).collect()
 //! }
 //!
-//! #[test]
-//! fn lattice_vpn() {
+//! fn main() {
 //!     let points: Vec<Point> = lattice_points(10);
 //!
-//!     let tree = VPTree::new(points).unwrap();
+//!     let tree = VPTree::new(points);
 //!
 //!     let ps = tree.nearest_neighbors(&Point::new(4.46, 4.4), 4, true);
 //!     assert_eq!(ps.len(), 4);
@@ -76,8 +84,50 @@
 
 extern crate num;
 extern crate rand;
-extern crate order_stat;
+#[cfg(feature = "ndarray")]
+extern crate ndarray;
+#[cfg(feature = "nalgebra")]
+extern crate nalgebra;
+#[cfg(feature = "ordered-float")]
+extern crate ordered_float;
+// The PyO3 macros refer to `::core`, which needs declaring in 2015
+// edition crates.
+#[cfg(feature = "python")]
+extern crate core;
+#[cfg(feature = "python")]
+extern crate numpy;
+#[cfg(feature = "python")]
+extern crate pyo3;
+#[cfg(feature = "testing")]
+extern crate proptest;
+#[cfg(feature = "arbitrary")]
+extern crate arbitrary;
 
+#[cfg(feature = "ndarray")]
+pub mod array;
+pub mod cluster;
+#[cfg(feature = "concurrent")]
+pub mod concurrent;
+pub mod counting;
+pub mod distance;
+pub mod error;
+pub mod eval;
+#[cfg(feature = "arbitrary")]
+pub mod fuzz;
+pub mod index;
+pub mod metrics;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "testing")]
+pub mod testing;
 pub mod vptree;
 
-pub use vptree::{VPTree, MetricItem};
+#[cfg(feature = "concurrent")]
+pub use concurrent::ConcurrentVPTree;
+pub use counting::{CountingMetric, DistanceCounter};
+pub use distance::Distance;
+pub use error::{BuildError, QueryError};
+pub use index::{BruteForce, NearestNeighborIndex};
+#[cfg(feature = "raw")]
+pub use vptree::raw;
+pub use vptree::{VPTree, VPTreeBuilder, VPMap, MetricItem, Aggregate, CompactVPTree, DistanceHistogram, Exclude, Iter, LocalOutlierFactors, NodeLayout, PersistentVPTree, QueryScratch, SearchTrace, ShardedVPTree, SlidingVPTree, SpillTree, TtlVPTree, TreeStats, TreeView, NodeInfo, TraceEvent, VantageStrategy, WeightedVPTree};
@@ -0,0 +1,319 @@
+//! Ready-made metrics over coordinate vectors and bit strings.
+//!
+//! Each coordinate type wraps anything that can be viewed as a slice
+//! of floats, like a `Vec<f32>`, a `[f64; 3]` or a `Box<[f64]>`, and
+//! implements `MetricItem` with the corresponding distance. All
+//! coordinates of items in the same tree must have the same length.
+//!
+//! With the `edit-distance` feature, `EditDistance` measures strings
+//! by their Levenshtein distance, and with the `geo` feature,
+//! `GeoPoint` measures coordinates on the Earth in meters. With the
+//! `simd` feature, Euclidean distances between `f32` coordinates use
+//! SIMD instructions.
+//!
+//! There is deliberately no squared Euclidean metric. Squaring saves a
+//! square root per evaluation and ranks neighbors the same way, but it
+//! breaks the triangle inequality: the points 0, 1 and 2 on a line are
+//! at squared distances 1 and 1 from their neighbors and 4 from each
+//! other. A tree over such a "metric" prunes subtrees it should not,
+//! and silently misses neighbors.
+//!
+//! Fixed-size float arrays, like `[f64; 3]`, and tuples of two to four
+//! floats are already `MetricItem`s under the Euclidean distance, so
+//! they need no wrapper. So are `nalgebra` vectors and points, like
+//! `SVector<f64, 3>`, `DVector<f32>` and `Point3<f64>`, with the
+//! `nalgebra` feature.
+//!
+//! Metrics can be combined with `SumMetric`, `MaxMetric` and `Scaled`,
+//! and transformed with `Sqrt` and `Capped`, which all keep them
+//! metrics. `Sqrt` also turns a squared distance back into a metric.
+//! With the `ordered-float` feature, `Ordered` measures the distances
+//! of any float metric as totally ordered `OrderedFloat`s.
+//!
+//! ```rust
+//! use vptree::VPTree;
+//! use vptree::metrics::Euclidean;
+//!
+//! let points = vec![Euclidean(vec![0.0, 0.0]), Euclidean(vec![3.0, 4.0])];
+//! let tree = VPTree::new(points);
+//! let nearest = tree.nearest_neighbor(&Euclidean(vec![2.0, 3.0])).unwrap();
+//! assert_eq!(nearest.0, vec![3.0, 4.0]);
+//! ```
+
+use std::slice;
+use num::Float;
+
+use distance::Distance;
+use vptree::MetricItem;
+
+mod combinators;
+#[cfg(feature = "edit-distance")]
+mod edit;
+#[cfg(feature = "geo")]
+mod geo;
+#[cfg(feature = "nalgebra")]
+mod linalg;
+mod mahalanobis;
+#[cfg(feature = "ordered-float")]
+mod ordered;
+mod periodic;
+#[cfg(feature = "simd")]
+mod simd;
+mod weighted;
+
+pub use self::combinators::{Capped, MaxMetric, Scaled, Sqrt, SumMetric};
+#[cfg(feature = "edit-distance")]
+pub use self::edit::EditDistance;
+#[cfg(feature = "geo")]
+pub use self::geo::GeoPoint;
+pub use self::mahalanobis::{Mahalanobis, MahalanobisBuilder};
+#[cfg(feature = "ordered-float")]
+pub use self::ordered::Ordered;
+pub use self::periodic::{Periodic, PeriodicBuilder};
+pub use self::weighted::{WeightedEuclidean, WeightedEuclideanBuilder};
+
+/// Coordinates under the Euclidean (L2) distance.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Euclidean<V>(pub V);
+
+/// Coordinates under the Manhattan (L1) distance, the sum of the
+/// absolute differences of the coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Manhattan<V>(pub V);
+
+/// Coordinates under the Chebyshev (L∞) distance, the largest absolute
+/// difference of any coordinate.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Chebyshev<V>(pub V);
+
+/// Coordinates under the Minkowski (Lp) distance, for integer `P`.
+///
+/// `Minkowski<V, 1>` and `Minkowski<V, 2>` measure the same distances
+/// as `Manhattan` and `Euclidean`, and large `P` approach `Chebyshev`.
+/// The triangle inequality only holds for `P` of at least 1, so using
+/// `Minkowski<V, 0>` as a metric fails to compile.
+///
+/// ```rust
+/// use vptree::MetricItem;
+/// use vptree::metrics::Minkowski;
+///
+/// let (a, b) = (Minkowski::<_, 3>([0.0, 0.0]), Minkowski([1.0, 1.0]));
+/// assert!((a.distance(&b) - 2f64.cbrt()).abs() < 1e-12);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Minkowski<V, const P: u32>(pub V);
+
+impl<V, const P: u32> Minkowski<V, P> {
+    /// Fails to evaluate, and so to compile, for p below 1.
+    const VALID_P: () = assert!(P >= 1, "Minkowski distances need p >= 1");
+}
+
+/// Return the absolute differences of the coordinates of `a` and `b`.
+fn differences<'a, F: Float>(a: &'a [F], b: &'a [F]) -> impl Iterator<Item = F> + 'a {
+    debug_assert_eq!(a.len(), b.len(), "coordinates must have the same length");
+    a.iter().zip(b).map(|(&x, &y)| (x - y).abs())
+}
+
+/// Floats the Euclidean distances of `Euclidean` and float arrays are
+/// measured in.
+///
+/// With the `simd` feature, the `f32` implementation uses SIMD
+/// instructions, which speeds up high-dimensional distances several
+/// times. The sum is then taken in a different order, so distances
+/// can differ from the scalar ones in the last bits.
+pub trait SquaredDistance: Float {
+    /// Return the sum of the squared differences of `a` and `b`.
+    fn squared_distance(a: &[Self], b: &[Self]) -> Self {
+        differences(a, b).fold(Self::zero(), |acc, d| acc + d * d)
+    }
+}
+
+impl SquaredDistance for f64 {}
+
+#[cfg(not(feature = "simd"))]
+impl SquaredDistance for f32 {}
+
+impl<F: SquaredDistance + Distance, V: AsRef<[F]>> MetricItem<F> for Euclidean<V> {
+    fn distance(&self, b: &Self) -> F {
+        F::squared_distance(self.0.as_ref(), b.0.as_ref()).sqrt()
+    }
+}
+
+impl<F: Float + Distance, V: AsRef<[F]>> MetricItem<F> for Manhattan<V> {
+    fn distance(&self, b: &Self) -> F {
+        differences(self.0.as_ref(), b.0.as_ref()).fold(F::zero(), |acc, d| acc + d)
+    }
+}
+
+impl<F: Float + Distance, V: AsRef<[F]>> MetricItem<F> for Chebyshev<V> {
+    fn distance(&self, b: &Self) -> F {
+        differences(self.0.as_ref(), b.0.as_ref()).fold(F::zero(), Float::max)
+    }
+}
+
+impl<F: Float + Distance, V: AsRef<[F]>, const P: u32> MetricItem<F> for Minkowski<V, P> {
+    fn distance(&self, b: &Self) -> F {
+        let () = Self::VALID_P;
+        let ds = differences(self.0.as_ref(), b.0.as_ref());
+        match P {
+            1 => ds.fold(F::zero(), |acc, d| acc + d),
+            2 => ds.fold(F::zero(), |acc, d| acc + d * d).sqrt(),
+            _ => {
+                let p = F::from(P).unwrap();
+                ds.fold(F::zero(), |acc, d| acc + d.powf(p)).powf(p.recip())
+            }
+        }
+    }
+}
+
+/// A direction under the angular distance, the angle in radians
+/// between two vectors.
+///
+/// Cosine similarity ranks neighbors the same way, but neither it nor
+/// one minus it is a metric, so a tree over it misses neighbors. The
+/// angle between vectors is a metric. Vectors are normalized when
+/// they are wrapped, so only their directions matter.
+///
+/// ```rust
+/// use vptree::MetricItem;
+/// use vptree::metrics::Angular;
+///
+/// let a = Angular::new(vec![2.0, 0.0]).unwrap();
+/// let b = Angular::new(vec![1.0, 1.0]).unwrap();
+/// assert!((a.distance(&b) - std::f64::consts::FRAC_PI_4).abs() < 1e-12);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Angular<V>(V);
+
+impl<V> Angular<V> {
+    /// Normalize `coords` to unit length and wrap them.
+    ///
+    /// Returns `None` if `coords` has no direction, because its length
+    /// is zero or not finite.
+    pub fn new<F: Float>(mut coords: V) -> Option<Self> where V: AsRef<[F]> + AsMut<[F]> {
+        let norm = coords.as_ref().iter().fold(F::zero(), |acc, &x| acc + x * x).sqrt();
+        if !(norm > F::zero() && norm.is_finite()) {
+            return None;
+        }
+        for x in coords.as_mut() {
+            *x = *x / norm;
+        }
+        Some(Angular(coords))
+    }
+
+    /// Return the normalized coordinates.
+    pub fn coords(&self) -> &V {
+        &self.0
+    }
+
+    /// Unwrap the normalized coordinates.
+    pub fn into_inner(self) -> V {
+        self.0
+    }
+}
+
+impl<F: Float + Distance, V: AsRef<[F]>> MetricItem<F> for Angular<V> {
+    fn distance(&self, b: &Self) -> F {
+        // The arccosine of the dot product loses precision for nearly
+        // parallel vectors. Half the angle is also the arctangent of
+        // the lengths of the difference and the sum of unit vectors.
+        let (x, y) = (self.0.as_ref(), b.0.as_ref());
+        debug_assert_eq!(x.len(), y.len(), "coordinates must have the same length");
+        let (diff, sum) = x.iter().zip(y).fold((F::zero(), F::zero()), |(diff, sum), (&x, &y)| {
+            (diff + (x - y) * (x - y), sum + (x + y) * (x + y))
+        });
+        diff.sqrt().atan2(sum.sqrt()) * F::from(2).unwrap()
+    }
+}
+
+impl<F: SquaredDistance + Distance, const N: usize> MetricItem<F> for [F; N] {
+    /// Return the Euclidean distance.
+    fn distance(&self, b: &Self) -> F {
+        Euclidean(self).distance(&Euclidean(b))
+    }
+}
+
+macro_rules! tuple_metric {
+    ($($x:ident $y:ident),*) => {
+        impl<F: Float + Distance> MetricItem<F> for ($(tuple_metric!(@float $x),)*) {
+            /// Return the Euclidean distance.
+            fn distance(&self, b: &Self) -> F {
+                let ($($x,)*) = *self;
+                let ($($y,)*) = *b;
+                (F::zero() $(+ ($x - $y) * ($x - $y))*).sqrt()
+            }
+        }
+    };
+    (@float $x:ident) => { F };
+}
+
+tuple_metric!(x0 y0, x1 y1);
+tuple_metric!(x0 y0, x1 y1, x2 y2);
+tuple_metric!(x0 y0, x1 y1, x2 y2, x3 y3);
+
+/// Bit strings stored in 64-bit words, for `HammingBits`.
+pub trait BitBlocks {
+    /// Return the words of the bit string.
+    fn blocks(&self) -> &[u64];
+}
+
+impl BitBlocks for u64 {
+    fn blocks(&self) -> &[u64] {
+        slice::from_ref(self)
+    }
+}
+
+impl BitBlocks for [u64] {
+    fn blocks(&self) -> &[u64] {
+        self
+    }
+}
+
+impl<const N: usize> BitBlocks for [u64; N] {
+    fn blocks(&self) -> &[u64] {
+        self
+    }
+}
+
+impl BitBlocks for Vec<u64> {
+    fn blocks(&self) -> &[u64] {
+        self
+    }
+}
+
+impl BitBlocks for Box<[u64]> {
+    fn blocks(&self) -> &[u64] {
+        self
+    }
+}
+
+impl<B: BitBlocks + ?Sized> BitBlocks for &B {
+    fn blocks(&self) -> &[u64] {
+        (**self).blocks()
+    }
+}
+
+/// A bit string under the Hamming distance, the number of bits that
+/// differ.
+///
+/// Suits binary hashes and fingerprints, like perceptual image hashes.
+/// Bit strings of the same tree must have the same number of words.
+///
+/// ```rust
+/// use vptree::VPTree;
+/// use vptree::metrics::HammingBits;
+///
+/// let hashes = vec![HammingBits(0xff00u64), HammingBits(0x0ff0), HammingBits(0x000f)];
+/// let tree: VPTree<u32, _> = VPTree::new(hashes);
+/// assert_eq!(tree.nearest_neighbor(&HammingBits(0x0f0f)).unwrap().0, 0x000f);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct HammingBits<B>(pub B);
+
+impl<B: BitBlocks> MetricItem<u32> for HammingBits<B> {
+    fn distance(&self, b: &Self) -> u32 {
+        let (x, y) = (self.0.blocks(), b.0.blocks());
+        debug_assert_eq!(x.len(), y.len(), "bit strings must have the same length");
+        x.iter().zip(y).map(|(&x, &y)| (x ^ y).count_ones()).sum()
+    }
+}
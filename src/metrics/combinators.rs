@@ -0,0 +1,151 @@
+//! Metrics built from other metrics.
+
+use num::Float;
+
+use distance::Distance;
+use vptree::MetricItem;
+
+/// A pair of items, at the sum of the distances between their parts.
+///
+/// The sum of two metrics is a metric, so records with several kinds
+/// of fields can be indexed together. `Scaled` balances parts measured
+/// in different units.
+///
+/// ```rust
+/// use vptree::MetricItem;
+/// use vptree::metrics::{Manhattan, Scaled, SumMetric};
+///
+/// // A location in kilometers, plus a price where 10 units count as
+/// // much as a kilometer.
+/// let a = SumMetric(Manhattan([0.0, 0.0]), Scaled::new(Manhattan([100.0]), 0.1));
+/// let b = SumMetric(Manhattan([3.0, 4.0]), Scaled::new(Manhattan([120.0]), 0.1));
+/// assert_eq!(a.distance(&b), 9.0);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SumMetric<A, B>(pub A, pub B);
+
+/// A pair of items, at the larger of the distances between their
+/// parts.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct MaxMetric<A, B>(pub A, pub B);
+
+/// An item whose distances are multiplied by a constant factor.
+///
+/// Scaling a metric by a positive factor keeps it a metric, as long as
+/// every item in a tree has the same factor.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Scaled<M, F> {
+    item: M,
+    factor: F,
+}
+
+/// An item at the square root of the distance of the wrapped item.
+///
+/// Any concave, increasing transform that maps zero to zero keeps a
+/// metric a metric, and the square root is one. It compresses large
+/// distances, and is a metric even over squared Euclidean distances,
+/// making it the Euclidean distance again.
+///
+/// Convex transforms like squaring are not safe: they break the
+/// triangle inequality, and a tree over them misses neighbors. No
+/// adapter is provided for them.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Sqrt<M>(pub M);
+
+/// An item whose distances are capped at a constant.
+///
+/// Capping a metric keeps it a metric, as long as every item in a
+/// tree has the same cap. Distances beyond the cap are all treated as
+/// equally far, which suits radius queries below the cap and limits
+/// the influence of outliers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Capped<M, F> {
+    item: M,
+    cap: F,
+}
+
+impl<F: Distance, A: MetricItem<F>, B: MetricItem<F>> MetricItem<F> for SumMetric<A, B> {
+    fn distance(&self, b: &Self) -> F {
+        self.0.distance(&b.0).saturating_add(self.1.distance(&b.1))
+    }
+}
+
+impl<F: Distance, A: MetricItem<F>, B: MetricItem<F>> MetricItem<F> for MaxMetric<A, B> {
+    fn distance(&self, b: &Self) -> F {
+        self.0.distance(&b.0).larger(self.1.distance(&b.1))
+    }
+}
+
+impl<M, F: Float> Scaled<M, F> {
+    /// Wrap `item`, with its distances multiplied by `factor`.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `factor` is positive and finite.
+    pub fn new(item: M, factor: F) -> Self {
+        assert!(factor > F::zero() && factor.is_finite(), "scale factors must be positive and finite");
+        Scaled { item, factor }
+    }
+
+    /// Return the wrapped item.
+    pub fn item(&self) -> &M {
+        &self.item
+    }
+
+    /// Return the factor distances are multiplied by.
+    pub fn factor(&self) -> F {
+        self.factor
+    }
+
+    /// Unwrap the item.
+    pub fn into_inner(self) -> M {
+        self.item
+    }
+}
+
+impl<F: Float + Distance, M: MetricItem<F>> MetricItem<F> for Scaled<M, F> {
+    fn distance(&self, b: &Self) -> F {
+        debug_assert!(self.factor == b.factor, "scaled items must share a factor");
+        self.item.distance(&b.item) * self.factor
+    }
+}
+
+impl<F: Float + Distance, M: MetricItem<F>> MetricItem<F> for Sqrt<M> {
+    fn distance(&self, b: &Self) -> F {
+        self.0.distance(&b.0).sqrt()
+    }
+}
+
+impl<M, F: Distance> Capped<M, F> {
+    /// Wrap `item`, with its distances capped at `cap`.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `cap` is positive.
+    pub fn new(item: M, cap: F) -> Self {
+        assert!(cap > F::zero(), "caps must be positive");
+        Capped { item, cap }
+    }
+
+    /// Return the wrapped item.
+    pub fn item(&self) -> &M {
+        &self.item
+    }
+
+    /// Return the largest distance reported.
+    pub fn cap(&self) -> F {
+        self.cap
+    }
+
+    /// Unwrap the item.
+    pub fn into_inner(self) -> M {
+        self.item
+    }
+}
+
+impl<F: Distance, M: MetricItem<F>> MetricItem<F> for Capped<M, F> {
+    fn distance(&self, b: &Self) -> F {
+        debug_assert!(self.cap == b.cap, "capped items must share a cap");
+        self.item.distance(&b.item).smaller(self.cap)
+    }
+}
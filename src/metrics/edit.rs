@@ -0,0 +1,92 @@
+//! The Levenshtein distance between strings.
+
+use std::mem;
+
+use vptree::MetricItem;
+
+/// A string under the Levenshtein distance, the fewest single
+/// character insertions, deletions and substitutions that turn one
+/// string into the other.
+///
+/// Characters are compared as Unicode scalar values, with a faster
+/// path when both strings are ASCII.
+///
+/// ```rust
+/// use vptree::VPTree;
+/// use vptree::metrics::EditDistance;
+///
+/// let words = vec!["kitten", "sitting", "mitten", "fitting"];
+/// let tree: VPTree<u32, _> = VPTree::new(words.into_iter().map(EditDistance).collect());
+/// assert_eq!(tree.nearest_neighbor(&EditDistance("smitten")).unwrap().0, "mitten");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct EditDistance<S>(pub S);
+
+impl<S: AsRef<str>> EditDistance<S> {
+    /// Return the distance to `other` if it is at most `max`, or
+    /// `None` otherwise.
+    ///
+    /// Only the band of the edit table within `max` of its diagonal
+    /// is filled in, and the search stops as soon as every path
+    /// through it costs more than `max`, so this is much cheaper
+    /// than `distance` for small bounds.
+    pub fn distance_within(&self, other: &Self, max: u32) -> Option<u32> {
+        let (a, b) = (self.0.as_ref(), other.0.as_ref());
+        if a.is_ascii() && b.is_ascii() {
+            levenshtein(a.as_bytes(), b.as_bytes(), max)
+        } else {
+            let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+            levenshtein(&a, &b, max)
+        }
+    }
+}
+
+impl<S: AsRef<str>> MetricItem<u32> for EditDistance<S> {
+    fn distance(&self, b: &Self) -> u32 {
+        self.distance_within(b, u32::MAX).expect("every distance is at most u32::MAX")
+    }
+}
+
+/// Return the Levenshtein distance between `a` and `b` if it is at
+/// most `max`.
+fn levenshtein<C: PartialEq>(a: &[C], b: &[C], max: u32) -> Option<u32> {
+    // A shared prefix or suffix never costs anything.
+    let prefix = a.iter().zip(b).take_while(|&(x, y)| x == y).count();
+    let (a, b) = (&a[prefix..], &b[prefix..]);
+    let suffix = a.iter().rev().zip(b.iter().rev()).take_while(|&(x, y)| x == y).count();
+    let (a, b) = (&a[..a.len() - suffix], &b[..b.len() - suffix]);
+    let (a, b) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    let (m, n) = (a.len(), b.len());
+    if (n - m) as u64 > max as u64 {
+        return None;
+    }
+
+    // Rows of the edit table over `b`, with every cost above `max`
+    // clamped to `over`. Cells further than `max` from the diagonal
+    // cost more than `max`, so only the band around it is computed.
+    let over = max.saturating_add(1);
+    let band = max as usize;
+    let mut prev: Vec<u32> = (0..=n).map(|j| (j as u32).min(over)).collect();
+    let mut cur = vec![over; n + 1];
+    for i in 1..=m {
+        let lo = i.saturating_sub(band).max(1);
+        let hi = i.saturating_add(band).min(n);
+        cur[lo - 1] = if lo == 1 { (i as u32).min(over) } else { over };
+        let mut row_min = cur[lo - 1];
+        for j in lo..=hi {
+            let substitute = prev[j - 1].saturating_add((a[i - 1] != b[j - 1]) as u32);
+            let cost = substitute.min(prev[j].saturating_add(1)).min(cur[j - 1].saturating_add(1)).min(over);
+            cur[j] = cost;
+            row_min = row_min.min(cost);
+        }
+        if hi < n {
+            cur[hi + 1] = over;
+        }
+        if row_min > max {
+            return None;
+        }
+        mem::swap(&mut prev, &mut cur);
+    }
+
+    Some(prev[n]).filter(|&d| d <= max)
+}
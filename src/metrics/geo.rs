@@ -0,0 +1,75 @@
+//! Great-circle distances between points on the Earth.
+
+use vptree::MetricItem;
+
+/// A point on the Earth's surface, given by its latitude and longitude
+/// in degrees.
+///
+/// Points are measured by their great-circle distance on a sphere of
+/// the Earth's mean radius, computed with the haversine formula, in
+/// meters. Query radii are in meters too. The Earth is not quite a
+/// sphere, so distances can be off by up to about half a percent.
+///
+/// Use `central_angle` for distances in radians on the unit sphere,
+/// and `meters_to_radians` and `radians_to_meters` to convert between
+/// the two.
+///
+/// ```rust
+/// use vptree::VPTree;
+/// use vptree::metrics::GeoPoint;
+///
+/// let cities = vec![
+///     GeoPoint::new(48.8566, 2.3522),   // Paris
+///     GeoPoint::new(51.5074, -0.1278),  // London
+///     GeoPoint::new(52.5200, 13.4050),  // Berlin
+/// ];
+/// let tree = VPTree::new(cities);
+/// let brussels = GeoPoint::new(50.8503, 4.3517);
+/// assert_eq!(tree.within_radius(&brussels, 350_000.0, true).len(), 2);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct GeoPoint {
+    /// Latitude in degrees, positive to the north.
+    pub lat: f64,
+    /// Longitude in degrees, positive to the east.
+    pub lon: f64,
+}
+
+impl GeoPoint {
+    /// The mean radius of the Earth, in meters.
+    pub const EARTH_RADIUS: f64 = 6_371_008.8;
+
+    /// Create a point from its latitude and longitude in degrees.
+    pub fn new(lat: f64, lon: f64) -> Self {
+        GeoPoint { lat, lon }
+    }
+
+    /// Return the angle in radians between this point and `other`, as
+    /// seen from the center of the Earth.
+    pub fn central_angle(&self, other: &GeoPoint) -> f64 {
+        let (lat1, lat2) = (self.lat.to_radians(), other.lat.to_radians());
+        let half_dlat = (lat2 - lat1) / 2.0;
+        let half_dlon = (other.lon - self.lon).to_radians() / 2.0;
+        let h = half_dlat.sin().powi(2) + lat1.cos() * lat2.cos() * half_dlon.sin().powi(2);
+        2.0 * h.sqrt().atan2((1.0 - h).max(0.0).sqrt())
+    }
+
+    /// Convert a distance along the Earth's surface in meters to a
+    /// central angle in radians.
+    pub fn meters_to_radians(meters: f64) -> f64 {
+        meters / GeoPoint::EARTH_RADIUS
+    }
+
+    /// Convert a central angle in radians to a distance along the
+    /// Earth's surface in meters.
+    pub fn radians_to_meters(radians: f64) -> f64 {
+        radians * GeoPoint::EARTH_RADIUS
+    }
+}
+
+impl MetricItem<f64> for GeoPoint {
+    /// Return the great-circle distance in meters.
+    fn distance(&self, b: &Self) -> f64 {
+        GeoPoint::radians_to_meters(self.central_angle(b))
+    }
+}
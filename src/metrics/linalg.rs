@@ -0,0 +1,32 @@
+//! Euclidean distances between `nalgebra` vectors and points.
+
+use nalgebra::{DefaultAllocator, Dim, DimName, Matrix, OPoint, Scalar, U1};
+use nalgebra::allocator::Allocator;
+use nalgebra::storage::RawStorage;
+use num::Float;
+
+use distance::Distance;
+use vptree::MetricItem;
+
+/// Column vectors of any storage, including `SVector`, `DVector` and
+/// their views, under the Euclidean distance.
+impl<F, R, S> MetricItem<F> for Matrix<F, R, U1, S>
+    where F: Scalar + Float + Distance, R: Dim, S: RawStorage<F, R>
+{
+    /// Return the Euclidean distance.
+    fn distance(&self, b: &Self) -> F {
+        debug_assert_eq!(self.nrows(), b.nrows(), "vectors must have the same length");
+        self.iter().zip(b.iter()).fold(F::zero(), |acc, (&x, &y)| acc + (x - y) * (x - y)).sqrt()
+    }
+}
+
+/// Points, including `Point2` and `Point3`, under the Euclidean
+/// distance.
+impl<F, D> MetricItem<F> for OPoint<F, D>
+    where F: Scalar + Float + Distance, D: DimName, DefaultAllocator: Allocator<D>
+{
+    /// Return the Euclidean distance.
+    fn distance(&self, b: &Self) -> F {
+        self.coords.distance(&b.coords)
+    }
+}
@@ -0,0 +1,120 @@
+//! The Mahalanobis distance for correlated coordinates.
+
+use num::Float;
+
+use distance::Distance;
+use error::BuildError;
+use vptree::MetricItem;
+
+/// Maps coordinates into `Mahalanobis` points, for a given covariance.
+///
+/// The Mahalanobis distance between `x` and `y` is the square root of
+/// `(x - y)ᵀ Σ⁻¹ (x - y)` for a covariance `Σ`. Writing `Σ` as `L Lᵀ`,
+/// with `L` its lower triangular Cholesky factor, this is the Euclidean
+/// distance between `L⁻¹ x` and `L⁻¹ y`. Each point is transformed once
+/// when it is created, so measuring distances costs no more than the
+/// Euclidean distance.
+///
+/// ```rust
+/// use vptree::MetricItem;
+/// use vptree::metrics::MahalanobisBuilder;
+///
+/// // The second coordinate varies four times as much as the first.
+/// let space = MahalanobisBuilder::from_covariance([[1.0, 0.0], [0.0, 16.0]]).unwrap();
+/// let origin = space.point([0.0, 0.0]);
+/// assert_eq!(origin.distance(&space.point([1.0, 0.0])), 1.0);
+/// assert_eq!(origin.distance(&space.point([0.0, 4.0])), 1.0);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MahalanobisBuilder<F, const D: usize> {
+    /// The lower triangular Cholesky factor of the covariance.
+    cholesky: [[F; D]; D],
+}
+
+/// A point under the Mahalanobis distance, created by
+/// `MahalanobisBuilder::point`.
+///
+/// Points from different builders can't be meaningfully compared.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mahalanobis<F, const D: usize> {
+    coords: [F; D],
+    /// `coords` transformed by the inverse of the Cholesky factor.
+    whitened: [F; D],
+}
+
+impl<F: Float, const D: usize> MahalanobisBuilder<F, D> {
+    /// Create a builder for the covariance matrix `covariance`.
+    ///
+    /// Only the lower triangle of the matrix is read, and the rest is
+    /// assumed to mirror it. Returns `BuildError::InvalidParameter` if
+    /// the matrix is not positive definite.
+    pub fn from_covariance(covariance: [[F; D]; D]) -> Result<Self, BuildError> {
+        let mut l = [[F::zero(); D]; D];
+        for i in 0..D {
+            for j in 0..=i {
+                let s = (0..j).fold(covariance[i][j], |s, k| s - l[i][k] * l[j][k]);
+                if i == j {
+                    if !(s > F::zero() && s.is_finite()) {
+                        return Err(BuildError::InvalidParameter("covariance must be positive definite"));
+                    }
+                    l[i][i] = s.sqrt();
+                } else {
+                    l[i][j] = s / l[j][j];
+                }
+            }
+        }
+        Ok(MahalanobisBuilder { cholesky: l })
+    }
+
+    /// Create a builder from the lower triangular Cholesky factor `L`
+    /// of the covariance `L Lᵀ`.
+    ///
+    /// Only the lower triangle of the factor is read. Returns
+    /// `BuildError::InvalidParameter` unless the diagonal is positive
+    /// and every entry is finite.
+    pub fn from_cholesky(cholesky: [[F; D]; D]) -> Result<Self, BuildError> {
+        let mut l = [[F::zero(); D]; D];
+        for i in 0..D {
+            if !(cholesky[i][i] > F::zero() && cholesky[i][i].is_finite()) {
+                return Err(BuildError::InvalidParameter("Cholesky factor must have a positive diagonal"));
+            }
+            for j in 0..=i {
+                if !cholesky[i][j].is_finite() {
+                    return Err(BuildError::InvalidParameter("Cholesky factor must be finite"));
+                }
+                l[i][j] = cholesky[i][j];
+            }
+        }
+        Ok(MahalanobisBuilder { cholesky: l })
+    }
+
+    /// Return the lower triangular Cholesky factor of the covariance.
+    pub fn cholesky(&self) -> &[[F; D]; D] {
+        &self.cholesky
+    }
+
+    /// Create a point with the coordinates `coords`.
+    pub fn point(&self, coords: [F; D]) -> Mahalanobis<F, D> {
+        // Solve L w = coords by forward substitution.
+        let l = &self.cholesky;
+        let mut whitened = [F::zero(); D];
+        for i in 0..D {
+            let s = (0..i).fold(coords[i], |s, k| s - l[i][k] * whitened[k]);
+            whitened[i] = s / l[i][i];
+        }
+        Mahalanobis { coords, whitened }
+    }
+}
+
+impl<F, const D: usize> Mahalanobis<F, D> {
+    /// Return the coordinates of the point.
+    pub fn coords(&self) -> &[F; D] {
+        &self.coords
+    }
+}
+
+impl<F: Float + Distance, const D: usize> MetricItem<F> for Mahalanobis<F, D> {
+    fn distance(&self, b: &Self) -> F {
+        self.whitened.iter().zip(&b.whitened).fold(F::zero(), |acc, (&x, &y)| acc + (x - y) * (x - y)).sqrt()
+    }
+}
@@ -0,0 +1,39 @@
+//! Adapting metrics to totally ordered distances.
+
+use ordered_float::OrderedFloat;
+
+use distance::Distance;
+use vptree::MetricItem;
+
+/// An item measuring the distances of the wrapped item as
+/// `OrderedFloat`s.
+///
+/// This lets a tree over an existing float metric use totally ordered
+/// distances without rewriting the metric. A NaN distance then puts
+/// an item infinitely far away, instead of failing construction or
+/// queries.
+///
+/// ```rust
+/// extern crate ordered_float;
+/// extern crate vptree;
+///
+/// use ordered_float::OrderedFloat;
+/// use vptree::VPTree;
+/// use vptree::metrics::{Euclidean, Ordered};
+///
+/// # fn main() {
+/// let points = vec![Ordered(Euclidean([0.0, 0.0])), Ordered(Euclidean([f64::NAN, 1.0]))];
+/// let tree: VPTree<OrderedFloat<f64>, _> = VPTree::new(points);
+/// let nearest = tree.nearest_neighbors(&Ordered(Euclidean([1.0, 1.0])), 2, true);
+/// assert_eq!(nearest[0].0, Euclidean([0.0, 0.0]));
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Ordered<M>(pub M);
+
+impl<F, M> MetricItem<OrderedFloat<F>> for Ordered<M>
+    where F: Distance, M: MetricItem<F>, OrderedFloat<F>: Distance {
+    fn distance(&self, b: &Self) -> OrderedFloat<F> {
+        OrderedFloat(self.0.distance(&b.0))
+    }
+}
@@ -0,0 +1,94 @@
+//! Euclidean distances under periodic boundary conditions.
+
+use num::Float;
+
+use distance::Distance;
+use error::BuildError;
+use vptree::MetricItem;
+
+/// Maps coordinates into `Periodic` points, for a box whose opposite
+/// faces are joined.
+///
+/// A point leaving the box through one face comes back in through the
+/// opposite one, as in molecular dynamics simulations or tiling
+/// textures. The distance between two points is the Euclidean distance
+/// to the nearest periodic image of the other point, known as the
+/// minimum image convention.
+///
+/// ```rust
+/// use vptree::MetricItem;
+/// use vptree::metrics::PeriodicBuilder;
+///
+/// let space = PeriodicBuilder::new([10.0, 10.0]).unwrap();
+/// let (a, b) = (space.point([1.0, 5.0]), space.point([9.0, 5.0]));
+/// assert_eq!(a.distance(&b), 2.0);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PeriodicBuilder<F, const D: usize> {
+    lengths: [F; D],
+}
+
+/// A point in a periodic box, created by `PeriodicBuilder::point`.
+///
+/// Points from boxes of different sizes can't be meaningfully
+/// compared.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Periodic<F, const D: usize> {
+    /// The coordinates, wrapped into the box.
+    coords: [F; D],
+    lengths: [F; D],
+}
+
+impl<F: Float, const D: usize> PeriodicBuilder<F, D> {
+    /// Create a builder for a box with the given side lengths, with a
+    /// corner at the origin.
+    ///
+    /// Returns `BuildError::InvalidParameter` unless every length is
+    /// positive and finite.
+    pub fn new(lengths: [F; D]) -> Result<Self, BuildError> {
+        if lengths.iter().any(|&l| !(l > F::zero() && l.is_finite())) {
+            return Err(BuildError::InvalidParameter("box lengths must be positive and finite"));
+        }
+        Ok(PeriodicBuilder { lengths })
+    }
+
+    /// Return the side lengths of the box.
+    pub fn lengths(&self) -> &[F; D] {
+        &self.lengths
+    }
+
+    /// Create a point with the coordinates `coords`, which are wrapped
+    /// into the box.
+    pub fn point(&self, mut coords: [F; D]) -> Periodic<F, D> {
+        for (x, &l) in coords.iter_mut().zip(&self.lengths) {
+            *x = *x % l;
+            if *x < F::zero() {
+                *x = *x + l;
+            }
+            // Adding `l` to a tiny negative remainder can round up to
+            // `l` itself.
+            if *x >= l {
+                *x = F::zero();
+            }
+        }
+        Periodic { coords, lengths: self.lengths }
+    }
+}
+
+impl<F, const D: usize> Periodic<F, D> {
+    /// Return the coordinates of the point, wrapped into the box.
+    pub fn coords(&self) -> &[F; D] {
+        &self.coords
+    }
+}
+
+impl<F: Float + Distance, const D: usize> MetricItem<F> for Periodic<F, D> {
+    fn distance(&self, b: &Self) -> F {
+        debug_assert!(self.lengths == b.lengths, "points must be in the same box");
+        self.coords.iter().zip(&b.coords).zip(&self.lengths).fold(F::zero(), |acc, ((&x, &y), &l)| {
+            let dx = (x - y).abs();
+            let dx = dx.min(l - dx);
+            acc + dx * dx
+        }).sqrt()
+    }
+}
@@ -0,0 +1,85 @@
+//! SIMD Euclidean distances between `f32` coordinates.
+//!
+//! On x86-64, AVX is used when the CPU supports it, and SSE, which
+//! every x86-64 CPU has, otherwise. Other targets sum in eight
+//! independent lanes, which compilers vectorize reliably.
+
+use super::{differences, SquaredDistance};
+
+impl SquaredDistance for f32 {
+    fn squared_distance(a: &[f32], b: &[f32]) -> f32 {
+        debug_assert_eq!(a.len(), b.len(), "coordinates must have the same length");
+        let n = a.len().min(b.len());
+        let (a, b) = (&a[..n], &b[..n]);
+
+        #[cfg(target_arch = "x86_64")]
+        {
+            if is_x86_feature_detected!("avx") {
+                // Safe, since the CPU supports AVX.
+                unsafe { avx(a, b) }
+            } else {
+                sse(a, b)
+            }
+        }
+        #[cfg(not(target_arch = "x86_64"))]
+        {
+            lanes(a, b)
+        }
+    }
+}
+
+/// Return the sum of the squared differences of the elements left
+/// over after the last full vector.
+fn remainder(a: &[f32], b: &[f32]) -> f32 {
+    differences(a, b).fold(0.0, |acc, d| acc + d * d)
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx")]
+unsafe fn avx(a: &[f32], b: &[f32]) -> f32 {
+    use std::arch::x86_64::*;
+
+    let (xs, ys) = (a.chunks_exact(8), b.chunks_exact(8));
+    let tail = remainder(xs.remainder(), ys.remainder());
+    let mut acc = _mm256_setzero_ps();
+    for (x, y) in xs.zip(ys) {
+        let d = _mm256_sub_ps(_mm256_loadu_ps(x.as_ptr()), _mm256_loadu_ps(y.as_ptr()));
+        acc = _mm256_add_ps(acc, _mm256_mul_ps(d, d));
+    }
+    let mut sums = [0.0f32; 8];
+    _mm256_storeu_ps(sums.as_mut_ptr(), acc);
+    sums.iter().sum::<f32>() + tail
+}
+
+#[cfg(target_arch = "x86_64")]
+fn sse(a: &[f32], b: &[f32]) -> f32 {
+    use std::arch::x86_64::*;
+
+    let (xs, ys) = (a.chunks_exact(4), b.chunks_exact(4));
+    let tail = remainder(xs.remainder(), ys.remainder());
+    let mut sums = [0.0f32; 4];
+    // Safe, since SSE is part of x86-64 and every chunk holds four
+    // elements.
+    unsafe {
+        let mut acc = _mm_setzero_ps();
+        for (x, y) in xs.zip(ys) {
+            let d = _mm_sub_ps(_mm_loadu_ps(x.as_ptr()), _mm_loadu_ps(y.as_ptr()));
+            acc = _mm_add_ps(acc, _mm_mul_ps(d, d));
+        }
+        _mm_storeu_ps(sums.as_mut_ptr(), acc);
+    }
+    sums.iter().sum::<f32>() + tail
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn lanes(a: &[f32], b: &[f32]) -> f32 {
+    let (xs, ys) = (a.chunks_exact(8), b.chunks_exact(8));
+    let tail = remainder(xs.remainder(), ys.remainder());
+    let mut sums = [0.0f32; 8];
+    for (x, y) in xs.zip(ys) {
+        for ((s, &x), &y) in sums.iter_mut().zip(x).zip(y) {
+            *s += (x - y) * (x - y);
+        }
+    }
+    sums.iter().sum::<f32>() + tail
+}
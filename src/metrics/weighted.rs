@@ -0,0 +1,84 @@
+//! Euclidean distances with a weight on each dimension.
+
+use num::Float;
+
+use distance::Distance;
+use error::BuildError;
+use vptree::MetricItem;
+
+/// Maps coordinates into `WeightedEuclidean` points, for a given
+/// weight on each dimension.
+///
+/// The distance between `x` and `y` is the square root of the sum of
+/// `w[i] * (x[i] - y[i])²`, which puts features of different scales on
+/// an equal footing. Each point is scaled by the square roots of the
+/// weights once when it is created, so measuring distances costs no
+/// more than the Euclidean distance.
+///
+/// A weight of zero ignores its dimension entirely, so distinct points
+/// can then be at distance zero. Trees handle this correctly.
+///
+/// ```rust
+/// use vptree::MetricItem;
+/// use vptree::metrics::WeightedEuclideanBuilder;
+///
+/// let space = WeightedEuclideanBuilder::new([4.0, 1.0]).unwrap();
+/// assert_eq!(space.point([0.0, 0.0]).distance(&space.point([1.0, 0.0])), 2.0);
+/// assert_eq!(space.point([0.0, 0.0]).distance(&space.point([0.0, 1.0])), 1.0);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WeightedEuclideanBuilder<F, const D: usize> {
+    /// The square root of each weight.
+    scales: [F; D],
+}
+
+/// A point under a weighted Euclidean distance, created by
+/// `WeightedEuclideanBuilder::point`.
+///
+/// Points from builders with different weights can't be meaningfully
+/// compared.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WeightedEuclidean<F, const D: usize> {
+    coords: [F; D],
+    /// `coords` multiplied by the square roots of the weights.
+    scaled: [F; D],
+}
+
+impl<F: Float, const D: usize> WeightedEuclideanBuilder<F, D> {
+    /// Create a builder with the weight of each dimension.
+    ///
+    /// Returns `BuildError::InvalidParameter` unless every weight is
+    /// non-negative and finite.
+    pub fn new(weights: [F; D]) -> Result<Self, BuildError> {
+        let mut scales = weights;
+        for w in &mut scales {
+            if !(*w >= F::zero() && w.is_finite()) {
+                return Err(BuildError::InvalidParameter("weights must be non-negative and finite"));
+            }
+            *w = w.sqrt();
+        }
+        Ok(WeightedEuclideanBuilder { scales })
+    }
+
+    /// Create a point with the coordinates `coords`.
+    pub fn point(&self, coords: [F; D]) -> WeightedEuclidean<F, D> {
+        let mut scaled = coords;
+        for (x, &s) in scaled.iter_mut().zip(&self.scales) {
+            *x = *x * s;
+        }
+        WeightedEuclidean { coords, scaled }
+    }
+}
+
+impl<F, const D: usize> WeightedEuclidean<F, D> {
+    /// Return the coordinates of the point.
+    pub fn coords(&self) -> &[F; D] {
+        &self.coords
+    }
+}
+
+impl<F: Float + Distance, const D: usize> MetricItem<F> for WeightedEuclidean<F, D> {
+    fn distance(&self, b: &Self) -> F {
+        self.scaled.iter().zip(&b.scaled).fold(F::zero(), |acc, (&x, &y)| acc + (x - y) * (x - y)).sqrt()
+    }
+}
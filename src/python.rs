@@ -0,0 +1,106 @@
+//! Python bindings, exposing trees over the rows of NumPy arrays.
+//!
+//! The module is built with [maturin](https://www.maturin.rs), using
+//! the `pyproject.toml` at the root of the repository:
+//!
+//! ```python
+//! import numpy as np
+//! from vptree import VPTree
+//!
+//! data = np.random.rand(1000, 8)
+//! tree = VPTree.build(data, metric="manhattan", seed=7)
+//! neighbors = tree.query(data[0], k=5)
+//! nearby = tree.query_radius(data[0], r=0.5)
+//! ```
+//!
+//! Queries return the indices of the matching rows, nearest first.
+//! The GIL is released while trees are built and searched.
+
+use numpy::{PyReadonlyArray1, PyReadonlyArray2};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use array::{ArrayVPTree, Norm};
+use vptree::VPTreeBuilder;
+
+/// A vantage point tree over the rows of a 2-D array of floats.
+#[pyclass(name = "VPTree", module = "vptree", frozen)]
+pub struct PyVPTree {
+    tree: ArrayVPTree<'static>,
+}
+
+/// Return the norm named by `metric`, with `p` for Minkowski
+/// distances.
+fn norm(metric: &str, p: Option<f64>) -> PyResult<Norm> {
+    match (metric, p) {
+        ("euclidean", None) | ("l2", None) => Ok(Norm::L2),
+        ("manhattan", None) | ("l1", None) => Ok(Norm::L1),
+        ("chebyshev", None) | ("linf", None) => Ok(Norm::LInf),
+        ("minkowski", Some(p)) => Ok(Norm::Lp(p)),
+        ("minkowski", None) => Err(PyValueError::new_err("the minkowski metric needs p")),
+        (_, Some(_)) => Err(PyValueError::new_err("p is only used by the minkowski metric")),
+        _ => Err(PyValueError::new_err(format!("unknown metric {:?}", metric))),
+    }
+}
+
+#[pymethods]
+impl PyVPTree {
+    /// Build a tree over the rows of `data`.
+    ///
+    /// `metric` is one of "euclidean", "manhattan", "chebyshev" or
+    /// "minkowski", which needs `p`. Rows are copied into the tree.
+    #[staticmethod]
+    #[pyo3(signature = (data, metric = "euclidean", p = None, leaf_size = None, seed = None))]
+    fn build(py: Python<'_>, data: PyReadonlyArray2<'_, f64>, metric: &str, p: Option<f64>,
+             leaf_size: Option<usize>, seed: Option<u64>) -> PyResult<Self> {
+        let norm = norm(metric, p)?;
+        let data = data.as_array().to_owned();
+        let tree = py.detach(|| {
+            let mut builder = VPTreeBuilder::new();
+            if let Some(leaf_size) = leaf_size {
+                builder = builder.leaf_size(leaf_size);
+            }
+            if let Some(seed) = seed {
+                builder = builder.seed(seed);
+            }
+            ArrayVPTree::from_array(&builder, data, norm)
+        }).map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(PyVPTree { tree })
+    }
+
+    fn __len__(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Return the indices of the `k` rows closest to `x`, nearest
+    /// first.
+    #[pyo3(signature = (x, k = 1))]
+    fn query(&self, py: Python<'_>, x: PyReadonlyArray1<'_, f64>, k: usize) -> PyResult<Vec<usize>> {
+        let x = self.checked(x)?;
+        Ok(py.detach(|| self.tree.nearest_neighbors(x.view(), k, true)))
+    }
+
+    /// Return the indices of the rows within distance `r` of `x`,
+    /// nearest first.
+    fn query_radius(&self, py: Python<'_>, x: PyReadonlyArray1<'_, f64>, r: f64) -> PyResult<Vec<usize>> {
+        let x = self.checked(x)?;
+        Ok(py.detach(|| self.tree.within_radius(x.view(), r, true)))
+    }
+}
+
+impl PyVPTree {
+    /// Copy the query `x`, checking that it is as long as the rows.
+    fn checked(&self, x: PyReadonlyArray1<'_, f64>) -> PyResult<::ndarray::Array1<f64>> {
+        let x = x.as_array();
+        if x.len() != self.tree.dim() {
+            return Err(PyValueError::new_err(format!("expected a query of length {}, got {}", self.tree.dim(), x.len())));
+        }
+        Ok(x.to_owned())
+    }
+}
+
+/// The `vptree` Python module.
+#[pymodule]
+fn vptree(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyVPTree>()
+}
@@ -0,0 +1,98 @@
+//! Helpers for property testing metrics against trees.
+//!
+//! The strategies generate point sets as coordinate vectors of a given
+//! dimension, which can be mapped into any `MetricItem`. Each stresses
+//! trees differently: uniform points are the easy case, clusters give
+//! very uneven splits, and points on a low-dimensional manifold have
+//! distances concentrated in a narrow range. `assert_matches_brute_force`
+//! then checks that a tree over the items answers queries exactly as a
+//! linear scan does, which fails if the metric breaks the triangle
+//! inequality.
+//!
+//! ```rust
+//! extern crate proptest;
+//! extern crate vptree;
+//!
+//! use proptest::test_runner::TestRunner;
+//! use vptree::VPTree;
+//! use vptree::metrics::Manhattan;
+//! use vptree::testing::{assert_matches_brute_force, clusters};
+//!
+//! # fn main() {
+//! let mut runner = TestRunner::default();
+//! runner.run(&(clusters(3, 4, 1..200), clusters(3, 2, 10)), |(points, queries)| {
+//!     let points = points.into_iter().map(Manhattan).collect();
+//!     let queries: Vec<_> = queries.into_iter().map(Manhattan).collect();
+//!     assert_matches_brute_force(&VPTree::builder(), points, &queries, 5, 2.0);
+//!     Ok(())
+//! }).unwrap();
+//! # }
+//! ```
+
+use std::f64::consts::PI;
+use std::fmt::Debug;
+
+use proptest::collection::{vec, SizeRange};
+use proptest::strategy::Strategy;
+
+use distance::Distance;
+use index::{assert_same_answers, BruteForce};
+use vptree::{MetricItem, VPTreeBuilder};
+
+/// Generate `len` points with coordinates uniform in `[-1, 1)`.
+pub fn uniform(dim: usize, len: impl Into<SizeRange>) -> impl Strategy<Value = Vec<Vec<f64>>> {
+    vec(vec(-1.0..1.0, dim), len)
+}
+
+/// Generate `len` points around `clusters` centers, with coordinates
+/// normally distributed with unit variance around centers uniform in
+/// `[-10, 10)`.
+///
+/// # Panics
+///
+/// Panics if `clusters` is 0.
+pub fn clusters(dim: usize, clusters: usize, len: impl Into<SizeRange>) -> impl Strategy<Value = Vec<Vec<f64>>> {
+    assert!(clusters > 0, "points need at least one cluster");
+    let centers = vec(vec(-10.0..10.0, dim), clusters);
+    // Each coordinate is drawn from a pair of uniform variables, by the
+    // Box-Muller transform.
+    let offsets = vec((0..clusters, vec((f64::MIN_POSITIVE..=1.0, 0.0..1.0), dim)), len);
+    (centers, offsets).prop_map(|(centers, offsets)| {
+        offsets.into_iter().map(|(c, us)| {
+            centers[c].iter().zip(us).map(|(x, (u, v)): (&f64, (f64, f64))| x + (-2.0 * u.ln()).sqrt() * (2.0 * PI * v).cos()).collect()
+        }).collect()
+    })
+}
+
+/// Generate `len` points on an `intrinsic_dim`-dimensional torus,
+/// embedded in `dim` dimensions by a random linear map.
+///
+/// Each point has `intrinsic_dim` angles, whose cosines and sines are
+/// mapped to its coordinates, so every point lies within a bounded
+/// distance of the origin.
+pub fn manifold(dim: usize, intrinsic_dim: usize, len: impl Into<SizeRange>) -> impl Strategy<Value = Vec<Vec<f64>>> {
+    let embedding = vec(vec(-1.0..1.0, 2 * intrinsic_dim), dim);
+    let angles = vec(vec(0.0..2.0 * PI, intrinsic_dim), len);
+    (embedding, angles).prop_map(|(embedding, angles)| {
+        angles.into_iter().map(|t| {
+            let features: Vec<f64> = t.iter().flat_map(|&t| vec![t.cos(), t.sin()]).collect();
+            embedding.iter().map(|row| row.iter().zip(&features).map(|(a, f)| a * f).sum::<f64>()).collect()
+        }).collect()
+    })
+}
+
+/// Build a tree over `items` with `builder`, and assert that its `k`
+/// nearest neighbors and the items within `radius` of each of
+/// `queries` are at the same distances as those a linear scan finds.
+///
+/// Distances are compared rather than items, so that ties may be
+/// broken either way.
+///
+/// # Panics
+///
+/// Panics if any answer differs, or if the tree fails to build.
+pub fn assert_matches_brute_force<F, T>(builder: &VPTreeBuilder<F, T>, items: Vec<T>, queries: &[T], k: usize, radius: F)
+    where F: Distance + Debug, T: MetricItem<F> + Clone {
+    let tree = builder.build(items.clone());
+    assert_same_answers(&tree, &BruteForce::new(items), queries, k, radius);
+}
@@ -1,14 +1,61 @@
 //! Vantage-Point Trees are a data structure for fast
 //! k-nearest-neighbor searches.
-extern crate rand;
-
-use rand::distributions::{Range, IndependentSample};
-use std::borrow::Borrow;
 use std::collections::{BinaryHeap};
-use std::cmp::{Ord, PartialOrd, Ordering};
-use std::fmt::{Debug, Display};
-use num::Float;
-use order_stat::kth_by;
+use std::iter::FromIterator;
+use std::cmp::{Ord, PartialOrd, Ordering, Reverse};
+use distance::Distance;
+use rand::Rng;
+use rand::distributions::{Range, IndependentSample};
+use error::{BuildError, QueryError};
+
+mod builder;
+mod classify;
+mod compact;
+mod debug;
+mod diverse;
+mod dot;
+mod histogram;
+mod insert;
+mod iter;
+mod layout;
+mod lof;
+mod map;
+mod mst;
+mod neighborhood;
+mod persistent;
+#[cfg(feature = "raw")]
+pub mod raw;
+mod remove;
+mod scratch;
+mod seeding;
+mod shard;
+mod sliding;
+mod spill;
+mod stats;
+mod trace;
+mod vantage;
+mod view;
+mod weighted;
+
+pub use self::builder::VPTreeBuilder;
+pub use self::compact::CompactVPTree;
+pub use self::histogram::DistanceHistogram;
+use self::builder::BuildConfig;
+use self::trace::Tracer;
+pub use self::iter::Iter;
+pub use self::layout::NodeLayout;
+pub use self::lof::LocalOutlierFactors;
+pub use self::map::VPMap;
+pub use self::persistent::PersistentVPTree;
+pub use self::scratch::QueryScratch;
+pub use self::shard::ShardedVPTree;
+pub use self::sliding::{SlidingVPTree, TtlVPTree};
+pub use self::spill::SpillTree;
+pub use self::stats::TreeStats;
+pub use self::trace::{SearchTrace, TraceEvent};
+pub use self::vantage::VantageStrategy;
+pub use self::view::{NodeInfo, TreeView};
+pub use self::weighted::WeightedVPTree;
 
 /// Defines a metric for items in a metric space.
 ///
@@ -21,35 +68,196 @@ use order_stat::kth_by;
 /// A VP-Tree can only be constructed by a set forming a metric. If
 /// the `distance` function does not satisfy the metric conditions, a
 /// vp-tree constructed from the elements will not be correct.
-pub trait MetricItem<F: Float> {
+///
+/// Distances can be measured in any `Distance` type, including the
+/// integer types for discrete metrics.
+pub trait MetricItem<F: Distance> {
     /// Return the distance to another element in the metric space.
     ///
     /// The `distance` function must satisfy the metric properties.
     fn distance(&self, b: &Self) -> F;
 }
 
-struct TaggedItem<F: Float, T: MetricItem<F>> {
+/// An item stored in the tree.
+#[derive(Clone)]
+struct TaggedItem<F: Distance, T: MetricItem<F>> {
     pub item: T,
-    pub dist: F
+    /// Position of the item in the vector the tree was built from.
+    pub index: usize,
+    /// Distance to the vantage point of the enclosing node, during
+    /// and after construction.
+    pub dist: F,
+    /// Number of input items this item stands for: 1, unless
+    /// duplicates were collapsed into it.
+    pub count: u32,
+}
+
+impl<F: Distance, T: MetricItem<F>> TaggedItem<F, T> {
+    fn new(item: T, index: usize) -> Self {
+        TaggedItem { item, index, dist: F::zero(), count: 1 }
+    }
+
+    /// Collapse `dup`, at distance zero from this item, into it,
+    /// keeping whichever of the two came first.
+    fn absorb(&mut self, dup: TaggedItem<F, T>) {
+        self.count += dup.count;
+        if dup.index < self.index {
+            self.item = dup.item;
+            self.index = dup.index;
+        }
+    }
+
+    /// Return a lower bound on the distance from an object to this
+    /// item, given the distance `d` from the object to the vantage
+    /// point the item's `dist` was measured against.
+    ///
+    /// By the triangle inequality, this lets leaf scans skip exact
+    /// distance evaluations for items that can't qualify.
+    fn center_bound(&self, d: F) -> F {
+        d.abs_diff(self.dist)
+    }
+}
+
+/// Return true if `d` is a usable distance, which rules out negative
+/// distances and any, like NaN, that can't be compared with zero.
+fn valid_distance<F: Distance>(d: F) -> bool {
+    d >= F::zero()
+}
+
+/// Return the distance from `query` to `item`, panicking as the other
+/// queries do if the metric returns a negative or NaN distance.
+fn query_distance<F: Distance, T: MetricItem<F>>(query: &T, item: &T) -> F {
+    let d = T::distance(query, item);
+    if !valid_distance(d) {
+        panic!("failed to query VPTree: {}", QueryError::InvalidDistance);
+    }
+    d
+}
+
+/// Items to leave out of a nearest neighbor query.
+///
+/// Useful when the query point is itself stored in the tree, and
+/// would otherwise always be its own nearest neighbor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exclude {
+    /// Skip every item at distance exactly zero from the query.
+    Zero,
+    /// Skip the item at the given position in the vector the tree was
+    /// built from.
+    Index(usize),
+}
+
+impl Exclude {
+    fn keeps<F: Distance>(self, index: usize, dist: F) -> bool {
+        match self {
+            Exclude::Zero => dist != F::zero(),
+            Exclude::Index(i) => index != i,
+        }
+    }
+}
+
+/// How the distances from several query points to a single item are
+/// combined into one score, for aggregate nearest neighbor queries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregate {
+    /// Sum of the distances to every query point.
+    Sum,
+    /// Largest distance to any query point.
+    Max,
 }
 
-/// Return a randomly-selected vantage point.
-fn select_vantage_point<F: Float, T: MetricItem<F>>(items: &Vec<TaggedItem<F, T>>) -> usize {
-    // Randomly select a point.
-    let mut rng = rand::thread_rng();
+impl Aggregate {
+    fn combine<F: Distance, I: Iterator<Item = F>>(self, ds: I) -> F {
+        match self {
+            Aggregate::Sum => ds.fold(F::zero(), |acc, d| acc.saturating_add(d)),
+            Aggregate::Max => ds.fold(F::zero(), |acc, d| acc.larger(d)),
+        }
+    }
+}
+
+/// Split `items` around their median distance, returning the outer
+/// half.
+fn split_at_median<F: Distance, T: MetricItem<F>>(items: &mut Vec<TaggedItem<F, T>>) -> Vec<TaggedItem<F, T>> {
+    let n = items.len();
+
+    // We want to split the array into two as follows:
+    //
+    // The left array gets an extra element when the number of
+    // elements is odd.
+    //
+    // The last element of the left array is larger than all
+    // others, and smaller than eevery element in the right array.
+    //
+    // The selection is an introselect, falling back to median of
+    // medians, so it takes linear time even on adversarial or heavily
+    // duplicated distances.
+    if n > 1 {
+        items.select_nth_unstable_by((n-1)/2, |a, b| a.dist.partial_cmp(&b.dist).expect("distances were validated"));
+    }
+
+    items.split_off(n.div_ceil(2))
+}
 
+/// Split `items` around the median of a random sample of
+/// `sample_size` of their distances, returning the items further
+/// than the estimated median.
+fn split_at_sampled_median<F: Distance, T: MetricItem<F>, R: Rng>(items: &mut Vec<TaggedItem<F, T>>, sample_size: usize,
+                                                                rng: &mut R) -> Vec<TaggedItem<F, T>> {
     let range = Range::new(0, items.len());
-    let i = range.ind_sample(&mut rng);
-    let random_item = &items[i];
+    let mut sample: Vec<F> = (0..sample_size).map(|_| items[range.ind_sample(rng)].dist).collect();
+    let mu = *sample.select_nth_unstable_by((sample_size - 1) / 2, |a, b| a.partial_cmp(b).expect("distances were validated")).1;
+    split_around(items, mu)
+}
+
+/// Split `items` three ways around `mu`, returning the outer items.
+///
+/// Items closer than `mu` are inner and items further are outer. The
+/// items at exactly `mu`, of which there can be many on grids or
+/// quantized data, are divided between the two: they top up the inner
+/// items to half of the total, and the rest are outer. Sending them
+/// all to one side would leave the tree unbalanced, and as deep as
+/// the number of items in the worst case. Each subtree's distance
+/// bounds cover whichever ties it gets, so searches stay exact.
+fn split_around<F: Distance, T: MetricItem<F>>(items: &mut Vec<TaggedItem<F, T>>, mu: F) -> Vec<TaggedItem<F, T>> {
+    let half = items.len().div_ceil(2);
+    let (mut inner, rest): (Vec<_>, Vec<_>) = items.drain(..).partition(|ti| ti.dist < mu);
+    let (mut tied, mut outer): (Vec<_>, Vec<_>) = rest.into_iter().partition(|ti| ti.dist <= mu);
 
-    let min_d = (F::zero(), i);
+    // `mu` is the distance of one of the items, so if none are closer,
+    // at least one tie is made inner.
+    let to_inner = half.saturating_sub(inner.len()).min(tied.len());
+    outer.extend(tied.drain(to_inner..));
+    inner.append(&mut tied);
+    *items = inner;
+    outer
+}
 
-    // The vantage point will be the point furthest from the selected
-    // one.
-    items.iter().enumerate().fold(min_d, |acc, (i, y)| {
-        let d = T::distance(&random_item.item, &y.item);
-        if d > acc.0 { (d, i) } else { acc }
-    }).1
+/// Order the indices of a pair so the smaller comes first.
+fn ordered_pair<F>(i: usize, j: usize, d: F) -> (usize, usize, F) {
+    if i < j { (i, j, d) } else { (j, i, d) }
+}
+
+/// Collapse the duplicates among the items of a leaf bucket into one
+/// item each.
+///
+/// Duplicates are at the same distance from the leaf's center, so
+/// only items with equal stored distances need to be compared.
+fn collapse_bucket<F: Distance, T: MetricItem<F>>(items: &mut Vec<TaggedItem<F, T>>) {
+    items.sort_by(|a, b| a.dist.partial_cmp(&b.dist).expect("distances were validated"));
+    let mut kept: Vec<TaggedItem<F, T>> = Vec::with_capacity(items.len());
+    // Items at the same distance from the center are adjacent, and
+    // start at `run` in `kept`.
+    let mut run = 0;
+    for ti in items.drain(..) {
+        if kept.last().is_some_and(|last| last.dist != ti.dist) {
+            run = kept.len();
+        }
+        match kept[run..].iter_mut().find(|k| T::distance(&k.item, &ti.item) == F::zero()) {
+            Some(k) => k.absorb(ti),
+            None => kept.push(ti)
+        }
+    }
+    *items = kept;
 }
 
 /// Subtree split for non-leaf nodes.
@@ -57,135 +265,529 @@ fn select_vantage_point<F: Float, T: MetricItem<F>>(items: &Vec<TaggedItem<F, T>
 /// Vantage point trees in our implementation are left (inner) biased,
 /// so every non-leaf node has an inner subtree and an optional outer
 /// subtree.
-struct InnerNode<F: Float, N> {
+///
+/// Subtrees are referred to through `C`, by default their position in
+/// the tree's node arena.
+#[derive(Clone)]
+struct InnerNode<F: Distance, C = u32> {
+    /// Largest distance from the vantage point to any inner item.
     pub mu: F,
-    pub inner: Box<N>,
-    pub outer: Option<Box<N>>
+    /// Smallest distance from the vantage point to any inner item.
+    pub inner_min: F,
+    /// Smallest distance from the vantage point to any outer item.
+    pub outer_min: F,
+    /// Largest distance from the vantage point to any outer item.
+    pub outer_max: F,
+    /// The inner subtree.
+    pub inner: C,
+    /// The outer subtree, if any items lie outside `mu`.
+    pub outer: Option<C>
+}
+
+impl<F: Distance, C> InnerNode<F, C> {
+    /// Return lower bounds on the distance from an object to anything
+    /// in the inner and outer subtrees, given the distance `d` from
+    /// the object to the vantage point.
+    ///
+    /// Each subtree's items lie in a shell around the vantage point,
+    /// so the bounds may be negative, or zero for unsigned distances,
+    /// when the object is inside the shell.
+    fn bounds(&self, d: F) -> (F, F) {
+        (self.inner_min.saturating_sub(d).larger(d.saturating_sub(self.mu)),
+         self.outer_min.saturating_sub(d).larger(d.saturating_sub(self.outer_max)))
+    }
+}
+
+impl<F: Distance> InnerNode<F> {
+    fn inner_node<'a, T: MetricItem<F>>(&self, nodes: &'a [VPNode<F, T>]) -> &'a VPNode<F, T> {
+        &nodes[self.inner as usize]
+    }
+
+    fn outer_node<'a, T: MetricItem<F>>(&self, nodes: &'a [VPNode<F, T>]) -> Option<&'a VPNode<F, T>> {
+        self.outer.map(|i| &nodes[i as usize])
+    }
+}
+
+#[derive(Clone)]
+struct VPNode<F: Distance, T: MetricItem<F>> {
+    contents: Option<InnerNode<F>>,
+    /// The vantage point, tagged with its distance to the parent's
+    /// vantage point.
+    center: TaggedItem<F, T>,
+    /// For leaf nodes, the items other than the center, tagged with
+    /// their distance to the center. Empty for non-leaf nodes.
+    bucket: Vec<TaggedItem<F, T>>,
+    /// Distance from the center to the furthest item in the subtree.
+    radius: F,
+    /// Number of items in the subtree rooted at this node, including
+    /// the center.
+    size: usize,
 }
 
-struct VPNode<F: Float, T: MetricItem<F>> {
-    contents: Option<InnerNode<F, VPNode<F, T>>>,
-    center: T,
+/// A node under construction, with the items of its subtrees.
+struct Split<F: Distance, T: MetricItem<F>> {
+    node: VPNode<F, T>,
+    inner: Vec<TaggedItem<F, T>>,
+    outer: Vec<TaggedItem<F, T>>,
 }
 
 /// A `HeapElem` is a wrapper for items, used when collecting
 /// nearest-neighbor query results.
-struct HeapElem<'a, F: Float, T: 'a> {
+struct HeapElem<'a, F: Distance, T: 'a> {
     dist: F,
     item: &'a T
 }
 
-impl<'a, F: Float, T: 'a> HeapElem<'a, F, T> {
+impl<'a, F: Distance, T: 'a> HeapElem<'a, F, T> {
     fn new(d: F, i: &'a T) -> Self{
         HeapElem { dist: d, item: i }
     }
 }
 
-impl<'a, F: Float, T: 'a> PartialOrd for HeapElem<'a, F, T> {
+impl<'a, F: Distance, T: 'a> PartialOrd for HeapElem<'a, F, T> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.dist.partial_cmp(&other.dist)
+        Some(self.cmp(other))
     }
 }
 
-impl<'a, F: Float, T: 'a> PartialEq for HeapElem<'a, F, T> {
+impl<'a, F: Distance, T: 'a> PartialEq for HeapElem<'a, F, T> {
     fn eq(&self, other: &Self) -> bool {
         self.dist.eq(&other.dist)
     }
 }
 
-impl<'a, F: Float, T: 'a> Eq for HeapElem<'a, F, T> {
+impl<'a, F: Distance, T: 'a> Eq for HeapElem<'a, F, T> {
 }
 
-impl<'a, F: Float, T: 'a> Ord for HeapElem<'a, F, T> {
+impl<'a, F: Distance, T: 'a> Ord for HeapElem<'a, F, T> {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.partial_cmp(other).unwrap()
+        self.dist.partial_cmp(&other.dist).expect("metric returned a NaN distance")
+    }
+}
+
+/// Heap of the nearest items found so far, furthest on top.
+type NearestHeap<'a, F, T> = BinaryHeap<HeapElem<'a, F, TaggedItem<F, T>>>;
+
+/// Heap of the furthest items found so far, nearest on top.
+type FarthestHeap<'a, F, T> = BinaryHeap<Reverse<HeapElem<'a, F, TaggedItem<F, T>>>>;
+
+/// Push `ti` onto a heap of the `n` nearest items, if it is closer
+/// than the current furthest one.
+fn push_nearest<'a, F: Distance, T: MetricItem<F>>(heap: &mut NearestHeap<'a, F, T>, n: usize,
+                                                ti: &'a TaggedItem<F, T>, d: F) {
+    if heap.len() < n {
+        heap.push(HeapElem::new(d, ti));
+    } else if heap.peek().unwrap().dist > d {
+        heap.pop();
+        heap.push(HeapElem::new(d, ti));
     }
 }
 
-impl<F: Float, T: MetricItem<F>> VPNode<F, T> {
-    /// Creates a new node from the set of `items`.
-    pub fn new(mut items: Vec<TaggedItem<F, T>>) -> VPNode<F, T> {
-        if items.len() == 1 {
-            return VPNode { contents: None,
-                            center: items.pop().unwrap().item
-            };
+/// Push `ti` onto a heap of the `n` furthest items, if it is further
+/// than the current closest one.
+fn push_farthest<'a, F: Distance, T: MetricItem<F>>(heap: &mut FarthestHeap<'a, F, T>, n: usize,
+                                                 ti: &'a TaggedItem<F, T>, d: F) {
+    if heap.len() < n {
+        heap.push(Reverse(HeapElem::new(d, ti)));
+    } else if heap.peek().unwrap().0.dist < d {
+        heap.pop();
+        heap.push(Reverse(HeapElem::new(d, ti)));
+    }
+}
+
+impl<F: Distance, T: MetricItem<F>> VPNode<F, T> {
+    /// Build the nodes of a tree over `items`, in pre-order, so that
+    /// the root comes first.
+    ///
+    /// Construction works through an explicit stack of pending
+    /// subtrees rather than recursing, so that badly skewed trees
+    /// cannot overflow the call stack.
+    ///
+    /// The nodes are pushed onto `nodes`, which must be empty, so that
+    /// its allocation can be reused.
+    ///
+    /// Fails if any distance between items is negative or NaN.
+    pub fn build<R: Rng>(items: Vec<TaggedItem<F, T>>, builder: &VPTreeBuilder<F, T>,
+                         mut nodes: Vec<VPNode<F, T>>, rng: &mut R) -> Result<Vec<VPNode<F, T>>, BuildError> {
+        debug_assert!(nodes.is_empty());
+        let mut work = vec![(items, 1, None)];
+        while let Some((items, depth, parent)) = work.pop() {
+            let id = nodes.len() as u32;
+            let split = VPNode::split(items, builder, depth, rng)?;
+            let node = split.node;
+            if node.contents.is_some() {
+                if !split.outer.is_empty() {
+                    work.push((split.outer, depth + 1, Some((id, false))));
+                }
+                work.push((split.inner, depth + 1, Some((id, true))));
+            }
+
+            if let Some((p, is_inner)) = parent {
+                let contents = nodes[p as usize].contents.as_mut().expect("parent node has subtrees");
+                if is_inner {
+                    contents.inner = id;
+                } else {
+                    contents.outer = Some(id);
+                }
+            }
+            nodes.push(node);
         }
+        Ok(nodes)
+    }
 
-        let sel_index = select_vantage_point(&items);
+    /// Choose a vantage point for `items` at level `depth`, and split
+    /// off the remaining items.
+    ///
+    /// Returns a node without subtrees, along with the split distance
+    /// and the inner and outer items if the node should have any.
+    fn split<R: Rng>(mut items: Vec<TaggedItem<F, T>>, builder: &VPTreeBuilder<F, T>, depth: usize, rng: &mut R)
+                     -> Result<Split<F, T>, BuildError> {
+        let sel_index = if items.len() == 1 { 0 } else { builder.select_vantage_point(&items, rng)? };
 
-        let vp = items.swap_remove(sel_index);
+        let mut vp = items.swap_remove(sel_index);
 
         // Compute the new distance from the vantage point for all of
         // the items.
-        for mut ti in items.iter_mut() {
+        for ti in items.iter_mut() {
             ti.dist = T::distance(&ti.item, &vp.item);
+            if !valid_distance(ti.dist) {
+                return Err(BuildError::InvalidDistance);
+            }
         }
 
-        let n = items.len();
-
-        // We want to split the array into two as follows:
-        //
-        // The left array gets an extra element when the number of
-        // elements is odd.
-        //
-        // The last element of the left array is larger than all
-        // others, and smaller than eevery element in the right array.
-        if n > 1 {
-            kth_by(&mut items, (n-1)/2, |a, b| a.dist.partial_cmp(&b.dist).unwrap());
+        if builder.config.collapse_duplicates {
+            let (dups, rest): (Vec<_>, Vec<_>) = items.into_iter().partition(|ti| ti.dist == F::zero());
+            for dup in dups {
+                vp.absorb(dup);
+            }
+            items = rest;
         }
 
-        let right_items = items.split_off((n+1)/2);
+        let size = items.len() + 1;
+        let radius = items.iter().fold(F::zero(), |acc, ti| acc.larger(ti.dist));
 
-        match items.last().map(|x| x.dist) {
-            Some(dist) => {
-                let mu = dist;
-                let inner = Box::new(VPNode::new(items));
-                let outer = if right_items.is_empty() { None } else { Some(Box::new(VPNode::new(right_items))) };
-                VPNode { center: vp.item, contents: Some(InnerNode {
-                    mu: mu,
-                    inner: inner,
-                    outer: outer })}
-            },
-            None => {
-                VPNode { center: vp.item, contents: None }
+        // Small subtrees, and those at the depth limit, are kept as a
+        // flat bucket.
+        if size <= builder.config.leaf_size || builder.config.max_depth.is_some_and(|m| depth >= m) {
+            if builder.config.collapse_duplicates {
+                collapse_bucket(&mut items);
             }
+            let size = items.len() + 1;
+            let node = VPNode { contents: None, center: vp, bucket: items, radius, size };
+            return Ok(Split { node, inner: Vec::new(), outer: Vec::new() });
         }
+
+        let mut right_items = match builder.config.median_sample_size {
+            Some(s) if items.len() > s => split_at_sampled_median(&mut items, s, rng),
+            _ => split_at_median(&mut items)
+        };
+
+        // Duplicates are at the same distance, so keeping every item at
+        // the median distance inner keeps them together.
+        if builder.config.collapse_duplicates {
+            let mu = items.iter().fold(F::zero(), |hi, ti| hi.larger(ti.dist));
+            let (tied, rest): (Vec<_>, Vec<_>) = right_items.into_iter().partition(|ti| ti.dist <= mu);
+            items.extend(tied);
+            right_items = rest;
+        }
+
+        // Record the range of distances covered by each subtree. Every
+        // inner item is within `mu` of the vantage point, and every
+        // outer item at least `mu` away.
+        let (inner_min, mu) = items.iter().fold((F::infinity(), F::zero()), |(lo, hi), ti| (lo.smaller(ti.dist), hi.larger(ti.dist)));
+        let outer_min = right_items.iter().fold(F::infinity(), |lo, ti| lo.smaller(ti.dist));
+
+        // Subtree positions are filled in as the subtrees are built.
+        let contents = InnerNode { mu, inner_min, outer_min, outer_max: radius, inner: 0, outer: None };
+        let node = VPNode { contents: Some(contents), center: vp, bucket: Vec::new(), radius, size };
+        Ok(Split { node, inner: items, outer: right_items })
+    }
+
+    /// Return a lower bound on the distance from `obj` to anything in
+    /// this subtree, given the distance `d_parent` from `obj` to the
+    /// parent's vantage point.
+    ///
+    /// The distance from the parent's vantage point to this node's
+    /// center was stored at construction, so by the triangle
+    /// inequality this costs no distance evaluations. It lets whole
+    /// subtrees be skipped without measuring the distance to their
+    /// center.
+    fn parent_bound(&self, d_parent: F) -> F {
+        self.center.center_bound(d_parent).saturating_sub(self.radius)
     }
 
     /// Push the nearest neighbors of this tree onto the binary heap,
     /// replacing existing further-away elemtns as necessary.
-    pub fn nearest_neighbors<'b, 'a: 'b>(&'a self, obj: &T, n: usize,
-                                         heap: &'b mut BinaryHeap<HeapElem<'a, F, Self>>)  {
-        let d_center = T::distance(obj, &self.center);
+    pub fn nearest_neighbors<'a>(&'a self, nodes: &'a [Self], obj: &T, n: usize, heap: &mut NearestHeap<'a, F, T>) {
+        self.nearest_neighbors_filtered(nodes, obj, n, &|_, _| true, heap);
+    }
 
-        let elem = HeapElem::new(d_center, self);
+    /// Push the nearest neighbors of this tree onto the binary heap,
+    /// considering only the items for which `keep` returns true when
+    /// given the item and its distance to `obj`.
+    ///
+    /// Panics if the metric returns a negative or NaN distance to
+    /// `obj`.
+    pub fn nearest_neighbors_filtered<'a, P>(&'a self, nodes: &'a [Self], obj: &T, n: usize, keep: &P, heap: &mut NearestHeap<'a, F, T>)
+        where P: Fn(&TaggedItem<F, T>, F) -> bool {
+        if let Err(e) = self.nearest_neighbors_with_stack(nodes, obj, n, keep, heap, &mut Vec::new()) {
+            panic!("failed to query VPTree: {}", e);
+        }
+    }
 
-        // Push the element on if it is closer than the current furthest element.
-        if heap.len() < n {
-            heap.push(elem);
-        } else if heap.peek().unwrap().dist > elem.dist {
-            heap.pop();
-            heap.push(elem);
+    /// Like `nearest_neighbors_filtered`, but keeping the subtrees
+    /// waiting to be searched in `stack`, each with a lower bound on
+    /// the distance from `obj` to any of its elements.
+    ///
+    /// Stops and fails at the first negative or NaN distance, leaving
+    /// whatever was found so far in `heap`.
+    pub fn nearest_neighbors_with_stack<'a, P>(&'a self, nodes: &'a [Self], obj: &T, n: usize, keep: &P,
+                                               heap: &mut NearestHeap<'a, F, T>, stack: &mut Vec<(&'a Self, F)>)
+                                               -> Result<(), QueryError>
+        where P: Fn(&TaggedItem<F, T>, F) -> bool {
+        self.nearest_neighbors_traced(nodes, obj, n, keep, heap, stack, &mut ())
+    }
+
+    /// Like `nearest_neighbors_with_stack`, but reporting each step of
+    /// the search to `tracer`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn nearest_neighbors_traced<'a, P, R>(&'a self, nodes: &'a [Self], obj: &T, n: usize, keep: &P,
+                                              heap: &mut NearestHeap<'a, F, T>, stack: &mut Vec<(&'a Self, F)>,
+                                              tracer: &mut R) -> Result<(), QueryError>
+        where P: Fn(&TaggedItem<F, T>, F) -> bool, R: Tracer<'a, F, T> {
+        stack.clear();
+        stack.push((self, F::zero()));
+
+        while let Some((node, bound)) = stack.pop() {
+            // The heap may have filled up since the subtree was pushed.
+            if heap.len() >= n && heap.peek().unwrap().dist <= bound {
+                tracer.prune(nodes, node, bound, heap.peek().unwrap().dist);
+                continue;
+            }
+
+            let d_center = T::distance(obj, &node.center.item);
+            if !valid_distance(d_center) {
+                return Err(QueryError::InvalidDistance);
+            }
+            tracer.visit(&node.center, d_center);
+
+            // Push the element on if it is closer than the current furthest element.
+            if keep(&node.center, d_center) {
+                push_nearest(heap, n, &node.center, d_center);
+            }
+
+            for ti in &node.bucket {
+                // Skip items that can't beat the current furthest
+                // element, judging by their stored distance to the
+                // center.
+                if heap.len() >= n && ti.center_bound(d_center) >= heap.peek().unwrap().dist {
+                    tracer.skip(ti, ti.center_bound(d_center), heap.peek().unwrap().dist);
+                    continue;
+                }
+                let d = T::distance(obj, &ti.item);
+                if !valid_distance(d) {
+                    return Err(QueryError::InvalidDistance);
+                }
+                tracer.evaluate(ti, d);
+                if keep(ti, d) {
+                    push_nearest(heap, n, ti, d);
+                }
+            }
+
+            // If we have an inner or outer node.
+            if let Some(ref contents) = node.contents {
+                let (inner_bound, outer_bound) = contents.bounds(d_center);
+                let some_inner = Some(contents.inner_node(nodes));
+                let mut children = [(some_inner, inner_bound), (contents.outer_node(nodes), outer_bound)];
+
+                // Traverse the outer node first if we're outside the ring.
+                if d_center > contents.mu {
+                    children.swap(0, 1);
+                }
+
+                // Push in reverse, so that the first node is searched first.
+                for &(node_opt, bound) in children.iter().rev() {
+                    if let Some(child) = node_opt {
+                        stack.push((child, bound.larger(child.parent_bound(d_center))));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Push all pairs of elements of this subtree within `radius` of
+    /// each other.
+    pub fn pairs_within(&self, nodes: &[Self], radius: F, v: &mut Vec<(usize, usize, F)>) {
+        for (i, a) in self.bucket.iter().enumerate() {
+            // Distances to the center were stored at construction.
+            if a.dist < radius {
+                v.push(ordered_pair(self.center.index, a.index, a.dist));
+            }
+            for b in &self.bucket[i+1..] {
+                if b.center_bound(a.dist) >= radius {
+                    continue;
+                }
+                let d = T::distance(&a.item, &b.item);
+                if d < radius {
+                    v.push(ordered_pair(a.index, b.index, d));
+                }
+            }
         }
 
-        // If we have an inner or outer node.
         if let Some(ref contents) = self.contents {
-            let mu = contents.mu;
-            let some_inner = Some(&contents.inner);
-            let mut nodes = [(some_inner, true), (contents.outer.as_ref(), false)];
+            let mut near = Vec::new();
+            contents.inner_node(nodes).within_radius(nodes, &self.center.item, radius, &mut near);
+            if let Some(outer) = contents.outer_node(nodes) {
+                outer.within_radius(nodes, &self.center.item, radius, &mut near);
+            }
+            v.extend(near.into_iter().map(|x| ordered_pair(self.center.index, x.item.index, x.dist)));
 
-            // Traverse the outer node first if we're outside the ring.
-            if d_center > mu {
-                nodes.swap(0, 1);
+            contents.inner_node(nodes).pairs_within(nodes, radius, v);
+            if let Some(outer) = contents.outer_node(nodes) {
+                outer.pairs_within(nodes, radius, v);
+                contents.inner_node(nodes).cross_pairs_within(nodes, outer, radius, v);
+            }
+        }
+    }
+
+    /// Push all pairs of elements, one from this subtree and one from
+    /// `other`, within `radius` of each other.
+    fn cross_pairs_within(&self, nodes: &[Self], other: &Self, radius: F, v: &mut Vec<(usize, usize, F)>) {
+        // No pair can be close enough if the bounding balls are too
+        // far apart.
+        let d_centers = T::distance(&self.center.item, &other.center.item);
+        if d_centers.saturating_sub(self.radius).saturating_sub(other.radius) >= radius {
+            return;
+        }
+
+        // Descend into the larger of the two subtrees.
+        let (a, b) = if self.size >= other.size { (self, other) } else { (other, self) };
+
+        for ti in Some(&a.center).into_iter().chain(a.bucket.iter()) {
+            let mut near = Vec::new();
+            b.within_radius(nodes, &ti.item, radius, &mut near);
+            v.extend(near.into_iter().map(|x| ordered_pair(ti.index, x.item.index, x.dist)));
+        }
+
+        if let Some(ref contents) = a.contents {
+            contents.inner_node(nodes).cross_pairs_within(nodes, b, radius, v);
+            if let Some(outer) = contents.outer_node(nodes) {
+                outer.cross_pairs_within(nodes, b, radius, v);
+            }
+        }
+    }
+
+    /// Return the distance from `obj` to its `k`th nearest element in
+    /// this tree, or `None` if there are fewer than `k` elements.
+    pub fn kth_nearest_distance(&self, nodes: &[Self], obj: &T, k: usize) -> Option<F> {
+        if k == 0 {
+            return None;
+        }
+
+        let mut heap = BinaryHeap::with_capacity(k);
+        self.nearest_neighbors(nodes, obj, k, &mut heap);
+
+        if heap.len() < k {
+            None
+        } else {
+            heap.peek().map(|x| x.dist)
+        }
+    }
+
+    /// Push every element of this subtree that has `obj` among its
+    /// `k` nearest neighbors in `root`.
+    pub fn reverse_nearest_neighbors<'a>(&'a self, nodes: &'a [Self], root: &Self, obj: &T, k: usize, v: &mut Vec<&'a T>) {
+        for ti in Some(&self.center).into_iter().chain(self.bucket.iter()) {
+            // Each item is its own nearest neighbor, so look one further.
+            if let Some(d_k) = root.kth_nearest_distance(nodes, &ti.item, k + 1) {
+                if T::distance(obj, &ti.item) <= d_k {
+                    v.push(&ti.item);
+                }
             }
+        }
 
-            for &(node_opt, is_inner) in &nodes {
+        if let Some(ref contents) = self.contents {
+            contents.inner_node(nodes).reverse_nearest_neighbors(nodes, root, obj, k, v);
+            if let Some(outer) = contents.outer_node(nodes) {
+                outer.reverse_nearest_neighbors(nodes, root, obj, k, v);
+            }
+        }
+    }
+
+    /// Replace `best` with the element of this tree minimizing the
+    /// aggregate distance to `objs`, if it beats the current `best`.
+    pub fn aggregate_nearest_neighbor<'a>(&'a self, nodes: &'a [Self], objs: &[T], agg: Aggregate,
+                                          best: &mut Option<HeapElem<'a, F, TaggedItem<F, T>>>) {
+        let ds: Vec<F> = objs.iter().map(|q| query_distance(q, &self.center.item)).collect();
+        let score = agg.combine(ds.iter().cloned());
+
+        if best.as_ref().is_none_or(|b| score < b.dist) {
+            *best = Some(HeapElem::new(score, &self.center));
+        }
+
+        for ti in &self.bucket {
+            let lower = agg.combine(ds.iter().map(|&d| ti.center_bound(d)));
+            if best.as_ref().is_some_and(|b| lower >= b.dist) {
+                continue;
+            }
+            let score = agg.combine(objs.iter().map(|q| query_distance(q, &ti.item)));
+            if best.as_ref().is_none_or(|b| score < b.dist) {
+                *best = Some(HeapElem::new(score, ti));
+            }
+        }
+
+        if let Some(ref contents) = self.contents {
+            // Both aggregates are monotone, so combining the per-query
+            // lower bounds gives a lower bound for each subtree.
+            let inner_bound = agg.combine(ds.iter().map(|&d| contents.bounds(d).0.larger(F::zero())));
+            let outer_bound = agg.combine(ds.iter().map(|&d| contents.bounds(d).1.larger(F::zero())));
+            let mut children = [(Some(contents.inner_node(nodes)), inner_bound), (contents.outer_node(nodes), outer_bound)];
+
+            // Traverse the more promising subtree first.
+            if outer_bound < inner_bound {
+                children.swap(0, 1);
+            }
+
+            for &(node_opt, bound) in &children {
                 if let Some(node) = node_opt {
-                    let d_max = heap.peek().unwrap().dist;
-                    let possible_new_elem = (is_inner && d_max > d_center - mu) || (!is_inner && d_max > mu - d_center);
-                    if possible_new_elem {
-                        let x: &Self = node.borrow();
-                        x.nearest_neighbors(obj, n, heap);
+                    if best.as_ref().is_none_or(|b| bound < b.dist) {
+                        node.aggregate_nearest_neighbor(nodes, objs, agg, best);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Push the farthest neighbors of this tree onto the min-heap,
+    /// replacing existing closer elements as necessary.
+    pub fn farthest_neighbors<'a>(&'a self, nodes: &'a [Self], obj: &T, n: usize, heap: &mut FarthestHeap<'a, F, T>) {
+        let d_center = query_distance(obj, &self.center.item);
+
+        // Push the element on if it is further than the current closest element.
+        push_farthest(heap, n, &self.center, d_center);
+
+        for ti in &self.bucket {
+            // Skip items that can't be further than the current
+            // closest element.
+            if heap.len() >= n && d_center.saturating_add(ti.dist) <= heap.peek().unwrap().0.dist {
+                continue;
+            }
+            push_farthest(heap, n, ti, query_distance(obj, &ti.item));
+        }
+
+        if let Some(ref contents) = self.contents {
+            // Every inner item is within `mu` of the center, and every
+            // outer item within `radius`, so these bound the distance
+            // from `obj` to anything in each subtree. The outer subtree
+            // is more likely to hold far elements, so visit it first.
+            let children = [(contents.outer_node(nodes), d_center.saturating_add(contents.outer_max)),
+                            (Some(contents.inner_node(nodes)), d_center.saturating_add(contents.mu))];
+
+            for &(node_opt, d_upper) in &children {
+                if let Some(node) = node_opt {
+                    if heap.len() < n || heap.peek().unwrap().0.dist < d_upper {
+                        node.farthest_neighbors(nodes, obj, n, heap);
                     }
                 }
             }
@@ -193,36 +795,266 @@ impl<F: Float, T: MetricItem<F>> VPNode<F, T> {
     }
 
     /// Return all elements within a given radius of the node.
-    pub fn within_radius<'a, 'b: 'a>(&'b self, obj: &T, radius: F, v: &mut Vec<HeapElem<'a, F, Self>>) {
-        let d_center = T::distance(obj, &self.center);
+    ///
+    /// Panics if the metric returns a negative or NaN distance to
+    /// `obj`.
+    pub fn within_radius<'a>(&'a self, nodes: &'a [Self], obj: &T, radius: F, v: &mut Vec<HeapElem<'a, F, TaggedItem<F, T>>>) {
+        if let Err(e) = self.within_radius_with_stack(nodes, obj, radius, v, &mut Vec::new()) {
+            panic!("failed to query VPTree: {}", e);
+        }
+    }
 
-        // Push the element on if it is closer than the current furthest element.
+    /// Like `within_radius`, but keeping the subtrees waiting to be
+    /// searched in `stack`.
+    ///
+    /// Stops and fails at the first negative or NaN distance, leaving
+    /// whatever was found so far in `v`.
+    pub fn within_radius_with_stack<'a>(&'a self, nodes: &'a [Self], obj: &T, radius: F,
+                                        v: &mut Vec<HeapElem<'a, F, TaggedItem<F, T>>>, stack: &mut Vec<&'a Self>)
+                                        -> Result<(), QueryError> {
+        self.within_radius_traced(nodes, obj, radius, v, stack, &mut ())
+    }
+
+    /// Like `within_radius_with_stack`, but reporting each step of the
+    /// search to `tracer`.
+    pub fn within_radius_traced<'a, R>(&'a self, nodes: &'a [Self], obj: &T, radius: F,
+                                       v: &mut Vec<HeapElem<'a, F, TaggedItem<F, T>>>, stack: &mut Vec<&'a Self>,
+                                       tracer: &mut R) -> Result<(), QueryError>
+        where R: Tracer<'a, F, T> {
+        stack.clear();
+        stack.push(self);
+
+        while let Some(node) = stack.pop() {
+            let d_center = T::distance(obj, &node.center.item);
+            if !valid_distance(d_center) {
+                return Err(QueryError::InvalidDistance);
+            }
+            tracer.visit(&node.center, d_center);
+
+            if d_center < radius {
+                v.push(HeapElem::new(d_center, &node.center));
+            }
+
+            for ti in &node.bucket {
+                if ti.center_bound(d_center) >= radius {
+                    tracer.skip(ti, ti.center_bound(d_center), radius);
+                    continue;
+                }
+                let d = T::distance(obj, &ti.item);
+                if !valid_distance(d) {
+                    return Err(QueryError::InvalidDistance);
+                }
+                tracer.evaluate(ti, d);
+                if d < radius {
+                    v.push(HeapElem::new(d, ti));
+                }
+            }
+
+            // If we have an inner or outer node.
+            if let Some(ref contents) = node.contents {
+                let (inner_bound, outer_bound) = contents.bounds(d_center);
+                let children = [(Some(contents.inner_node(nodes)), inner_bound), (contents.outer_node(nodes), outer_bound)];
+                for &(child, bound) in &children {
+                    if let Some(child) = child {
+                        let bound = bound.larger(child.parent_bound(d_center));
+                        if radius > bound {
+                            stack.push(child);
+                        } else {
+                            tracer.prune(nodes, child, bound, radius);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Return true if any element is within a given radius of the
+    /// node, stopping at the first one found.
+    pub fn any_within_radius(&self, nodes: &[Self], obj: &T, radius: F) -> bool {
+        let d_center = query_distance(obj, &self.center.item);
         if d_center < radius {
-            v.push(HeapElem::new(d_center, &self));
+            return true;
+        }
+
+        if self.bucket.iter().any(|ti| ti.center_bound(d_center) < radius && query_distance(obj, &ti.item) < radius) {
+            return true;
         }
 
-        // If we have an inner or outer node.
         if let Some(ref contents) = self.contents {
-            let mu = contents.mu;
-            let some_inner = Some(&contents.inner);
-            let mut nodes = [(some_inner, true), (contents.outer.as_ref(), false)];
+            let (inner_bound, outer_bound) = contents.bounds(d_center);
+            let some_inner = Some(contents.inner_node(nodes));
+            let mut children = [(some_inner, inner_bound), (contents.outer_node(nodes), outer_bound)];
 
             // Traverse the outer node first if we're outside the ring.
-            if d_center > mu {
-                nodes.swap(0, 1);
+            if d_center > contents.mu {
+                children.swap(0, 1);
             }
 
-            for &(node_opt, is_inner) in &nodes {
+            for &(node_opt, bound) in &children {
                 if let Some(node) = node_opt {
-                    let possible_new_elem = (is_inner && radius > d_center - mu) || (!is_inner && radius > mu - d_center);
-                    if possible_new_elem {
-                        let x: &Self = node.borrow();
-                        x.within_radius(obj, radius, v);
+                    let possible_elem = radius > bound && radius > node.parent_bound(d_center);
+                    if possible_elem && node.any_within_radius(nodes, obj, radius) {
+                        return true;
                     }
                 }
             }
         }
+        false
+    }
+
+    /// Count the elements within a given radius of the node.
+    ///
+    /// Subtrees lying entirely inside the radius are counted using
+    /// their stored size, without visiting any of their elements.
+    pub fn count_within_radius(&self, nodes: &[Self], obj: &T, radius: F) -> usize {
+        let d_center = query_distance(obj, &self.center.item);
+        if d_center.saturating_add(self.radius) < radius {
+            return self.size;
+        }
+
+        let mut count = if d_center < radius { 1 } else { 0 };
+        count += self.bucket.iter().filter(|ti| {
+            ti.center_bound(d_center) < radius && query_distance(obj, &ti.item) < radius
+        }).count();
+
+        if let Some(ref contents) = self.contents {
+            let (inner_bound, outer_bound) = contents.bounds(d_center);
+            let inner = contents.inner_node(nodes);
+            if radius > inner_bound && radius > inner.parent_bound(d_center) {
+                count += inner.count_within_radius(nodes, obj, radius);
+            }
+            if let Some(outer) = contents.outer_node(nodes) {
+                if radius > outer_bound && radius > outer.parent_bound(d_center) {
+                    count += outer.count_within_radius(nodes, obj, radius);
+                }
+            }
+        }
+        count
+    }
+}
+
+/// A subtree taking part in a dual-tree traversal: either an entire
+/// node, along with the arena holding it, or a single item.
+enum DualView<'a, F: Distance + 'a, T: MetricItem<F> + 'a> {
+    Whole(&'a [VPNode<F, T>], &'a VPNode<F, T>),
+    Item(&'a TaggedItem<F, T>),
+}
 
+impl<'a, F: Distance, T: MetricItem<F>> Clone for DualView<'a, F, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, F: Distance, T: MetricItem<F>> Copy for DualView<'a, F, T> {}
+
+impl<'a, F: Distance, T: MetricItem<F>> DualView<'a, F, T> {
+    /// The item at the center of the view's bounding ball.
+    fn center(self) -> &'a TaggedItem<F, T> {
+        match self {
+            DualView::Whole(_, n) => &n.center,
+            DualView::Item(ti) => ti
+        }
+    }
+
+    fn radius(self) -> F {
+        match self {
+            DualView::Whole(_, n) => n.radius,
+            DualView::Item(_) => F::zero()
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            DualView::Whole(_, n) => n.size,
+            DualView::Item(_) => 1
+        }
+    }
+
+    /// Split a whole node into its items and its subtrees.
+    fn children(self) -> Vec<Self> {
+        let mut v = Vec::new();
+        if let DualView::Whole(nodes, n) = self {
+            v.push(DualView::Item(&n.center));
+            v.extend(n.bucket.iter().map(DualView::Item));
+            if let Some(ref contents) = n.contents {
+                v.push(DualView::Whole(nodes, contents.inner_node(nodes)));
+                if let Some(outer) = contents.outer_node(nodes) {
+                    v.push(DualView::Whole(nodes, outer));
+                }
+            }
+        }
+        v
+    }
+}
+
+/// State for a dual-tree all-k-nearest-neighbors search.
+///
+/// Alongside the candidate heap for every query point, we keep a
+/// bound for every query subtree: no point in the subtree is still
+/// looking for neighbors further away than the bound. A pair of
+/// subtrees whose bounding balls are further apart than that can be
+/// skipped entirely.
+struct DualKnn<'a, F: Distance + 'a, T: MetricItem<F> + 'a> {
+    k: usize,
+    heaps: Vec<NearestHeap<'a, F, T>>,
+    subtree_bounds: Vec<F>,
+}
+
+impl<'a, F: Distance, T: MetricItem<F>> DualKnn<'a, F, T> {
+    fn new(n: usize, k: usize) -> Self {
+        DualKnn {
+            k,
+            heaps: (0..n).map(|_| BinaryHeap::with_capacity(k)).collect(),
+            subtree_bounds: vec![F::infinity(); n]
+        }
+    }
+
+    /// Distance to the current `k`th candidate of a query point.
+    fn point_bound(&self, index: usize) -> F {
+        let heap = &self.heaps[index];
+        if heap.len() < self.k { F::infinity() } else { heap.peek().unwrap().dist }
+    }
+
+    fn bound(&self, q: DualView<'a, F, T>) -> F {
+        match q {
+            // Subtree bounds are keyed by the index of the center.
+            DualView::Whole(_, n) => self.subtree_bounds[n.center.index],
+            DualView::Item(ti) => self.point_bound(ti.index)
+        }
+    }
+
+    fn traverse(&mut self, q: DualView<'a, F, T>, d: DualView<'a, F, T>) {
+        let (qc, dc) = (q.center(), d.center());
+        let d_centers = T::distance(&qc.item, &dc.item);
+        if d_centers.saturating_sub(q.radius()).saturating_sub(d.radius()) >= self.bound(q) {
+            return;
+        }
+
+        let split_query = match (q, d) {
+            (DualView::Item(_), DualView::Item(_)) => {
+                push_nearest(&mut self.heaps[qc.index], self.k, dc, d_centers);
+                return;
+            },
+            (DualView::Whole(..), DualView::Item(_)) => true,
+            (DualView::Item(_), DualView::Whole(..)) => false,
+            _ => q.size() >= d.size()
+        };
+
+        if split_query {
+            let children = q.children();
+            for &child in &children {
+                self.traverse(child, d);
+            }
+
+            let bound = children.iter().fold(F::zero(), |acc, &c| acc.larger(self.bound(c)));
+            self.subtree_bounds[qc.index] = bound;
+        } else {
+            for child in d.children() {
+                self.traverse(q, child);
+            }
+        }
     }
 }
 
@@ -230,52 +1062,337 @@ impl<F: Float, T: MetricItem<F>> VPNode<F, T> {
 ///
 /// A vantage-point tree stores a set of points to be later queried
 /// against.
-pub struct VPTree<F: Float, T: MetricItem<F>> {
-    root: VPNode<F, T>
+#[derive(Clone)]
+pub struct VPTree<F: Distance, T: MetricItem<F>> {
+    /// Every node of the tree, with the root first and every node
+    /// ahead of its subtrees. Subtrees refer to each other by
+    /// position.
+    nodes: Vec<VPNode<F, T>>,
+    /// The options the tree was built with, for rebuilding.
+    config: BuildConfig,
+    /// Number of items inserted or removed since the tree was last
+    /// built.
+    modified: usize,
 }
 
-impl<F: Float, T: MetricItem<F>> VPTree<F, T> {
+impl<F: Distance, T: MetricItem<F>> VPTree<F, T> {
     /// Construct a new vantage point tree from a set of elements.
     ///
-    /// Returns `None` if `items` is an empty vector.
+    /// `items` may be empty, in which case every query on the tree
+    /// comes back empty.
     ///
     /// `new` makes no effort to check that the `MetricItem` trait
     /// implementation actually defines a matric. If the metric is not
     /// defined correctly, the resulting tree may not yield correct
     /// answers for later queries.
-    pub fn new(items: Vec<T>) -> Option<VPTree<F, T>> {
-        let n = items.len();
-        if n > 0 {
-            let tagged_items: Vec<TaggedItem<F, T>> = items.into_iter()
-                .map(|x| TaggedItem { item: x, dist: F::zero() }).collect();
-            Some(VPTree { root: VPNode::new(tagged_items) })
-        } else {
-            None
+    ///
+    /// # Panics
+    ///
+    /// Panics if the metric returns a negative or NaN distance during
+    /// construction. Use `try_new` to handle this as an error instead.
+    pub fn new(items: Vec<T>) -> VPTree<F, T> {
+        VPTreeBuilder::new().build(items)
+    }
+
+    /// Construct a new vantage point tree from a set of elements,
+    /// reporting construction problems as errors.
+    ///
+    /// Unlike `new`, returns `BuildError::Empty` if `items` is empty,
+    /// and `BuildError::InvalidDistance` instead of panicking if the
+    /// metric returns a negative or NaN distance.
+    pub fn try_new(items: Vec<T>) -> Result<VPTree<F, T>, BuildError> {
+        VPTreeBuilder::new().try_build(items)
+    }
+
+    /// Construct a new vantage point tree from a set of elements,
+    /// making every random choice from `seed`.
+    ///
+    /// Building from the same items with the same seed always yields
+    /// the same tree.
+    pub fn new_seeded(items: Vec<T>, seed: u64) -> VPTree<F, T> {
+        VPTreeBuilder::new().seed(seed).build(items)
+    }
+
+    /// Construct a new vantage point tree from a set of elements,
+    /// drawing every random choice from `rng`.
+    pub fn new_with_rng<R: Rng>(items: Vec<T>, rng: &mut R) -> VPTree<F, T> {
+        VPTreeBuilder::new().build_with_rng(items, rng)
+    }
+
+    /// Return a builder for constructing a tree with non-default
+    /// options.
+    pub fn builder() -> VPTreeBuilder<F, T> {
+        VPTreeBuilder::new()
+    }
+
+    /// Return the number of items in the tree.
+    pub fn len(&self) -> usize {
+        self.root().map_or(0, |root| root.size)
+    }
+
+    /// Return true if the tree holds no items.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Return the number of levels in the tree.
+    ///
+    /// An empty tree has depth 0, and a tree with a single item has
+    /// depth 1.
+    pub fn depth(&self) -> usize {
+        // Every node is stored ahead of its subtrees, so its depth is
+        // known before its children are reached.
+        let mut depths = vec![1; self.nodes.len()];
+        for (i, node) in self.nodes.iter().enumerate() {
+            if let Some(ref c) = node.contents {
+                depths[c.inner as usize] = depths[i] + 1;
+                if let Some(outer) = c.outer {
+                    depths[outer as usize] = depths[i] + 1;
+                }
+            }
+        }
+        depths.into_iter().max().unwrap_or(0)
+    }
+
+    /// Return the number of leaf nodes in the tree.
+    pub fn leaf_count(&self) -> usize {
+        self.nodes.iter().filter(|node| node.contents.is_none()).count()
+    }
+
+    /// Return the root node, or `None` for an empty tree.
+    fn root(&self) -> Option<&VPNode<F, T>> {
+        self.nodes.first()
+    }
+
+    /// Consume the tree, returning the items in the order they were
+    /// passed in.
+    pub fn into_items(self) -> Vec<T> {
+        let mut items: Vec<Option<T>> = (0..self.len()).map(|_| None).collect();
+        for node in self.nodes {
+            for ti in Some(node.center).into_iter().chain(node.bucket) {
+                items[ti.index] = Some(ti.item);
+            }
         }
+        items.into_iter().map(|x| x.expect("every index appears once in the tree")).collect()
+    }
+
+    /// Consume the tree, returning the items sorted by ascending
+    /// distance from `query`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the metric returns a negative or NaN distance to
+    /// `query`.
+    pub fn into_sorted_by_distance_from(self, query: &T) -> Vec<T> {
+        let mut items: Vec<(F, T)> = self.into_items().into_iter().map(|x| (query_distance(query, &x), x)).collect();
+        items.sort_by(|a, b| a.0.partial_cmp(&b.0).expect("distances were validated"));
+        items.into_iter().map(|(_, x)| x).collect()
+    }
+
+    /// Return the position of a node holding an item at distance zero
+    /// from `item`, along with the item's position in the node's
+    /// bucket, or `None` if the item is the node's center.
+    fn find_position(&self, item: &T) -> Option<(usize, Option<usize>)> {
+        let mut stack = if self.nodes.is_empty() { Vec::new() } else { vec![0] };
+        while let Some(id) = stack.pop() {
+            let node = &self.nodes[id];
+            let d_center = T::distance(item, &node.center.item);
+            if d_center == F::zero() {
+                return Some((id, None));
+            }
+            for (i, ti) in node.bucket.iter().enumerate() {
+                if ti.center_bound(d_center) <= F::zero() && T::distance(item, &ti.item) == F::zero() {
+                    return Some((id, Some(i)));
+                }
+            }
+
+            if let Some(ref c) = node.contents {
+                let (inner_bound, outer_bound) = c.bounds(d_center);
+                for (child, bound) in Some((c.inner, inner_bound)).into_iter().chain(c.outer.map(|o| (o, outer_bound))) {
+                    if bound <= F::zero() && self.nodes[child as usize].parent_bound(d_center) <= F::zero() {
+                        stack.push(child as usize);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Recompute the size of every subtree after items were dropped.
+    fn recompute_sizes(&mut self) {
+        // Every node is stored ahead of its subtrees, so walking
+        // backwards finds each subtree's size before its parent's.
+        for id in (0..self.nodes.len()).rev() {
+            let children = self.nodes[id].contents.as_ref()
+                .map_or(0, |c| self.nodes[c.inner as usize].size + c.outer.map_or(0, |o| self.nodes[o as usize].size));
+            let node = &mut self.nodes[id];
+            node.size = 1 + node.bucket.len() + children;
+        }
+    }
+
+    /// Number the items from 0 up again, keeping their order, after
+    /// some of the `n` original items were dropped.
+    fn renumber(&mut self, n: usize) {
+        let mut new_index = vec![0; n];
+        for ti in self.nodes.iter().flat_map(|node| Some(&node.center).into_iter().chain(node.bucket.iter())) {
+            new_index[ti.index] = 1;
+        }
+        let mut next = 0;
+        for i in new_index.iter_mut() {
+            let present = *i;
+            *i = next;
+            next += present;
+        }
+        for node in &mut self.nodes {
+            for ti in Some(&mut node.center).into_iter().chain(node.bucket.iter_mut()) {
+                ti.index = new_index[ti.index];
+            }
+        }
+    }
+
+    /// Return every item of the tree, in storage order.
+    fn items(&self) -> Vec<&TaggedItem<F, T>> {
+        self.nodes.iter().flat_map(|node| Some(&node.center).into_iter().chain(node.bucket.iter())).collect()
+    }
+
+    /// Return every item, by its position in the vector the tree was
+    /// built from.
+    pub(crate) fn items_by_position(&self) -> Vec<&T> {
+        let mut items = vec![None; self.len()];
+        for ti in self.items() {
+            items[ti.index] = Some(&ti.item);
+        }
+        items.into_iter().map(|x| x.expect("positions run from 0 to the number of items")).collect()
+    }
+
+    /// Find the `k` points in the tree closest to `query`, as pairs of
+    /// a position and a distance, sorted by ascending distance.
+    pub(crate) fn nearest_positions(&self, query: &T, k: usize) -> Vec<(usize, F)> {
+        let mut heap = BinaryHeap::with_capacity(k);
+        if let (Some(root), true) = (self.root(), k > 0) {
+            root.nearest_neighbors(&self.nodes, query, k, &mut heap);
+        }
+        heap.into_sorted_vec().into_iter().map(|x| (x.item.index, x.dist)).collect()
+    }
+
+    /// Return the number of input items each item stands for, by its
+    /// position in the vector the tree was built from.
+    pub(crate) fn item_counts(&self) -> Vec<usize> {
+        let mut counts = vec![0; self.len()];
+        for ti in self.items() {
+            counts[ti.index] = ti.count as usize;
+        }
+        counts
     }
 
     /// Return all elements with a given radius of the target.
     ///
     /// If `sorted` is true, the elements are sorted by ascending
     /// distance from the query point,
+    ///
+    /// # Panics
+    ///
+    /// Panics if the metric returns a negative or NaN distance to
+    /// `query`. Use `try_within_radius` to handle this instead.
     pub fn within_radius(&self, query: &T, radius: F, sorted: bool) -> Vec<&T> {
+        let mut v = Vec::new();
+        self.within_radius_into(query, radius, sorted, &mut v);
+        v
+    }
+
+    /// Like `within_radius`, but returns `QueryError::InvalidDistance`
+    /// instead of panicking if the metric returns a negative or NaN
+    /// distance to `query`.
+    ///
+    /// The search stops at the first such distance.
+    pub fn try_within_radius(&self, query: &T, radius: F, sorted: bool) -> Result<Vec<&T>, QueryError> {
+        let mut elems = Vec::new();
+        if let Some(root) = self.root() {
+            root.within_radius_with_stack(&self.nodes, query, radius, &mut elems, &mut Vec::new())?;
+        }
+
+        if sorted {
+            elems.sort();
+        }
+        Ok(elems.into_iter().map(|x| &x.item.item).collect())
+    }
+
+    /// Like `within_radius`, but clears `out` and writes the elements
+    /// into it instead of allocating a new vector.
+    pub fn within_radius_into<'a>(&'a self, query: &T, radius: F, sorted: bool, out: &mut Vec<&'a T>) {
         let mut elems = Vec::new();
-        self.root.within_radius(query, radius, &mut elems);
+        if let Some(root) = self.root() {
+            root.within_radius(&self.nodes, query, radius, &mut elems);
+        }
 
         if sorted {
             elems.sort();
         }
 
-        elems.into_iter().map(|x| &x.item.center).collect()
+        out.clear();
+        out.extend(elems.into_iter().map(|x| &x.item.item));
     }
 
-    /// Find the closets point in tree to `query`.
-    pub fn nearest_neighbor(&self, query: &T) -> &T {
-        let mut heap = BinaryHeap::with_capacity(1);
-        self.root.nearest_neighbors(query, 1, &mut heap);
+    /// Return the number of elements within a given radius of the
+    /// target.
+    ///
+    /// Equivalent to `within_radius(query, radius, false).len()`, but
+    /// does not allocate, and skips over subtrees lying entirely
+    /// within the radius.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the metric returns a negative or NaN distance to
+    /// `query`.
+    pub fn count_within_radius(&self, query: &T, radius: F) -> usize {
+        self.root().map_or(0, |root| root.count_within_radius(&self.nodes, query, radius))
+    }
+
+    /// Return true if there is at least one element within a given
+    /// radius of the target.
+    ///
+    /// Equivalent to `!within_radius(query, radius, false).is_empty()`,
+    /// but the search stops as soon as a single element is found.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the metric returns a negative or NaN distance to
+    /// `query`.
+    pub fn any_within_radius(&self, query: &T, radius: F) -> bool {
+        self.root().is_some_and(|root| root.any_within_radius(&self.nodes, query, radius))
+    }
+
+    /// Return true if the tree holds an item at distance zero from
+    /// `item`.
+    ///
+    /// Only subtrees that could hold such an item are searched, and
+    /// the search stops at the first one found, so this is much
+    /// cheaper than a nearest neighbor search.
+    pub fn contains(&self, item: &T) -> bool {
+        self.find(item).is_some()
+    }
+
+    /// Return an item of the tree at distance zero from `item`, if
+    /// there is one.
+    ///
+    /// Searches like `contains`.
+    pub fn find(&self, item: &T) -> Option<&T> {
+        self.find_tagged(item).map(|ti| &ti.item)
+    }
 
-        let he = heap.pop().unwrap();
-        &he.item.center
+    /// Return an item at distance zero from `item`, tagged.
+    fn find_tagged(&self, item: &T) -> Option<&TaggedItem<F, T>> {
+        self.find_position(item).map(|(id, pos)| {
+            let node = &self.nodes[id];
+            pos.map_or(&node.center, |i| &node.bucket[i])
+        })
+    }
+
+    /// Find the closets point in tree to `query`.
+    ///
+    /// Returns `None` if the tree is empty.
+    pub fn nearest_neighbor(&self, query: &T) -> Option<&T> {
+        self.nearest_neighbors(query, 1, false).pop()
     }
 
     /// Find the `k` points in the tree closest to `query`.
@@ -284,45 +1401,302 @@ impl<F: Float, T: MetricItem<F>> VPTree<F, T> {
     /// distance to `query`.  If `k` is larger than the total number
     /// of points in the tree, all of the points in the tree are
     /// returned.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the metric returns a negative or NaN distance to
+    /// `query`. Use `try_nearest_neighbors` to handle this instead.
     pub fn nearest_neighbors(&self, query: &T, k: usize, sorted: bool) -> Vec<&T> {
+        let mut v = Vec::new();
+        self.nearest_neighbors_into(query, k, sorted, &mut v);
+        v
+    }
+
+    /// Like `nearest_neighbors`, but returns
+    /// `QueryError::InvalidDistance` instead of panicking if the
+    /// metric returns a negative or NaN distance to `query`.
+    ///
+    /// The search stops at the first such distance.
+    pub fn try_nearest_neighbors(&self, query: &T, k: usize, sorted: bool) -> Result<Vec<&T>, QueryError> {
         let mut heap = BinaryHeap::with_capacity(k);
-        self.root.nearest_neighbors(query, k, &mut heap);
+        if let (Some(root), true) = (self.root(), k > 0) {
+            root.nearest_neighbors_with_stack(&self.nodes, query, k, &|_, _| true, &mut heap, &mut Vec::new())?;
+        }
 
         let v = if sorted {
             heap.into_sorted_vec()
         } else {
             heap.into_vec()
         };
-        v.into_iter().map(|x| &x.item.center).collect()
+        Ok(v.into_iter().map(|x| &x.item.item).collect())
+    }
+
+    /// Like `nearest_neighbors`, but clears `out` and writes the
+    /// points into it instead of allocating a new vector.
+    pub fn nearest_neighbors_into<'a>(&'a self, query: &T, k: usize, sorted: bool, out: &mut Vec<&'a T>) {
+        let mut heap = BinaryHeap::with_capacity(k);
+        if let (Some(root), true) = (self.root(), k > 0) {
+            root.nearest_neighbors(&self.nodes, query, k, &mut heap);
+        }
 
+        let v = if sorted {
+            heap.into_sorted_vec()
+        } else {
+            heap.into_vec()
+        };
+        out.clear();
+        out.extend(v.into_iter().map(|x| &x.item.item));
     }
-}
 
-impl<F: Float + Display, T: MetricItem<F> + Debug> VPNode<F, T> {
-    pub fn dump(&self, prefix: &str) -> String {
-        let mut s: String = format!("{}elem: {:?}", prefix, self.center);
-        if let Some(ref c) = self.contents {
-            s += &format!(", mu: {}\n", c.mu);
-            let new_prefix = format!("{}  ", prefix);
+    /// Like `nearest_neighbors`, but also return the number of input
+    /// items each point stands for.
+    ///
+    /// The counts are all 1 unless the tree was built to collapse
+    /// duplicates. `k` counts distinct points, not their duplicates.
+    pub fn nearest_neighbors_counted(&self, query: &T, k: usize, sorted: bool) -> Vec<(&T, usize)> {
+        let mut heap = BinaryHeap::with_capacity(k);
+        if let (Some(root), true) = (self.root(), k > 0) {
+            root.nearest_neighbors(&self.nodes, query, k, &mut heap);
+        }
+
+        let v = if sorted {
+            heap.into_sorted_vec()
+        } else {
+            heap.into_vec()
+        };
+        v.into_iter().map(|x| (&x.item.item, x.item.count as usize)).collect()
+    }
 
-            let ref n: VPNode<F, T> = *c.inner.borrow();
-            s += &format!("{}{}", prefix, n.dump(&new_prefix));
+    /// Like `within_radius`, but also return the number of input items
+    /// each element stands for.
+    ///
+    /// The counts are all 1 unless the tree was built to collapse
+    /// duplicates.
+    pub fn within_radius_counted(&self, query: &T, radius: F, sorted: bool) -> Vec<(&T, usize)> {
+        let mut elems = Vec::new();
+        if let Some(root) = self.root() {
+            root.within_radius(&self.nodes, query, radius, &mut elems);
+        }
+
+        if sorted {
+            elems.sort();
+        }
+        elems.into_iter().map(|x| (&x.item.item, x.item.count as usize)).collect()
+    }
+
+    /// Return the distance from `query` to its `k`th nearest point
+    /// in the tree, counting from 1.
+    ///
+    /// Returns `None` if `k` is zero or larger than the number of
+    /// points in the tree.
+    pub fn kth_nearest_distance(&self, query: &T, k: usize) -> Option<F> {
+        self.root().and_then(|root| root.kth_nearest_distance(&self.nodes, query, k))
+    }
+
+    /// Find all points in the tree that would have `query` among
+    /// their own `k` nearest neighbors.
+    ///
+    /// A point's neighbors are the other points in the tree, so a
+    /// point `p` is returned when `query` is no further from `p` than
+    /// the `k`th nearest point to `p`, excluding `p` itself. This
+    /// runs a separate `k`-nearest-neighbor search for every point in
+    /// the tree.
+    pub fn reverse_nearest_neighbors(&self, query: &T, k: usize) -> Vec<&T> {
+        let mut v = Vec::new();
+        if let (Some(root), true) = (self.root(), k > 0) {
+            root.reverse_nearest_neighbors(&self.nodes, root, query, k, &mut v);
+        }
+        v
+    }
+
+    /// Find the point in the tree minimizing the aggregate distance to
+    /// all of `queries`.
+    ///
+    /// With `Aggregate::Sum` this is the point with the smallest total
+    /// distance to the queries; with `Aggregate::Max`, the point whose
+    /// furthest query is closest. Returns `None` if the tree is empty.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the metric returns a negative or NaN distance to
+    /// any of `queries`.
+    pub fn aggregate_nearest_neighbor(&self, queries: &[T], agg: Aggregate) -> Option<&T> {
+        let mut best = None;
+        if let Some(root) = self.root() {
+            root.aggregate_nearest_neighbor(&self.nodes, queries, agg, &mut best);
+        }
 
-            if let Some(ref outer) = c.outer {
-                let ref n: VPNode<F, T> = *outer.borrow();
-                s += &format!("{}{}", prefix, n.dump(&new_prefix));
+        best.map(|x| &x.item.item)
+    }
+
+    /// Compute the `k`-nearest-neighbor graph of the points in the
+    /// tree.
+    ///
+    /// Points are identified by their position in the vector the tree
+    /// was built from. Entry `i` of the result lists the indices of
+    /// the `k` points nearest to point `i`, excluding `i` itself,
+    /// together with their distances, sorted by ascending distance.
+    ///
+    /// The tree is searched against itself, as in
+    /// `all_nearest_neighbors`, so nearby points share the work of
+    /// ruling out subtrees.
+    pub fn knn_graph(&self, k: usize) -> Vec<Vec<(usize, F)>> {
+        // Walk the tree against itself, as `all_nearest_neighbors`
+        // does. Each point is among its own nearest neighbors, unless
+        // it has more than `k` duplicates, so look one further.
+        VPTree::all_nearest_neighbors(self, self, k.saturating_add(1)).into_iter().enumerate()
+            .map(|(i, ns)| ns.into_iter().filter(|&(j, _)| j != i).take(k).collect())
+            .collect()
+    }
+
+    /// Find all unordered pairs of points in the tree within `radius`
+    /// of each other.
+    ///
+    /// Each pair `(i, j, d)` holds the indices of the two points in
+    /// the vector the tree was built from, with `i < j`, and their
+    /// distance `d`. Pairs are returned in no particular order.
+    pub fn pairs_within(&self, radius: F) -> Vec<(usize, usize, F)> {
+        let mut v = Vec::new();
+        if let Some(root) = self.root() {
+            root.pairs_within(&self.nodes, radius, &mut v);
+        }
+        v
+    }
+
+    /// Find the `k` nearest neighbors in `data_tree` of every point in
+    /// `query_tree`.
+    ///
+    /// Entry `i` of the result lists the `k` points of `data_tree`
+    /// nearest to point `i` of `query_tree`, as pairs of an index and
+    /// a distance, sorted by ascending distance. Points are identified
+    /// by their position in the vector each tree was built from.
+    ///
+    /// The search walks both trees at once, so that a whole group of
+    /// query points can rule out a whole subtree of data points with a
+    /// single distance evaluation.
+    pub fn all_nearest_neighbors(query_tree: &VPTree<F, T>, data_tree: &VPTree<F, T>, k: usize)
+                                 -> Vec<Vec<(usize, F)>> {
+        let (query_root, data_root) = match (query_tree.root(), data_tree.root()) {
+            (Some(q), Some(d)) if k > 0 => (q, d),
+            (q, _) => return vec![Vec::new(); q.map_or(0, |q| q.size)]
+        };
+
+        let mut search = DualKnn::new(query_root.size, k);
+        search.traverse(DualView::Whole(&query_tree.nodes, query_root), DualView::Whole(&data_tree.nodes, data_root));
+
+        search.heaps.into_iter().map(|heap| {
+            heap.into_sorted_vec().into_iter().map(|x| (x.item.index, x.dist)).collect()
+        }).collect()
+    }
+
+    /// Return the directed Hausdorff distance from this tree to
+    /// `other`: the largest distance from any point in this tree to
+    /// its nearest point in `other`.
+    ///
+    /// The distance from an empty tree is zero, and the distance from
+    /// a non-empty tree to an empty one is infinite.
+    pub fn directed_hausdorff_distance(&self, other: &VPTree<F, T>) -> F {
+        self.items().into_iter().fold(F::zero(), |h, ti| {
+            // A point with a neighbor closer than the current maximum
+            // can't raise it, so we can avoid the full search.
+            if other.any_within_radius(&ti.item, h) {
+                h
+            } else {
+                h.larger(other.kth_nearest_distance(&ti.item, 1).unwrap_or_else(F::infinity))
             }
+        })
+    }
+
+    /// Return the Hausdorff distance between this tree and `other`.
+    ///
+    /// This is the larger of the two directed Hausdorff distances.
+    pub fn hausdorff_distance(&self, other: &VPTree<F, T>) -> F {
+        self.directed_hausdorff_distance(other).larger(other.directed_hausdorff_distance(self))
+    }
+
+    /// Find the closest point in the tree to `query`, skipping the
+    /// items matched by `exclude`.
+    ///
+    /// Returns `None` if every point in the tree is excluded.
+    pub fn nearest_neighbor_excluding(&self, query: &T, exclude: Exclude) -> Option<&T> {
+        self.nearest_neighbors_excluding(query, 1, false, exclude).pop()
+    }
+
+    /// Find the `k` points in the tree closest to `query`, skipping
+    /// the items matched by `exclude`.
+    ///
+    /// Otherwise behaves like `nearest_neighbors`.
+    pub fn nearest_neighbors_excluding(&self, query: &T, k: usize, sorted: bool, exclude: Exclude) -> Vec<&T> {
+        let mut heap = BinaryHeap::with_capacity(k);
+        if let (Some(root), true) = (self.root(), k > 0) {
+            root.nearest_neighbors_filtered(&self.nodes, query, k, &|x, d| exclude.keeps(x.index, d), &mut heap);
         }
-        s
+
+        let v = if sorted {
+            heap.into_sorted_vec()
+        } else {
+            heap.into_vec()
+        };
+        v.into_iter().map(|x| &x.item.item).collect()
+    }
+
+    /// Find the point in the tree furthest from `query`.
+    ///
+    /// Returns `None` if the tree is empty.
+    pub fn farthest_neighbor(&self, query: &T) -> Option<&T> {
+        self.farthest_neighbors(query, 1, false).pop()
+    }
+
+    /// Find the `k` points in the tree furthest from `query`.
+    ///
+    /// If `sorted` is true, the returned points will be sorted by
+    /// descending distance to `query`.  If `k` is larger than the
+    /// total number of points in the tree, all of the points in the
+    /// tree are returned.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the metric returns a negative or NaN distance to
+    /// `query`.
+    pub fn farthest_neighbors(&self, query: &T, k: usize, sorted: bool) -> Vec<&T> {
+        let mut heap = BinaryHeap::with_capacity(k);
+        if let (Some(root), true) = (self.root(), k > 0) {
+            root.farthest_neighbors(&self.nodes, query, k, &mut heap);
+        }
+
+        let v = if sorted {
+            heap.into_sorted_vec()
+        } else {
+            heap.into_vec()
+        };
+        v.into_iter().map(|x| &x.0.item.item).collect()
+    }
+}
+
+impl<F: Distance, T: MetricItem<F>> Default for VPTree<F, T> {
+    /// Construct an empty tree.
+    fn default() -> Self {
+        VPTree { nodes: Vec::new(), config: BuildConfig::default(), modified: 0 }
     }
 }
 
-impl <F: Float + Display, T: MetricItem<F> + Debug> VPTree<F, T> {
-    /// Return a pretty-printed recursive description of the entire tree.
+impl<F: Distance, T: MetricItem<F>> FromIterator<T> for VPTree<F, T> {
+    /// Construct a tree with the default options from the items of
+    /// `iter`, as with `new`.
     ///
-    /// This function is mainly intended for debugging.
-    #[inline]
-    pub fn dump(&self) -> String {
-        self.root.dump("")
+    /// The items are tagged as they are collected, so no intermediate
+    /// vector of untagged items is needed, and sources with an exact
+    /// size are collected without reallocating.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as `new`.
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let items: Vec<TaggedItem<F, T>> = iter.into_iter().enumerate()
+            .map(|(index, x)| TaggedItem::new(x, index)).collect();
+        match VPTreeBuilder::new().build_tagged(items, Vec::new()) {
+            Ok(tree) => tree,
+            Err(e) => panic!("failed to build VPTree: {}", e)
+        }
     }
 }
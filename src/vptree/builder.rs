@@ -0,0 +1,343 @@
+//! Configurable tree construction.
+
+use std::marker::PhantomData;
+use rand::{Rng, SeedableRng, XorShiftRng};
+use distance::Distance;
+
+use error::BuildError;
+use super::{MetricItem, NodeLayout, TaggedItem, VPNode, VPTree, VantageStrategy};
+
+/// A user-supplied function choosing a vantage point.
+type VantageSelector<T> = dyn Fn(&[&T]) -> usize;
+
+/// Default maximum number of items in a leaf bucket.
+pub(super) const DEFAULT_LEAF_SIZE: usize = 16;
+
+/// A builder for `VPTree`s with non-default construction options.
+///
+/// ```rust
+/// use vptree::{MetricItem, VPTree, VantageStrategy};
+///
+/// struct Point(f32);
+///
+/// impl MetricItem<f32> for Point {
+///     fn distance(&self, q: &Self) -> f32 {
+///         (self.0 - q.0).abs()
+///     }
+/// }
+///
+/// let points: Vec<_> = (0..100).map(|x| Point(x as f32)).collect();
+/// let tree = VPTree::builder()
+///     .vantage_strategy(VantageStrategy::Sampled(5))
+///     .build(points);
+/// assert_eq!(tree.len(), 100);
+/// ```
+pub struct VPTreeBuilder<F: Distance, T: MetricItem<F>> {
+    pub(super) config: BuildConfig,
+    selector: Option<Box<VantageSelector<T>>>,
+    _marker: PhantomData<fn(&T) -> F>,
+}
+
+/// The construction options other than a custom vantage point
+/// selector. Trees keep a copy, to rebuild themselves with later.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct BuildConfig {
+    pub strategy: VantageStrategy,
+    pub seed: Option<u64>,
+    pub median_sample_size: Option<usize>,
+    pub leaf_size: usize,
+    pub max_depth: Option<usize>,
+    pub layout: NodeLayout,
+    pub collapse_duplicates: bool,
+}
+
+impl Default for BuildConfig {
+    fn default() -> Self {
+        BuildConfig {
+            strategy: VantageStrategy::default(),
+            seed: None,
+            median_sample_size: None,
+            leaf_size: DEFAULT_LEAF_SIZE,
+            max_depth: None,
+            layout: NodeLayout::default(),
+            collapse_duplicates: false,
+        }
+    }
+}
+
+impl<F: Distance, T: MetricItem<F>> VPTreeBuilder<F, T> {
+    /// Create a builder with the default options.
+    pub fn new() -> Self {
+        VPTreeBuilder::from_config(BuildConfig::default())
+    }
+
+    /// Create a builder with the given options and no custom vantage
+    /// point selector.
+    pub(super) fn from_config(config: BuildConfig) -> Self {
+        VPTreeBuilder { config, selector: None, _marker: PhantomData }
+    }
+
+    /// Set the strategy used to choose vantage points.
+    pub fn vantage_strategy(mut self, strategy: VantageStrategy) -> Self {
+        self.config.strategy = strategy;
+        self
+    }
+
+    /// Choose vantage points with a custom function instead of one of
+    /// the built-in strategies.
+    ///
+    /// `selector` is called with the items of each subtree with more
+    /// than one item, and must return the index of the vantage point
+    /// among them. This lets domain knowledge, such as known cluster
+    /// centers, guide the construction.
+    pub fn vantage_selector<S>(mut self, selector: S) -> Self
+        where S: Fn(&[&T]) -> usize + 'static {
+        self.selector = Some(Box::new(selector));
+        self
+    }
+
+    /// Drive every random choice made during construction from
+    /// `seed`, so that building from the same items twice yields the
+    /// same tree.
+    ///
+    /// Without a seed, construction uses the thread-local random
+    /// number generator, except on `wasm32-unknown-unknown`, which has
+    /// no source of entropy to seed it from. There, unseeded builders
+    /// use a fixed seed instead, so set a seed or build with
+    /// `build_with_rng` to vary the trees built.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.config.seed = Some(seed);
+        self
+    }
+
+    /// Estimate each split distance from a random sample of
+    /// `sample_size` distances, instead of finding the exact median.
+    ///
+    /// This avoids a selection pass over every subtree larger than
+    /// the sample, at the cost of less even splits: with a sample of
+    /// size `s`, the fraction of items on either side of a split
+    /// typically strays from one half by about `1 / (2 * sqrt(s))`.
+    /// A few hundred samples keep trees close to balanced. Items at
+    /// exactly the estimated median are divided between the two sides
+    /// to even them out, so repeated distances don't unbalance the
+    /// tree.
+    ///
+    /// Building fails with `BuildError::InvalidParameter` if
+    /// `sample_size` is zero.
+    pub fn median_sample_size(mut self, sample_size: usize) -> Self {
+        self.config.median_sample_size = Some(sample_size);
+        self
+    }
+
+    /// Stop splitting subtrees of at most `leaf_size` items, and
+    /// store them in a flat bucket that is scanned linearly at query
+    /// time.
+    ///
+    /// Larger buckets make for shallower trees with fewer nodes, at
+    /// the cost of extra distance evaluations in each visited leaf.
+    /// The default is 16. A leaf size of 1 gives a classic tree with
+    /// one item per node.
+    ///
+    /// Building fails with `BuildError::InvalidParameter` if
+    /// `leaf_size` is zero.
+    pub fn leaf_size(mut self, leaf_size: usize) -> Self {
+        self.config.leaf_size = leaf_size;
+        self
+    }
+
+    /// Stop splitting at level `max_depth`, counting the root as
+    /// level 1, and keep every item of a subtree at that level in a
+    /// flat bucket, whatever its size.
+    ///
+    /// This bounds the recursion depth of construction and queries
+    /// for metrics or data that split badly, at the cost of linear
+    /// scans through any oversized buckets. By default, the depth is
+    /// unlimited.
+    ///
+    /// Building fails with `BuildError::InvalidParameter` if
+    /// `max_depth` is zero.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.config.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Set the order in which the nodes of the tree are stored in
+    /// memory. See `NodeLayout` for the options.
+    pub fn layout(mut self, layout: NodeLayout) -> Self {
+        self.config.layout = layout;
+        self
+    }
+
+    /// Collapse items at distance zero from each other into a single
+    /// item, which keeps count of how many input items it stands for.
+    ///
+    /// Identical items make for degenerate splits, with every
+    /// distance zero, so collapsing them keeps the tree small and
+    /// balanced. The first of each set of duplicates is kept, and the
+    /// rest are dropped. Queries see each kept item once; the
+    /// `_counted` variants of the queries also report the counts.
+    /// Later inserts of duplicates are counted in the same way.
+    pub fn collapse_duplicates(mut self, collapse: bool) -> Self {
+        self.config.collapse_duplicates = collapse;
+        self
+    }
+
+    pub(super) fn select_vantage_point<R: Rng>(&self, items: &[TaggedItem<F, T>], rng: &mut R)
+                                                -> Result<usize, BuildError> {
+        match self.selector {
+            Some(ref selector) => {
+                let refs: Vec<&T> = items.iter().map(|ti| &ti.item).collect();
+                let i = selector(&refs);
+                if i < items.len() {
+                    Ok(i)
+                } else {
+                    Err(BuildError::InvalidParameter("vantage point selector returned an out-of-range index"))
+                }
+            },
+            None => Ok(self.config.strategy.select(items, rng))
+        }
+    }
+
+    /// Build a tree from `items`.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as `VPTree::new`.
+    pub fn build(&self, items: Vec<T>) -> VPTree<F, T> {
+        match self.build_tree(items) {
+            Ok(tree) => tree,
+            Err(e) => panic!("failed to build VPTree: {}", e)
+        }
+    }
+
+    /// Build a tree from `items`, reporting construction problems as
+    /// errors, as with `VPTree::try_new`.
+    pub fn try_build(&self, items: Vec<T>) -> Result<VPTree<F, T>, BuildError> {
+        if items.is_empty() {
+            return Err(BuildError::Empty);
+        }
+        self.build_tree(items)
+    }
+
+    /// Build a tree from `items`, as with `try_build`, except that an
+    /// empty set of items is allowed.
+    pub(crate) fn try_build_allowing_empty(&self, items: Vec<T>) -> Result<VPTree<F, T>, BuildError> {
+        self.build_tree(items)
+    }
+
+    /// Build a tree from `items`, drawing every random choice from
+    /// `rng`. Any seed set on the builder is ignored.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as `VPTree::new`.
+    pub fn build_with_rng<R: Rng>(&self, items: Vec<T>, rng: &mut R) -> VPTree<F, T> {
+        match self.build_tree_with_rng(items, rng) {
+            Ok(tree) => tree,
+            Err(e) => panic!("failed to build VPTree: {}", e)
+        }
+    }
+
+    /// Build a tree from `items`, drawing every random choice from
+    /// `rng`, and reporting construction problems as errors.
+    pub fn try_build_with_rng<R: Rng>(&self, items: Vec<T>, rng: &mut R) -> Result<VPTree<F, T>, BuildError> {
+        if items.is_empty() {
+            return Err(BuildError::Empty);
+        }
+        self.build_tree_with_rng(items, rng)
+    }
+
+    fn build_tree(&self, items: Vec<T>) -> Result<VPTree<F, T>, BuildError> {
+        match self.config.seed {
+            Some(seed) => self.build_tree_with_rng(items, &mut seeded_rng(seed)),
+            None => self.build_tree_with_rng(items, &mut unseeded_rng())
+        }
+    }
+
+    /// Build a tree from items that have already been tagged with
+    /// their indices, using the builder's seed if it has one.
+    ///
+    /// The nodes are built into `nodes`, which must be empty, so that
+    /// an existing arena can be reused.
+    pub(super) fn build_tagged(&self, items: Vec<TaggedItem<F, T>>, nodes: Vec<VPNode<F, T>>)
+                               -> Result<VPTree<F, T>, BuildError> {
+        match self.config.seed {
+            Some(seed) => self.build_tagged_with_rng(items, nodes, &mut seeded_rng(seed)),
+            None => self.build_tagged_with_rng(items, nodes, &mut unseeded_rng())
+        }
+    }
+
+    /// Check that the options are within range.
+    fn validate(&self) -> Result<(), BuildError> {
+        if self.config.median_sample_size == Some(0) {
+            return Err(BuildError::InvalidParameter("median sample size must be positive"));
+        }
+        if self.config.leaf_size == 0 {
+            return Err(BuildError::InvalidParameter("leaf size must be positive"));
+        }
+        if self.config.max_depth == Some(0) {
+            return Err(BuildError::InvalidParameter("maximum depth must be positive"));
+        }
+        Ok(())
+    }
+
+    fn build_tree_with_rng<R: Rng>(&self, items: Vec<T>, rng: &mut R) -> Result<VPTree<F, T>, BuildError> {
+        self.validate()?;
+        let tagged_items: Vec<TaggedItem<F, T>> = items.into_iter().enumerate()
+            .map(|(index, x)| TaggedItem::new(x, index)).collect();
+        self.build_tagged_with_rng(tagged_items, Vec::new(), rng)
+    }
+
+    fn build_tagged_with_rng<R: Rng>(&self, items: Vec<TaggedItem<F, T>>, nodes: Vec<VPNode<F, T>>, rng: &mut R)
+                                     -> Result<VPTree<F, T>, BuildError> {
+        if items.len() > u32::MAX as usize {
+            return Err(BuildError::InvalidParameter("too many items for a single tree"));
+        }
+
+        let n = items.len();
+        let nodes = if items.is_empty() { nodes } else { VPNode::build(items, self, nodes, rng)? };
+        let mut tree = VPTree { nodes, config: self.config, modified: 0 };
+        if self.config.collapse_duplicates {
+            tree.recompute_sizes();
+            if tree.len() < n {
+                tree.renumber(n);
+            }
+        }
+        if self.config.layout != NodeLayout::PreOrder {
+            tree.relayout(self.config.layout);
+        }
+        Ok(tree)
+    }
+}
+
+/// Expand a 64-bit seed into a generator.
+///
+/// The generator is platform-independent, so a seed yields the same
+/// tree everywhere.
+fn seeded_rng(seed: u64) -> XorShiftRng {
+    let (lo, hi) = (seed as u32, (seed >> 32) as u32);
+    // The constants keep the seed from ever being all zeros, which
+    // XorShiftRng rejects.
+    XorShiftRng::from_seed([lo, hi, lo ^ 0x9e37_79b9, hi ^ 0x7f4a_7c15])
+}
+
+/// Return the generator used by builders without a seed.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub(super) fn unseeded_rng() -> ::rand::ThreadRng {
+    ::rand::thread_rng()
+}
+
+/// Return the generator used by builders without a seed.
+///
+/// `wasm32-unknown-unknown` has no entropy source, and seeding the
+/// thread-local generator there panics.
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+pub(super) fn unseeded_rng() -> XorShiftRng {
+    seeded_rng(0)
+}
+
+impl<F: Distance, T: MetricItem<F>> Default for VPTreeBuilder<F, T> {
+    fn default() -> Self {
+        VPTreeBuilder::new()
+    }
+}
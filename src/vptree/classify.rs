@@ -0,0 +1,65 @@
+//! Classification by votes of nearest neighbors.
+
+use std::collections::BinaryHeap;
+
+use distance::Distance;
+
+use super::{MetricItem, VPTree};
+
+impl<F: Distance, T: MetricItem<F>> VPTree<F, T> {
+    /// Predict the label of `query` by a majority vote of its `k`
+    /// nearest points in the tree, labelled by `label_fn`.
+    ///
+    /// A tie goes to the label of the nearest point among those tied.
+    /// In a tree that collapses duplicates, each point has as many
+    /// votes as the input items it stands for. Returns `None` if `k`
+    /// is zero or the tree is empty.
+    pub fn classify<L, G>(&self, query: &T, k: usize, label_fn: G) -> Option<L>
+        where L: PartialEq, G: Fn(&T) -> L {
+        self.vote(query, k, label_fn, false)
+    }
+
+    /// Like `classify`, but weighting each vote by the inverse of the
+    /// point's distance to `query`, so that nearer points count for
+    /// more.
+    ///
+    /// Points at distance zero outvote all others.
+    pub fn classify_weighted<L, G>(&self, query: &T, k: usize, label_fn: G) -> Option<L>
+        where L: PartialEq, G: Fn(&T) -> L {
+        self.vote(query, k, label_fn, true)
+    }
+
+    fn vote<L, G>(&self, query: &T, k: usize, label_fn: G, weighted: bool) -> Option<L>
+        where L: PartialEq, G: Fn(&T) -> L {
+        let mut heap = BinaryHeap::with_capacity(k);
+        if let (Some(root), true) = (self.root(), k > 0) {
+            root.nearest_neighbors(&self.nodes, query, k, &mut heap);
+        }
+        let neighbors = heap.into_sorted_vec();
+        let exact = weighted && neighbors.first().is_some_and(|x| x.dist == F::zero());
+
+        // Scores by label, in order of each label's nearest point.
+        let mut scores: Vec<(L, f64)> = Vec::new();
+        for x in neighbors {
+            let score = match (weighted, exact) {
+                (false, _) => x.item.count as f64,
+                (true, true) if x.dist == F::zero() => x.item.count as f64,
+                (true, true) => continue,
+                (true, false) => x.item.count as f64 / x.dist.to_f64(),
+            };
+            let label = label_fn(&x.item.item);
+            match scores.iter_mut().find(|s| s.0 == label) {
+                Some(s) => s.1 += score,
+                None => scores.push((label, score)),
+            }
+        }
+
+        let mut best: Option<(L, f64)> = None;
+        for (label, score) in scores {
+            if best.as_ref().is_none_or(|b| score > b.1) {
+                best = Some((label, score));
+            }
+        }
+        best.map(|b| b.0)
+    }
+}
@@ -0,0 +1,265 @@
+//! A read-only tree storing its split values in single precision.
+
+use std::collections::BinaryHeap;
+
+use super::{query_distance, HeapElem, MetricItem, VPNode, VPTree};
+
+/// A read-only vantage point tree over `f64` distances, storing its
+/// split values and cached distances as `f32`.
+///
+/// Every node of a `VPTree` keeps several distances alongside its
+/// items, which can dominate the size of a large index over small
+/// items. Halving them shrinks the tree and lets more of it stay in
+/// cache. Queries still measure distances in `f64`.
+///
+/// Stored lower bounds are rounded down and upper bounds rounded up,
+/// and distances cached for individual items are widened to the
+/// interval they were rounded within, so pruning stays conservative:
+/// queries find the same neighbors as the `VPTree` the tree was
+/// converted from, at the cost of occasionally evaluating a few more
+/// distances.
+///
+/// ```rust
+/// use vptree::{CompactVPTree, MetricItem, VPTree};
+///
+/// struct Point(f64);
+///
+/// impl MetricItem<f64> for Point {
+///     fn distance(&self, q: &Self) -> f64 {
+///         (self.0 - q.0).abs()
+///     }
+/// }
+///
+/// let tree = CompactVPTree::from(VPTree::new((0..100).map(|x| Point(x as f64 / 3.0)).collect()));
+/// assert_eq!(tree.nearest_neighbor(&Point(10.01)).unwrap().0, 10.0);
+/// assert_eq!(tree.within_radius(&Point(10.0), 0.5, false).len(), 3);
+/// ```
+pub struct CompactVPTree<T: MetricItem<f64>> {
+    nodes: Vec<CompactNode<T>>,
+    len: usize,
+}
+
+/// A node of a compact tree, laid out like `VPNode`.
+struct CompactNode<T> {
+    center: T,
+    /// Distance from the center to the parent's vantage point, rounded
+    /// down.
+    center_dist: f32,
+    /// For leaf nodes, the items other than the center, with their
+    /// distances to the center rounded down.
+    bucket: Vec<(T, f32)>,
+    /// Distance from the center to the furthest item in the subtree,
+    /// rounded up.
+    radius: f32,
+    split: Option<CompactSplit>,
+}
+
+/// The split of a non-leaf node, laid out like `InnerNode`.
+///
+/// `inner_min` and `outer_min` are rounded down, and `mu` and
+/// `outer_max` are rounded up.
+struct CompactSplit {
+    mu: f32,
+    inner_min: f32,
+    outer_min: f32,
+    outer_max: f32,
+    inner: u32,
+    outer: Option<u32>,
+}
+
+/// Return the largest `f32` no greater than `d`.
+fn round_down(d: f64) -> f32 {
+    let x = d as f32;
+    if f64::from(x) > d { x.next_down() } else { x }
+}
+
+/// Return the smallest `f32` no less than `d`.
+fn round_up(d: f64) -> f32 {
+    let x = d as f32;
+    if f64::from(x) < d { x.next_up() } else { x }
+}
+
+/// Return a lower bound on the distance from an object to an item,
+/// given the distance `d` from the object to the vantage point and the
+/// rounded down distance `stored` from the item to it.
+///
+/// The item's true distance lies between `stored` and the next larger
+/// `f32`, so the object is at least as far from it as from the nearer
+/// end of that interval.
+fn center_bound(stored: f32, d: f64) -> f64 {
+    let (lo, hi) = (f64::from(stored), f64::from(stored.next_up()));
+    (lo - d).max(d - hi).max(0.0)
+}
+
+/// Push `item` onto a heap of the `n` nearest items, as
+/// `push_nearest` does for tagged items.
+fn push_nearest<'a, T>(heap: &mut BinaryHeap<HeapElem<'a, f64, T>>, n: usize, d: f64, item: &'a T) {
+    if heap.len() < n {
+        heap.push(HeapElem::new(d, item));
+    } else if heap.peek().unwrap().dist > d {
+        heap.pop();
+        heap.push(HeapElem::new(d, item));
+    }
+}
+
+impl<T> CompactNode<T> {
+    /// Return a lower bound on the distance from an object to
+    /// anything in this subtree, given the distance from the object to
+    /// the parent's vantage point.
+    fn parent_bound(&self, d_parent: f64) -> f64 {
+        center_bound(self.center_dist, d_parent) - f64::from(self.radius)
+    }
+}
+
+impl CompactSplit {
+    /// Return lower bounds on the distance from an object to anything
+    /// in the inner and outer subtrees, as `InnerNode::bounds` does.
+    fn bounds(&self, d: f64) -> (f64, f64) {
+        ((f64::from(self.inner_min) - d).max(d - f64::from(self.mu)),
+         (f64::from(self.outer_min) - d).max(d - f64::from(self.outer_max)))
+    }
+}
+
+impl<T: MetricItem<f64>> CompactVPTree<T> {
+    /// Construct a new tree from a set of elements with the default
+    /// options.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as `VPTree::new`.
+    pub fn new(items: Vec<T>) -> Self {
+        CompactVPTree::from(VPTree::new(items))
+    }
+
+    /// Return the number of items in the tree.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Return true if the tree holds no items.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Find the closest point in the tree to `query`.
+    ///
+    /// Returns `None` if the tree is empty.
+    pub fn nearest_neighbor(&self, query: &T) -> Option<&T> {
+        self.nearest_neighbors(query, 1, false).pop()
+    }
+
+    /// Find the `k` points in the tree closest to `query`.
+    ///
+    /// Behaves like `VPTree::nearest_neighbors`.
+    pub fn nearest_neighbors(&self, query: &T, k: usize, sorted: bool) -> Vec<&T> {
+        let mut heap: BinaryHeap<HeapElem<f64, T>> = BinaryHeap::with_capacity(k);
+        let mut stack: Vec<(&CompactNode<T>, f64)> = self.nodes.first().map(|r| (r, 0.0)).into_iter().collect();
+        if k == 0 {
+            stack.clear();
+        }
+
+        while let Some((node, bound)) = stack.pop() {
+            if heap.len() >= k && heap.peek().unwrap().dist <= bound {
+                continue;
+            }
+
+            let d_center = query_distance(query, &node.center);
+            push_nearest(&mut heap, k, d_center, &node.center);
+            for &(ref item, dist) in &node.bucket {
+                if heap.len() >= k && center_bound(dist, d_center) >= heap.peek().unwrap().dist {
+                    continue;
+                }
+                push_nearest(&mut heap, k, query_distance(query, item), item);
+            }
+
+            if let Some(ref s) = node.split {
+                let (inner_bound, outer_bound) = s.bounds(d_center);
+                let mut children = [(Some(s.inner), inner_bound), (s.outer, outer_bound)];
+
+                // Search the side of the split the query is on first.
+                if d_center > f64::from(s.mu) {
+                    children.swap(0, 1);
+                }
+                for &(child, bound) in children.iter().rev() {
+                    if let Some(child) = child {
+                        let child = &self.nodes[child as usize];
+                        stack.push((child, bound.max(child.parent_bound(d_center))));
+                    }
+                }
+            }
+        }
+
+        let v = if sorted {
+            heap.into_sorted_vec()
+        } else {
+            heap.into_vec()
+        };
+        v.into_iter().map(|x| x.item).collect()
+    }
+
+    /// Return all elements within a given radius of the target.
+    ///
+    /// Behaves like `VPTree::within_radius`.
+    pub fn within_radius(&self, query: &T, radius: f64, sorted: bool) -> Vec<&T> {
+        let mut elems = Vec::new();
+        let mut stack: Vec<&CompactNode<T>> = self.nodes.first().into_iter().collect();
+        while let Some(node) = stack.pop() {
+            let d_center = query_distance(query, &node.center);
+            if d_center < radius {
+                elems.push(HeapElem::new(d_center, &node.center));
+            }
+            for &(ref item, dist) in &node.bucket {
+                if center_bound(dist, d_center) >= radius {
+                    continue;
+                }
+                let d = query_distance(query, item);
+                if d < radius {
+                    elems.push(HeapElem::new(d, item));
+                }
+            }
+
+            if let Some(ref s) = node.split {
+                let (inner_bound, outer_bound) = s.bounds(d_center);
+                let inner = &self.nodes[s.inner as usize];
+                if radius > inner_bound && radius > inner.parent_bound(d_center) {
+                    stack.push(inner);
+                }
+                if let Some(outer) = s.outer.map(|o| &self.nodes[o as usize]) {
+                    if radius > outer_bound && radius > outer.parent_bound(d_center) {
+                        stack.push(outer);
+                    }
+                }
+            }
+        }
+
+        if sorted {
+            elems.sort();
+        }
+        elems.into_iter().map(|x| x.item).collect()
+    }
+}
+
+impl<T: MetricItem<f64>> From<VPTree<f64, T>> for CompactVPTree<T> {
+    /// Convert a tree, keeping its structure and node layout.
+    fn from(tree: VPTree<f64, T>) -> Self {
+        let len = tree.len();
+        let nodes = tree.nodes.into_iter().map(|node| {
+            let VPNode { contents, center, bucket, radius, .. } = node;
+            CompactNode {
+                center_dist: round_down(center.dist),
+                center: center.item,
+                bucket: bucket.into_iter().map(|ti| (ti.item, round_down(ti.dist))).collect(),
+                radius: round_up(radius),
+                split: contents.map(|c| CompactSplit {
+                    mu: round_up(c.mu),
+                    inner_min: round_down(c.inner_min),
+                    outer_min: round_down(c.outer_min),
+                    outer_max: round_up(c.outer_max),
+                    inner: c.inner,
+                    outer: c.outer,
+                }),
+            }
+        }).collect();
+        CompactVPTree { nodes, len }
+    }
+}
@@ -0,0 +1,78 @@
+//! `Debug` formatting of trees, summarizing their deeper levels.
+
+use std::fmt::{self, Debug, Formatter};
+use distance::Distance;
+
+use super::{MetricItem, VPNode, VPTree};
+
+/// Number of levels of nodes shown in full. Deeper subtrees are
+/// summarized by their vantage point and size.
+const MAX_DEPTH: usize = 3;
+
+/// Number of items shown from each leaf bucket.
+const MAX_BUCKET: usize = 8;
+
+/// A node at a given level, formatted along with its subtrees.
+pub(super) struct NodeDebug<'a, F: Distance + 'a, T: MetricItem<F> + 'a> {
+    pub nodes: &'a [VPNode<F, T>],
+    pub node: &'a VPNode<F, T>,
+    pub depth: usize,
+}
+
+/// The items of a leaf bucket, formatted up to `MAX_BUCKET` of them.
+struct BucketDebug<'a, F: Distance + 'a, T: MetricItem<F> + 'a>(&'a VPNode<F, T>);
+
+impl<'a, F: Distance + Debug, T: MetricItem<F> + Debug> Debug for NodeDebug<'a, F, T> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let node = self.node;
+        let mut s = f.debug_struct("VPNode");
+        s.field("center", &node.center.item)
+            .field("radius", &node.radius)
+            .field("size", &node.size);
+        if self.depth >= MAX_DEPTH && node.size > 1 {
+            return s.finish_non_exhaustive();
+        }
+
+        if !node.bucket.is_empty() {
+            s.field("bucket", &BucketDebug(node));
+        }
+        if let Some(ref c) = node.contents {
+            let child = |node| NodeDebug { nodes: self.nodes, node, depth: self.depth + 1 };
+            s.field("mu", &c.mu).field("inner", &child(c.inner_node(self.nodes)));
+            if let Some(outer) = c.outer_node(self.nodes) {
+                s.field("outer", &child(outer));
+            }
+        }
+        s.finish()
+    }
+}
+
+impl<'a, F: Distance, T: MetricItem<F> + Debug> Debug for BucketDebug<'a, F, T> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let bucket = &self.0.bucket;
+        let mut list = f.debug_list();
+        list.entries(bucket.iter().take(MAX_BUCKET).map(|ti| &ti.item));
+        if bucket.len() > MAX_BUCKET {
+            list.entry(&format_args!("... {} more", bucket.len() - MAX_BUCKET));
+        }
+        list.finish()
+    }
+}
+
+impl<F: Distance + Debug, T: MetricItem<F> + Debug> Debug for VPTree<F, T> {
+    /// Show the size and depth of the tree, and its top levels of
+    /// nodes.
+    ///
+    /// Only the first few levels are shown in full, and only the
+    /// first few items of each leaf, so that even huge trees format
+    /// to a manageable size. Use `dump` to list every item.
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let mut s = f.debug_struct("VPTree");
+        s.field("len", &self.len()).field("depth", &self.depth());
+        match self.root() {
+            Some(node) => s.field("root", &NodeDebug { nodes: &self.nodes, node, depth: 1 }),
+            None => s.field("root", &None::<()>),
+        };
+        s.finish()
+    }
+}
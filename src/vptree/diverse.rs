@@ -0,0 +1,140 @@
+//! Nearest neighbor queries whose results are kept apart from each
+//! other.
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use distance::Distance;
+
+use super::{query_distance, MetricItem, TaggedItem, VPNode, VPTree};
+
+/// Something left to look at in a best-first search.
+enum Pending<'a, F: Distance + 'a, T: MetricItem<F> + 'a> {
+    /// A subtree, no closer than the bound.
+    Node(&'a VPNode<F, T>),
+    /// An item of a leaf bucket whose distance hasn't been measured,
+    /// no closer than the bound.
+    Unmeasured(&'a TaggedItem<F, T>),
+    /// An item at exactly the bound.
+    Measured(&'a TaggedItem<F, T>),
+}
+
+/// An entry of the best-first search queue, ordered so that the
+/// smallest bound comes first out of a `BinaryHeap`.
+struct Entry<'a, F: Distance + 'a, T: MetricItem<F> + 'a> {
+    bound: F,
+    pending: Pending<'a, F, T>,
+}
+
+impl<'a, F: Distance, T: MetricItem<F>> Entry<'a, F, T> {
+    /// Rank measured items ahead of anything else at the same bound,
+    /// since nothing else can turn out closer.
+    fn rank(&self) -> u8 {
+        match self.pending {
+            Pending::Measured(_) => 0,
+            _ => 1,
+        }
+    }
+}
+
+impl<'a, F: Distance, T: MetricItem<F>> PartialEq for Entry<'a, F, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<'a, F: Distance, T: MetricItem<F>> Eq for Entry<'a, F, T> {}
+
+impl<'a, F: Distance, T: MetricItem<F>> PartialOrd for Entry<'a, F, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<'a, F: Distance, T: MetricItem<F>> Ord for Entry<'a, F, T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.bound.partial_cmp(&self.bound).expect("metric returned a NaN distance")
+            .then(other.rank().cmp(&self.rank()))
+    }
+}
+
+impl<F: Distance, T: MetricItem<F>> VPTree<F, T> {
+    /// Find up to `k` points in the tree close to `query`, no two of
+    /// which are closer than `delta` to each other.
+    ///
+    /// The points are chosen greedily: in order of distance from
+    /// `query`, a point is taken unless it lies within `delta` of a
+    /// point already taken. The result is sorted by ascending
+    /// distance, and holds fewer than `k` points only if every other
+    /// point lies within `delta` of one of them. With `delta` zero,
+    /// this is `nearest_neighbors`.
+    ///
+    /// The tree is searched best-first, so the search stops as soon as
+    /// `k` points are taken, however many points were passed over. The
+    /// triangle inequality settles most comparisons with the points
+    /// taken from their distances to `query`, and passes over whole
+    /// subtrees lying within `delta` of a point taken.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the metric returns a negative or NaN distance to
+    /// `query`.
+    pub fn diverse_nearest_neighbors(&self, query: &T, k: usize, delta: F) -> Vec<&T> {
+        let mut taken: Vec<(&TaggedItem<F, T>, F)> = Vec::with_capacity(k);
+        let mut queue = BinaryHeap::new();
+        if let (Some(root), true) = (self.root(), k > 0) {
+            queue.push(Entry { bound: F::zero(), pending: Pending::Node(root) });
+        }
+
+        while let Some(Entry { bound, pending }) = queue.pop() {
+            match pending {
+                Pending::Node(node) => {
+                    let d_center = query_distance(query, &node.center.item);
+                    // Skip subtrees lying entirely within `delta` of
+                    // a point taken.
+                    if taken.iter().any(|&(_, d_a)| d_a.saturating_add(d_center).saturating_add(node.radius) < delta) {
+                        continue;
+                    }
+                    queue.push(Entry { bound: d_center, pending: Pending::Measured(&node.center) });
+                    for ti in &node.bucket {
+                        queue.push(Entry { bound: ti.center_bound(d_center), pending: Pending::Unmeasured(ti) });
+                    }
+                    if let Some(ref contents) = node.contents {
+                        let (inner_bound, outer_bound) = contents.bounds(d_center);
+                        let children = [(Some(contents.inner_node(&self.nodes)), inner_bound), (contents.outer_node(&self.nodes), outer_bound)];
+                        for &(child, bound) in &children {
+                            if let Some(child) = child {
+                                queue.push(Entry { bound: bound.larger(child.parent_bound(d_center)), pending: Pending::Node(child) });
+                            }
+                        }
+                    }
+                },
+                Pending::Unmeasured(ti) => {
+                    let d = query_distance(query, &ti.item);
+                    queue.push(Entry { bound: d, pending: Pending::Measured(ti) });
+                },
+                Pending::Measured(ti) => {
+                    // Two points are at least as far apart as their
+                    // distances to the query differ, and no further
+                    // apart than the sum.
+                    let apart = taken.iter().all(|&(a, d_a)| {
+                        if d_a.abs_diff(bound) >= delta {
+                            true
+                        } else if d_a.saturating_add(bound) < delta {
+                            false
+                        } else {
+                            T::distance(&a.item, &ti.item) >= delta
+                        }
+                    });
+                    if apart {
+                        taken.push((ti, bound));
+                        if taken.len() == k {
+                            break;
+                        }
+                    }
+                },
+            }
+        }
+        taken.into_iter().map(|(ti, _)| &ti.item).collect()
+    }
+}
@@ -0,0 +1,48 @@
+//! Graphviz export of the tree structure.
+
+use std::fmt::{Debug, Display, Write};
+use distance::Distance;
+
+use super::{MetricItem, VPNode, VPTree};
+
+impl<F: Distance + Display, T: MetricItem<F> + Debug> VPTree<F, T> {
+    /// Return a description of the tree in the Graphviz DOT language.
+    ///
+    /// Each node is labeled with its vantage point, followed by any
+    /// items in its leaf bucket on separate lines, and each edge
+    /// with the range of distances from the parent's vantage point
+    /// covered by the child subtree.
+    pub fn to_dot(&self) -> String {
+        let mut s = String::from("digraph vptree {\n");
+        if let Some(root) = self.root() {
+            let mut next_id = 0;
+            write_node(&self.nodes, root, &mut next_id, &mut s);
+        }
+        s.push_str("}\n");
+        s
+    }
+}
+
+/// Write the node and its subtrees, returning the node's id.
+fn write_node<F: Distance + Display, T: MetricItem<F> + Debug>(nodes: &[VPNode<F, T>], node: &VPNode<F, T>,
+                                                             next_id: &mut usize, s: &mut String) -> usize {
+    let id = *next_id;
+    *next_id += 1;
+
+    let label: Vec<String> = Some(&node.center).into_iter().chain(node.bucket.iter())
+        .map(|ti| format!("{:?}", ti.item).replace('\\', "\\\\").replace('"', "\\\""))
+        .collect();
+    let label = label.join("\\n");
+    writeln!(s, "  n{} [label=\"{}\"];", id, label).unwrap();
+
+    if let Some(ref c) = node.contents {
+        let inner_id = write_node(nodes, c.inner_node(nodes), next_id, s);
+        writeln!(s, "  n{} -> n{} [label=\"{} - {}\"];", id, inner_id, c.inner_min, c.mu).unwrap();
+
+        if let Some(outer) = c.outer_node(nodes) {
+            let outer_id = write_node(nodes, outer, next_id, s);
+            writeln!(s, "  n{} -> n{} [label=\"{} - {}\"];", id, outer_id, c.outer_min, c.outer_max).unwrap();
+        }
+    }
+    id
+}
@@ -0,0 +1,137 @@
+//! Histograms of the distances between items.
+
+use rand::Rng;
+use rand::distributions::{IndependentSample, Range};
+
+use distance::Distance;
+
+use super::{query_distance, MetricItem, VPTree};
+
+/// The distribution of a set of distances, over bins of equal width,
+/// as returned by `VPTree::distance_histogram`.
+///
+/// Distances are converted to `f64`. The bins span the smallest to the
+/// largest distance, and the last bin includes its upper edge.
+///
+/// In high dimensions, distances tend to concentrate around their
+/// mean, and a histogram with a narrow peak far from zero means few
+/// subtrees can be pruned, whatever the tree.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DistanceHistogram {
+    /// Lower edge of the first bin: the smallest distance.
+    pub min: f64,
+    /// Upper edge of the last bin: the largest distance.
+    pub max: f64,
+    /// Number of distances in each bin.
+    pub counts: Vec<usize>,
+}
+
+impl DistanceHistogram {
+    /// Count `distances` into `bins` bins.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bins` is 0.
+    fn new(distances: &[f64], bins: usize) -> Self {
+        assert!(bins > 0, "histograms need at least one bin");
+        let min = distances.iter().cloned().fold(f64::INFINITY, f64::min);
+        let max = distances.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        let (min, max) = if distances.is_empty() { (0.0, 0.0) } else { (min, max) };
+
+        let mut counts = vec![0; bins];
+        let width = (max - min) / bins as f64;
+        for &d in distances {
+            let i = if width > 0.0 { ((d - min) / width) as usize } else { 0 };
+            counts[i.min(bins - 1)] += 1;
+        }
+        DistanceHistogram { min, max, counts }
+    }
+
+    /// Return the width of each bin.
+    pub fn bin_width(&self) -> f64 {
+        (self.max - self.min) / self.counts.len() as f64
+    }
+
+    /// Return the lower and upper edges of bin `i`.
+    pub fn bin_edges(&self, i: usize) -> (f64, f64) {
+        let w = self.bin_width();
+        (self.min + i as f64 * w, self.min + (i + 1) as f64 * w)
+    }
+
+    /// Return the number of distances counted.
+    pub fn total(&self) -> usize {
+        self.counts.iter().sum()
+    }
+
+    /// Estimate the distance below which a fraction `q` of the
+    /// distances lie, interpolating linearly within bins.
+    ///
+    /// Returns `None` if the histogram is empty.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `q` is between 0 and 1.
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        assert!((0.0..=1.0).contains(&q), "quantiles must be between 0 and 1");
+        let total = self.total();
+        if total == 0 {
+            return None;
+        }
+
+        let target = q * total as f64;
+        let mut below = 0.0;
+        for (i, &c) in self.counts.iter().enumerate() {
+            let c = c as f64;
+            if c > 0.0 && below + c >= target {
+                let (lo, hi) = self.bin_edges(i);
+                return Some(lo + (hi - lo) * ((target - below) / c).max(0.0));
+            }
+            below += c;
+        }
+        Some(self.max)
+    }
+}
+
+impl<F: Distance, T: MetricItem<F>> VPTree<F, T> {
+    /// Return the histogram of the distances from `query` to every
+    /// item in the tree, over `bins` bins.
+    ///
+    /// This measures the distance to every item.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bins` is 0, or if the metric returns a negative or
+    /// NaN distance to `query`.
+    pub fn distance_histogram(&self, query: &T, bins: usize) -> DistanceHistogram {
+        let ds: Vec<f64> = self.items().into_iter().map(|ti| query_distance(query, &ti.item).to_f64()).collect();
+        DistanceHistogram::new(&ds, bins)
+    }
+
+    /// Return the histogram of the distances between `pairs` pairs of
+    /// distinct items in the tree, drawn at random from `rng`, over
+    /// `bins` bins.
+    ///
+    /// This estimates the distribution of all pairwise distances, at
+    /// a cost of one distance evaluation per pair. The histogram is
+    /// empty if the tree holds fewer than two items.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bins` is 0.
+    pub fn sample_distance_histogram<R: Rng>(&self, pairs: usize, bins: usize, rng: &mut R) -> DistanceHistogram {
+        let items = self.items();
+        let mut ds = Vec::new();
+        if items.len() >= 2 {
+            let first = Range::new(0, items.len());
+            let second = Range::new(0, items.len() - 1);
+            ds = (0..pairs).map(|_| {
+                let i = first.ind_sample(rng);
+                // Skip over `i`, so that the pair is distinct.
+                let j = second.ind_sample(rng);
+                let j = if j >= i { j + 1 } else { j };
+                items[i].item.distance(&items[j].item).to_f64()
+            }).collect();
+        }
+        DistanceHistogram::new(&ds, bins)
+    }
+}
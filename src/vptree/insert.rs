@@ -0,0 +1,171 @@
+//! Adding items to an existing tree.
+
+use std::mem;
+use distance::Distance;
+
+use error::BuildError;
+use super::{valid_distance, MetricItem, TaggedItem, VPNode, VPTree, VPTreeBuilder};
+
+impl<F: Distance, T: MetricItem<F>> VPTree<F, T> {
+    /// Insert `item` into the tree, without rebalancing it.
+    ///
+    /// The item goes into the leaf that a search for it would reach,
+    /// or into a new leaf if that would be an empty outer subtree.
+    /// Many inserts can leave the tree unbalanced, with leaf buckets
+    /// larger than the leaf size it was built with; `extend` inserts
+    /// a batch of items and then rebuilds the tree if needed, and
+    /// `needs_rebuild` and `rebuild` let callers do the same.
+    ///
+    /// The item is given the next index, so it is reported by
+    /// `knn_graph` and the like after all the existing items. If the
+    /// tree was built to collapse duplicates and already holds an
+    /// item at distance zero, that item's count goes up instead.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the metric returns a negative or NaN distance.
+    pub fn insert(&mut self, item: T) {
+        if self.config.collapse_duplicates {
+            if let Some((id, pos)) = self.find_position(&item) {
+                let node = &mut self.nodes[id];
+                match pos {
+                    Some(i) => node.bucket[i].count += 1,
+                    None => node.center.count += 1
+                }
+                return;
+            }
+        }
+
+        let index = self.len();
+        assert!(index < u32::MAX as usize, "too many items for a single tree");
+
+        let mut ti = TaggedItem::new(item, index);
+        self.modified += 1;
+        if self.nodes.is_empty() {
+            self.nodes.push(leaf(ti));
+            return;
+        }
+
+        let new_id = self.nodes.len() as u32;
+        let mut id = 0;
+        loop {
+            let node = &mut self.nodes[id];
+            let d = T::distance(&ti.item, &node.center.item);
+            if !valid_distance(d) {
+                panic!("failed to insert into VPTree: {}", BuildError::InvalidDistance);
+            }
+            ti.dist = d;
+            node.size += 1;
+            node.radius = node.radius.larger(d);
+
+            let c = match node.contents {
+                Some(ref mut c) => c,
+                None => {
+                    node.bucket.push(ti);
+                    return;
+                }
+            };
+
+            if d <= c.mu {
+                c.inner_min = c.inner_min.smaller(d);
+                id = c.inner as usize;
+            } else {
+                c.outer_min = c.outer_min.smaller(d);
+                c.outer_max = c.outer_max.larger(d);
+                match c.outer {
+                    Some(outer) => id = outer as usize,
+                    None => {
+                        c.outer = Some(new_id);
+                        break;
+                    }
+                }
+            }
+        }
+        self.nodes.push(leaf(ti));
+    }
+
+    /// Insert clones of every item in `items`, as with `extend`.
+    pub fn extend_from_slice(&mut self, items: &[T]) where T: Clone {
+        self.extend(items.iter().cloned());
+    }
+
+    /// Return true if the tree is likely to be badly unbalanced, and
+    /// worth rebuilding.
+    ///
+    /// This is the case once a quarter of the items have been inserted
+    /// or removed since the tree was built, or when the tree is more
+    /// than twice as deep as a balanced tree of the same size would
+    /// be. Trees over many duplicate items can be deep however they
+    /// are built, so rebuilding them may not help.
+    pub fn needs_rebuild(&self) -> bool {
+        if self.heavily_modified() {
+            return true;
+        }
+        let leaves = (self.len() / self.config.leaf_size).max(1);
+        let balanced_depth = (usize::BITS - leaves.leading_zeros()) as usize + 1;
+        self.depth() > 2 * balanced_depth + 2
+    }
+
+    /// Return true if enough items have been inserted or removed since
+    /// the tree was built that it is likely to be badly unbalanced.
+    ///
+    /// Unlike `needs_rebuild`, this takes constant time.
+    pub(super) fn heavily_modified(&self) -> bool {
+        self.modified * 4 > self.len()
+    }
+
+    /// Rebuild the tree from scratch with its original options, so
+    /// that it is balanced again.
+    ///
+    /// Items keep their indices, and the node storage is reused. A
+    /// custom vantage point selector is not kept with the tree, so the
+    /// rebuilt tree uses the vantage strategy instead.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the metric returns a negative or NaN distance.
+    pub fn rebuild(&mut self) {
+        let items = self.take_items();
+        self.rebuild_from(items);
+    }
+
+    /// Remove every item from the tree, returning them in index order.
+    /// The node storage is left empty, ready to be reused.
+    pub(super) fn take_items(&mut self) -> Vec<TaggedItem<F, T>> {
+        let mut items: Vec<TaggedItem<F, T>> = Vec::with_capacity(self.len());
+        for node in self.nodes.drain(..) {
+            items.push(node.center);
+            items.extend(node.bucket);
+        }
+        items.sort_by_key(|ti| ti.index);
+        items
+    }
+
+    /// Replace the tree with one built from `items` with the tree's
+    /// original options. The items must be indexed from 0 up.
+    pub(super) fn rebuild_from(&mut self, items: Vec<TaggedItem<F, T>>) {
+        let nodes = mem::take(&mut self.nodes);
+        match VPTreeBuilder::from_config(self.config).build_tagged(items, nodes) {
+            Ok(tree) => *self = tree,
+            Err(e) => panic!("failed to rebuild VPTree: {}", e)
+        }
+    }
+}
+
+/// Create a leaf node holding only `ti`.
+fn leaf<F: Distance, T: MetricItem<F>>(ti: TaggedItem<F, T>) -> VPNode<F, T> {
+    VPNode { contents: None, center: ti, bucket: Vec::new(), radius: F::zero(), size: 1 }
+}
+
+impl<F: Distance, T: MetricItem<F>> Extend<T> for VPTree<F, T> {
+    /// Insert every item of `iter`, and then rebuild the tree once if
+    /// the new items are likely to have unbalanced it.
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.insert(item);
+        }
+        if self.heavily_modified() {
+            self.rebuild();
+        }
+    }
+}
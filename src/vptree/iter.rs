@@ -0,0 +1,54 @@
+//! Iteration over the items stored in a tree.
+
+use std::slice;
+use distance::Distance;
+
+use super::{MetricItem, TaggedItem, VPNode, VPTree};
+
+/// An iterator over the items of a `VPTree`, as returned by
+/// `VPTree::iter`.
+pub struct Iter<'a, F: Distance + 'a, T: MetricItem<F> + 'a> {
+    nodes: slice::Iter<'a, VPNode<F, T>>,
+    bucket: slice::Iter<'a, TaggedItem<F, T>>,
+    remaining: usize,
+}
+
+impl<'a, F: Distance, T: MetricItem<F>> Iterator for Iter<'a, F, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        let item = match self.bucket.next() {
+            Some(ti) => &ti.item,
+            None => {
+                let node = self.nodes.next()?;
+                self.bucket = node.bucket.iter();
+                &node.center.item
+            }
+        };
+        self.remaining -= 1;
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, F: Distance, T: MetricItem<F>> ExactSizeIterator for Iter<'a, F, T> {}
+
+impl<F: Distance, T: MetricItem<F>> VPTree<F, T> {
+    /// Return an iterator over every item in the tree, in no
+    /// particular order.
+    pub fn iter(&self) -> Iter<'_, F, T> {
+        Iter { nodes: self.nodes.iter(), bucket: [].iter(), remaining: self.len() }
+    }
+}
+
+impl<'a, F: Distance, T: MetricItem<F>> IntoIterator for &'a VPTree<F, T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, F, T>;
+
+    fn into_iter(self) -> Iter<'a, F, T> {
+        self.iter()
+    }
+}
@@ -0,0 +1,125 @@
+//! Reordering of the node arena for better memory locality.
+
+use std::collections::VecDeque;
+use distance::Distance;
+
+use super::{MetricItem, VPNode, VPTree};
+
+/// The order in which the nodes of a tree are stored in memory.
+///
+/// Every query starts at the root and works its way down, so the top
+/// levels of the tree are touched far more often than the rest.
+/// Layouts that keep those levels close together reduce cache misses
+/// on large trees. Every layout keeps the root first, and each node
+/// ahead of its subtrees.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NodeLayout {
+    /// Depth-first order, with each inner subtree ahead of the outer
+    /// one. This is the order nodes are built in, and the default.
+    #[default]
+    PreOrder,
+    /// Level by level from the root, so that the top of the tree is
+    /// contiguous.
+    BreadthFirst,
+    /// The recursive van Emde Boas layout: the top half of the levels
+    /// is laid out first, followed by each of the subtrees hanging
+    /// off it, all laid out in the same way. Nearby nodes end up
+    /// close together at every scale, without depending on the cache
+    /// size.
+    VanEmdeBoas,
+}
+
+impl<F: Distance, T: MetricItem<F>> VPTree<F, T> {
+    /// Reorder the nodes of the tree in memory according to `layout`.
+    ///
+    /// This only affects performance: the results of every query are
+    /// unchanged.
+    pub fn relayout(&mut self, layout: NodeLayout) {
+        if self.nodes.is_empty() {
+            return;
+        }
+
+        let order = match layout {
+            NodeLayout::PreOrder => pre_order(&self.nodes),
+            NodeLayout::BreadthFirst => breadth_first_order(&self.nodes),
+            NodeLayout::VanEmdeBoas => van_emde_boas_order(&self.nodes),
+        };
+
+        let mut new_pos = vec![0; order.len()];
+        for (pos, &id) in order.iter().enumerate() {
+            new_pos[id as usize] = pos as u32;
+        }
+
+        let mut old: Vec<Option<VPNode<F, T>>> = self.nodes.drain(..).map(Some).collect();
+        self.nodes = order.into_iter().map(|id| {
+            let mut node = old[id as usize].take().expect("every node appears once in the layout");
+            if let Some(ref mut c) = node.contents {
+                c.inner = new_pos[c.inner as usize];
+                c.outer = c.outer.map(|o| new_pos[o as usize]);
+            }
+            node
+        }).collect();
+    }
+}
+
+/// Return the positions of the subtrees of `node`, inner first.
+fn children<F: Distance, T: MetricItem<F>>(node: &VPNode<F, T>) -> impl Iterator<Item = u32> {
+    node.contents.as_ref().map(|c| Some(c.inner).into_iter().chain(c.outer)).into_iter().flatten()
+}
+
+fn pre_order<F: Distance, T: MetricItem<F>>(nodes: &[VPNode<F, T>]) -> Vec<u32> {
+    let mut order = Vec::with_capacity(nodes.len());
+    let mut stack = vec![0];
+    while let Some(id) = stack.pop() {
+        order.push(id);
+        // Push in reverse, so that the inner subtree comes first.
+        let c: Vec<u32> = children(&nodes[id as usize]).collect();
+        stack.extend(c.into_iter().rev());
+    }
+    order
+}
+
+fn breadth_first_order<F: Distance, T: MetricItem<F>>(nodes: &[VPNode<F, T>]) -> Vec<u32> {
+    let mut order = Vec::with_capacity(nodes.len());
+    let mut queue = VecDeque::new();
+    queue.push_back(0);
+    while let Some(id) = queue.pop_front() {
+        order.push(id);
+        queue.extend(children(&nodes[id as usize]));
+    }
+    order
+}
+
+fn van_emde_boas_order<F: Distance, T: MetricItem<F>>(nodes: &[VPNode<F, T>]) -> Vec<u32> {
+    // Every node is stored ahead of its subtrees, so walking backwards
+    // finds each subtree's height before its parent's.
+    let mut heights = vec![1; nodes.len()];
+    for (i, node) in nodes.iter().enumerate().rev() {
+        heights[i] = 1 + children(node).map(|c| heights[c as usize]).max().unwrap_or(0);
+    }
+
+    let mut order = Vec::with_capacity(nodes.len());
+    van_emde_boas(nodes, 0, heights[0], &mut order);
+    order
+}
+
+/// Lay out the first `height` levels of the subtree at `root`.
+fn van_emde_boas<F: Distance, T: MetricItem<F>>(nodes: &[VPNode<F, T>], root: u32, height: usize,
+                                              order: &mut Vec<u32>) {
+    if height == 1 {
+        order.push(root);
+        return;
+    }
+
+    let top = height / 2;
+    van_emde_boas(nodes, root, top, order);
+
+    // Collect the roots of the bottom subtrees, from inner to outer.
+    let mut level = vec![root];
+    for _ in 0..top {
+        level = level.into_iter().flat_map(|id| children(&nodes[id as usize])).collect();
+    }
+    for id in level {
+        van_emde_boas(nodes, id, height - top, order);
+    }
+}
@@ -0,0 +1,103 @@
+//! Local outlier factors of the stored items.
+
+use distance::Distance;
+
+use super::{MetricItem, VPTree};
+
+/// The local outlier factor of every stored item, with the quantities
+/// it is computed from, as returned by `VPTree::local_outlier_factors`.
+///
+/// Entry `i` of each vector belongs to the item at position `i` of the
+/// vector the tree was built from. An item's neighbors are its `k`
+/// nearest other items.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalOutlierFactors<F> {
+    /// Number of neighbors of each item.
+    pub k: usize,
+    /// Positions of each item's neighbors, nearest first.
+    pub neighbors: Vec<Vec<usize>>,
+    /// Distance from each item to its `k`th nearest neighbor.
+    pub k_distances: Vec<F>,
+    /// Local reachability density of each item: the inverse of the
+    /// mean reachability distance from the item to its neighbors.
+    /// This is infinite for an item with more than `k` duplicates
+    /// stored separately.
+    pub densities: Vec<f64>,
+    /// Local outlier factor of each item: the mean density of its
+    /// neighbors, over its own. Items in a region of uniform density
+    /// score about 1, and outliers score higher.
+    pub scores: Vec<f64>,
+}
+
+impl<F: Distance> LocalOutlierFactors<F> {
+    /// Return the reachability distance from an item to the item at
+    /// position `o`, given the distance `d` between them: the larger
+    /// of `d` and the `k`-distance of `o`.
+    ///
+    /// Using it in place of `d` smooths out the distances between
+    /// items that lie close together.
+    pub fn reachability_distance(&self, o: usize, d: F) -> F {
+        d.larger(self.k_distances[o])
+    }
+}
+
+impl<F: Distance, T: MetricItem<F>> VPTree<F, T> {
+    /// Compute the local outlier factor of every stored item, from its
+    /// `k` nearest other items, following Breunig et al.
+    ///
+    /// The neighbors are found by a single dual-tree search of the
+    /// tree against itself, as in `all_nearest_neighbors`.
+    ///
+    /// Unlike `core_distances`, the scores ignore how many input items
+    /// a collapsed duplicate stands for: a tree built to collapse
+    /// duplicates is scored as if it held each distinct item once.
+    ///
+    /// Returns `None` if `k` is zero, or if the tree holds no more
+    /// than `k` items.
+    pub fn local_outlier_factors(&self, k: usize) -> Option<LocalOutlierFactors<F>> {
+        if k == 0 || self.len() <= k {
+            return None;
+        }
+
+        // Each item is among its own nearest neighbors, unless it has
+        // more than `k` duplicates, so look one further.
+        let knn: Vec<Vec<(usize, F)>> = VPTree::all_nearest_neighbors(self, self, k + 1).into_iter().enumerate()
+            .map(|(i, ns)| ns.into_iter().filter(|&(j, _)| j != i).take(k).collect())
+            .collect();
+
+        let k_distances: Vec<F> = knn.iter().map(|ns| ns[k - 1].1).collect();
+        let mut lof = LocalOutlierFactors {
+            k,
+            neighbors: knn.iter().map(|ns| ns.iter().map(|&(j, _)| j).collect()).collect(),
+            k_distances,
+            densities: Vec::new(),
+            scores: Vec::new(),
+        };
+
+        let mean_reach: Vec<f64> = knn.iter().map(|ns| {
+            ns.iter().map(|&(j, d)| lof.reachability_distance(j, d).to_f64()).sum::<f64>() / k as f64
+        }).collect();
+        lof.densities = mean_reach.iter().map(|&r| 1.0 / r).collect();
+        lof.scores = lof.neighbors.iter().zip(&mean_reach).map(|(ns, &r)| {
+            if r == 0.0 {
+                // Every neighbor is a duplicate, with an infinite
+                // density just like this item's.
+                1.0
+            } else {
+                r * ns.iter().map(|&j| lof.densities[j]).sum::<f64>() / k as f64
+            }
+        }).collect();
+        Some(lof)
+    }
+
+    /// Return the local outlier factor of every stored item, from its
+    /// `k` nearest other items.
+    ///
+    /// Entry `i` of the result is the score of the item at position
+    /// `i` of the vector the tree was built from. Scores near 1 are
+    /// typical, and outliers score higher. See
+    /// `local_outlier_factors` for the quantities behind the scores.
+    pub fn lof_scores(&self, k: usize) -> Option<Vec<f64>> {
+        self.local_outlier_factors(k).map(|lof| lof.scores)
+    }
+}
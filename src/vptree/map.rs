@@ -0,0 +1,184 @@
+//! Trees mapping keys to payloads.
+
+use std::collections::BinaryHeap;
+use distance::Distance;
+
+use error::BuildError;
+use super::{HeapElem, MetricItem, NearestHeap, TaggedItem, VPTree, VPTreeBuilder};
+
+/// A vantage point tree over keys, each with a payload value.
+///
+/// Only the keys are compared by the metric, so payloads need no
+/// part in `MetricItem`, and queries return each key along with its
+/// value. Values can be changed in place, but keys are only ever
+/// handed out by shared reference, so changing a value cannot move
+/// its entry within the tree.
+///
+/// ```rust
+/// use vptree::{MetricItem, VPMap};
+///
+/// struct Point(f32);
+///
+/// impl MetricItem<f32> for Point {
+///     fn distance(&self, q: &Self) -> f32 {
+///         (self.0 - q.0).abs()
+///     }
+/// }
+///
+/// let map = VPMap::new(vec![(Point(1.0), "one"), (Point(5.0), "five")]);
+/// assert_eq!(map.nearest_neighbor(&Point(4.0)).map(|(_, v)| *v), Some("five"));
+/// ```
+pub struct VPMap<F: Distance, K: MetricItem<F>, V> {
+    tree: VPTree<F, K>,
+    /// The value of each key, by index.
+    values: Vec<V>,
+}
+
+impl<F: Distance, K: MetricItem<F>, V> VPMap<F, K, V> {
+    /// Construct a map from key-value pairs with the default options.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as `VPTree::new`.
+    pub fn new(entries: Vec<(K, V)>) -> Self {
+        match VPMap::try_with_builder(&VPTreeBuilder::new(), entries) {
+            Ok(map) => map,
+            Err(e) => panic!("failed to build VPMap: {}", e)
+        }
+    }
+
+    /// Construct a map from key-value pairs, using the options of
+    /// `builder`.
+    ///
+    /// Fails as `try_build` does, except that an empty set of entries
+    /// is allowed. Every key keeps its own value, so a builder that
+    /// collapses duplicates is rejected with
+    /// `BuildError::InvalidParameter`.
+    pub fn try_with_builder(builder: &VPTreeBuilder<F, K>, entries: Vec<(K, V)>) -> Result<Self, BuildError> {
+        if builder.config.collapse_duplicates {
+            return Err(BuildError::InvalidParameter("maps cannot collapse duplicate keys"));
+        }
+        let (keys, values): (Vec<K>, Vec<V>) = entries.into_iter().unzip();
+        let tree = builder.try_build_allowing_empty(keys)?;
+        Ok(VPMap { tree, values })
+    }
+
+    /// Return the tree of keys, for queries that need no values.
+    pub fn tree(&self) -> &VPTree<F, K> {
+        &self.tree
+    }
+
+    /// Return the number of entries in the map.
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Return true if the map has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// Insert a key and its value, as with `VPTree::extend`.
+    pub fn insert(&mut self, key: K, value: V) {
+        self.tree.extend(Some(key));
+        self.values.push(value);
+    }
+
+    /// Return every entry of the map, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.tree.nodes.iter()
+            .flat_map(|node| Some(&node.center).into_iter().chain(node.bucket.iter()))
+            .map(move |ti| (&ti.item, &self.values[ti.index]))
+    }
+
+    /// Return every entry of the map in insertion order, with its
+    /// value mutable.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&K, &mut V)> {
+        let mut keys: Vec<(usize, &K)> = self.tree.nodes.iter()
+            .flat_map(|node| Some(&node.center).into_iter().chain(node.bucket.iter()))
+            .map(|ti| (ti.index, &ti.item))
+            .collect();
+        keys.sort_unstable_by_key(|&(index, _)| index);
+        keys.into_iter().map(|(_, k)| k).zip(self.values.iter_mut())
+    }
+
+    /// Return every value of the map in insertion order, mutably.
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut V> {
+        self.values.iter_mut()
+    }
+
+    /// Return the value of an entry whose key is at distance zero from
+    /// `key`, if there is one.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.tree.find_tagged(key).map(|ti| &self.values[ti.index])
+    }
+
+    /// Return the value of an entry whose key is at distance zero from
+    /// `key` mutably, if there is one.
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let index = self.tree.find_tagged(key)?.index;
+        Some(&mut self.values[index])
+    }
+
+    /// Find the entry whose key is closest to `query`.
+    ///
+    /// Returns `None` if the map is empty.
+    pub fn nearest_neighbor(&self, query: &K) -> Option<(&K, &V)> {
+        self.nearest_neighbors(query, 1, false).pop()
+    }
+
+    /// Find the entry whose key is closest to `query`, with its value
+    /// mutable.
+    ///
+    /// Returns `None` if the map is empty.
+    pub fn nearest_neighbor_mut(&mut self, query: &K) -> Option<(&K, &mut V)> {
+        let x = nearest(&self.tree, query)?;
+        Some((&x.item.item, &mut self.values[x.item.index]))
+    }
+
+    /// Find the `k` entries whose keys are closest to `query`.
+    ///
+    /// Otherwise behaves like `VPTree::nearest_neighbors`.
+    pub fn nearest_neighbors(&self, query: &K, k: usize, sorted: bool) -> Vec<(&K, &V)> {
+        let mut heap: NearestHeap<F, K> = BinaryHeap::with_capacity(k);
+        if let (Some(root), true) = (self.tree.root(), k > 0) {
+            root.nearest_neighbors(&self.tree.nodes, query, k, &mut heap);
+        }
+
+        let v = if sorted {
+            heap.into_sorted_vec()
+        } else {
+            heap.into_vec()
+        };
+        v.into_iter().map(|x| (&x.item.item, &self.values[x.item.index])).collect()
+    }
+
+    /// Return every entry whose key is within a given radius of the
+    /// target.
+    ///
+    /// Otherwise behaves like `VPTree::within_radius`.
+    pub fn within_radius(&self, query: &K, radius: F, sorted: bool) -> Vec<(&K, &V)> {
+        let mut elems = Vec::new();
+        if let Some(root) = self.tree.root() {
+            root.within_radius(&self.tree.nodes, query, radius, &mut elems);
+        }
+
+        if sorted {
+            elems.sort();
+        }
+        elems.into_iter().map(|x| (&x.item.item, &self.values[x.item.index])).collect()
+    }
+}
+
+/// Find the key in `tree` closest to `query`.
+///
+/// Taking the tree rather than the map lets callers borrow the values
+/// mutably at the same time.
+fn nearest<'a, F: Distance, K: MetricItem<F>>(tree: &'a VPTree<F, K>, query: &K)
+                                          -> Option<HeapElem<'a, F, TaggedItem<F, K>>> {
+    let mut heap: NearestHeap<F, K> = BinaryHeap::with_capacity(1);
+    if let Some(root) = tree.root() {
+        root.nearest_neighbors(&tree.nodes, query, 1, &mut heap);
+    }
+    heap.pop()
+}
@@ -0,0 +1,180 @@
+//! Minimum spanning trees of the stored items, by dual-tree Borůvka.
+
+use distance::Distance;
+
+use super::{ordered_pair, DualView, MetricItem, VPTree};
+
+/// Disjoint sets of item positions, with path halving.
+struct DisjointSets {
+    parent: Vec<usize>,
+}
+
+impl DisjointSets {
+    fn new(n: usize) -> Self {
+        DisjointSets { parent: (0..n).collect() }
+    }
+
+    fn find(&mut self, mut i: usize) -> usize {
+        while self.parent[i] != i {
+            self.parent[i] = self.parent[self.parent[i]];
+            i = self.parent[i];
+        }
+        i
+    }
+
+    /// Merge the sets of `i` and `j`, returning false if they were
+    /// already the same set.
+    fn union(&mut self, i: usize, j: usize) -> bool {
+        let (a, b) = (self.find(i), self.find(j));
+        self.parent[a] = b;
+        a != b
+    }
+}
+
+/// Return true if edge `a` comes before edge `b`: by distance, and
+/// then by endpoints.
+///
+/// Breaking ties consistently keeps Borůvka from adding a cycle when
+/// several edges have the same length.
+fn precedes<F: Distance>(a: (usize, usize, F), b: (usize, usize, F)) -> bool {
+    a.2 < b.2 || (a.2 == b.2 && (a.0, a.1) < (b.0, b.1))
+}
+
+/// State for one round of a dual-tree Borůvka search: the shortest
+/// edge out of every component.
+///
+/// As in `DualKnn`, each query subtree has a bound, here the longest
+/// of the shortest edges found so far out of the components of its
+/// items. Pairs of subtrees further apart than that are skipped, as
+/// are pairs lying entirely within a single component.
+struct DualMst<'a, F: Distance + 'a> {
+    /// Component of every item, by position.
+    components: &'a [usize],
+    /// Component of all items of every subtree, if they share one,
+    /// keyed by the position of the subtree's center.
+    subtree_components: Vec<Option<usize>>,
+    subtree_bounds: Vec<F>,
+    /// Shortest edge found so far out of every component.
+    best: Vec<Option<(usize, usize, F)>>,
+}
+
+impl<'a, F: Distance> DualMst<'a, F> {
+    fn component<T: MetricItem<F>>(&self, v: DualView<F, T>) -> Option<usize> {
+        match v {
+            DualView::Whole(_, n) => self.subtree_components[n.center.index],
+            DualView::Item(ti) => Some(self.components[ti.index]),
+        }
+    }
+
+    fn bound<T: MetricItem<F>>(&self, q: DualView<F, T>) -> F {
+        match q {
+            DualView::Whole(_, n) => self.subtree_bounds[n.center.index],
+            DualView::Item(ti) => self.best[self.components[ti.index]].map_or_else(F::infinity, |e| e.2),
+        }
+    }
+
+    fn traverse<T: MetricItem<F>>(&mut self, q: DualView<F, T>, d: DualView<F, T>) {
+        if let (Some(a), Some(b)) = (self.component(q), self.component(d)) {
+            if a == b {
+                return;
+            }
+        }
+        let (qc, dc) = (q.center(), d.center());
+        let d_centers = T::distance(&qc.item, &dc.item);
+        // An edge as long as the bound may still win on its endpoints,
+        // so only prune pairs strictly further apart.
+        if d_centers.saturating_sub(q.radius()).saturating_sub(d.radius()) > self.bound(q) {
+            return;
+        }
+
+        let split_query = match (q, d) {
+            (DualView::Item(_), DualView::Item(_)) => {
+                let c = self.components[qc.index];
+                let edge = ordered_pair(qc.index, dc.index, d_centers);
+                if self.best[c].is_none_or(|best| precedes(edge, best)) {
+                    self.best[c] = Some(edge);
+                }
+                return;
+            },
+            (DualView::Whole(..), DualView::Item(_)) => true,
+            (DualView::Item(_), DualView::Whole(..)) => false,
+            _ => q.size() >= d.size()
+        };
+
+        if split_query {
+            let children = q.children();
+            for &child in &children {
+                self.traverse(child, d);
+            }
+
+            let bound = children.iter().fold(F::zero(), |acc, &c| acc.larger(self.bound(c)));
+            self.subtree_bounds[qc.index] = bound;
+        } else {
+            for child in d.children() {
+                self.traverse(q, child);
+            }
+        }
+    }
+}
+
+impl<F: Distance, T: MetricItem<F>> VPTree<F, T> {
+    /// Find a minimum spanning tree over the stored items.
+    ///
+    /// Each edge `(i, j, d)` joins the items at positions `i` and `j`
+    /// of the vector the tree was built from, with `i < j`, at
+    /// distance `d`. Edges are sorted by ascending distance, which is
+    /// the order single-linkage clustering merges clusters in. A tree
+    /// that collapses duplicates spans only the items it kept.
+    ///
+    /// This runs Borůvka's algorithm: every round joins each component
+    /// to its nearest other component, and the nearest components are
+    /// found by walking the tree against itself, so that whole pairs
+    /// of subtrees are ruled out at once. There are at most `log2(n)`
+    /// rounds for `n` items.
+    pub fn minimum_spanning_tree(&self) -> Vec<(usize, usize, F)> {
+        let root = match self.root() {
+            Some(root) => root,
+            None => return Vec::new()
+        };
+        let n = self.len();
+        let mut sets = DisjointSets::new(n);
+        let mut edges = Vec::with_capacity(n - 1);
+        while edges.len() + 1 < n {
+            let components: Vec<usize> = (0..n).map(|i| sets.find(i)).collect();
+            let mut search = DualMst {
+                components: &components,
+                subtree_components: self.subtree_components(&components),
+                subtree_bounds: vec![F::infinity(); n],
+                best: vec![None; n],
+            };
+            let whole = DualView::Whole(&self.nodes, root);
+            search.traverse(whole, whole);
+
+            for (i, j, d) in search.best.into_iter().flatten() {
+                if sets.union(i, j) {
+                    edges.push((i, j, d));
+                }
+            }
+        }
+        edges.sort_by(|a, b| a.2.partial_cmp(&b.2).expect("distances must not be NaN"));
+        edges
+    }
+
+    /// Return the component shared by all items of every subtree, if
+    /// any, keyed by the position of the subtree's center.
+    fn subtree_components(&self, components: &[usize]) -> Vec<Option<usize>> {
+        let mut shared = vec![None; components.len()];
+        // Every node is stored ahead of its subtrees, so walking
+        // backwards finds each subtree's component before its parent's.
+        for node in self.nodes.iter().rev() {
+            let c = components[node.center.index];
+            let mut same = node.bucket.iter().all(|ti| components[ti.index] == c);
+            if let Some(ref contents) = node.contents {
+                same = same && shared[contents.inner_node(&self.nodes).center.index] == Some(c)
+                    && contents.outer_node(&self.nodes).is_none_or(|o| shared[o.center.index] == Some(c));
+            }
+            shared[node.center.index] = if same { Some(c) } else { None };
+        }
+        shared
+    }
+}
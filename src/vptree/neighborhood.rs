@@ -0,0 +1,198 @@
+//! Estimates of the structure of the data from the distances between
+//! stored items and their nearest neighbors.
+
+use std::collections::BinaryHeap;
+
+use rand::Rng;
+use rand::distributions::{IndependentSample, Range};
+
+use distance::Distance;
+
+use super::{MetricItem, TaggedItem, VPTree};
+
+impl<F: Distance, T: MetricItem<F>> VPTree<F, T> {
+    /// Return up to `samples` stored items, drawn at random from `rng`,
+    /// or every item if the tree holds no more than `samples`.
+    fn sample_items<R: Rng>(&self, samples: usize, rng: &mut R) -> Vec<&TaggedItem<F, T>> {
+        let items = self.items();
+        if samples >= items.len() {
+            return items;
+        }
+        let range = Range::new(0, items.len());
+        (0..samples).map(|_| items[range.ind_sample(rng)]).collect()
+    }
+
+    /// Return the distances from the stored item `ti` to its `k`
+    /// nearest other items, in ascending order.
+    ///
+    /// An item standing for collapsed duplicates has them as its
+    /// nearest neighbors, at distance zero.
+    fn neighbor_distances(&self, ti: &TaggedItem<F, T>, k: usize) -> Vec<F> {
+        let duplicates = (ti.count as usize - 1).min(k);
+        let mut ds = vec![F::zero(); duplicates];
+
+        let mut heap = BinaryHeap::with_capacity(k - duplicates);
+        if let (Some(root), true) = (self.root(), k > duplicates) {
+            root.nearest_neighbors_filtered(&self.nodes, &ti.item, k - duplicates, &|x, _| x.index != ti.index, &mut heap);
+        }
+        ds.extend(heap.into_sorted_vec().into_iter().map(|x| x.dist));
+        ds
+    }
+
+    /// Estimate the intrinsic dimension of the stored items, from the
+    /// distances of up to `samples` of them, drawn at random from
+    /// `rng`, to their two nearest neighbors.
+    ///
+    /// This is the maximum likelihood estimate of Facco et al.'s
+    /// TwoNN method: the ratio of the second to the first nearest
+    /// neighbor distance follows a Pareto distribution whose exponent
+    /// is the dimension. Points with an exact duplicate are skipped.
+    ///
+    /// A VP-tree prunes well when the intrinsic dimension is low,
+    /// whatever the dimension of the space the items are embedded in.
+    /// Above 10 or so, searches tend to visit most of the tree.
+    ///
+    /// Returns `None` if no sampled item has two neighbors at nonzero
+    /// distances.
+    pub fn estimate_intrinsic_dim<R: Rng>(&self, samples: usize, rng: &mut R) -> Option<f64> {
+        let mut n = 0;
+        let mut log_ratios = 0.0;
+        for ti in self.sample_items(samples, rng) {
+            let ds = self.neighbor_distances(ti, 2);
+            if ds.len() < 2 {
+                continue;
+            }
+            let (r1, r2) = (ds[0].to_f64(), ds[1].to_f64());
+            if r1 > 0.0 {
+                n += 1;
+                log_ratios += (r2 / r1).ln();
+            }
+        }
+
+        if n == 0 {
+            None
+        } else {
+            Some(n as f64 / log_ratios)
+        }
+    }
+
+    /// Suggest a radius within which a typical stored item has
+    /// `expected_neighbors` other items, from the distances of up to
+    /// `samples` items, drawn at random from `rng`, to their
+    /// `expected_neighbors`th nearest neighbors.
+    ///
+    /// The suggestion is the median of those distances, so about half
+    /// of the items have at least `expected_neighbors` others no
+    /// further away. This is a starting point for the radius of
+    /// density-based clustering, like DBSCAN's epsilon.
+    ///
+    /// Returns `None` if `expected_neighbors` is zero, or if the tree
+    /// holds no more than `expected_neighbors` items.
+    pub fn suggest_radius<R: Rng>(&self, expected_neighbors: usize, samples: usize, rng: &mut R) -> Option<F> {
+        if expected_neighbors == 0 || self.len() <= expected_neighbors {
+            return None;
+        }
+
+        let mut ds: Vec<F> = self.sample_items(samples, rng).into_iter()
+            .filter_map(|ti| self.neighbor_distances(ti, expected_neighbors).pop())
+            .collect();
+        if ds.is_empty() {
+            return None;
+        }
+        let mid = (ds.len() - 1) / 2;
+        Some(*ds.select_nth_unstable_by(mid, |a, b| a.partial_cmp(b).expect("distances must not be NaN")).1)
+    }
+
+    /// Estimate the density of the stored items around `query`, from
+    /// the distance `r` to its `k`th nearest item, as
+    /// `k / (n * r^dim)` for a tree of `n` items.
+    ///
+    /// This leaves out the volume of the unit ball, which general
+    /// metrics have no formula for, so estimates are comparable with
+    /// each other but not normalized. `dim` is the dimension of the
+    /// data, or an estimate from `estimate_intrinsic_dim`. A query
+    /// with `k` items at distance zero has an infinite density.
+    /// Collapsed duplicates count for as many input items as they
+    /// stand for, in both `k` and `n`.
+    ///
+    /// Returns `None` if `k` is zero or larger than the number of
+    /// items in the tree.
+    pub fn knn_density(&self, query: &T, k: usize, dim: f64) -> Option<f64> {
+        let mut heap = BinaryHeap::with_capacity(k);
+        if let (Some(root), true) = (self.root(), k > 0) {
+            root.nearest_neighbors(&self.nodes, query, k, &mut heap);
+        }
+
+        // Each item stands for at least one input item, so the `k`
+        // nearest are enough to reach the `k`th input item.
+        let mut seen = 0;
+        let n = self.input_len();
+        heap.into_sorted_vec().into_iter().find(|x| {
+            seen += x.item.count as usize;
+            seen >= k
+        }).map(|x| density(k, n, x.dist, dim))
+    }
+
+    /// Estimate the density around every stored item, as
+    /// `knn_density` does, from its `k`th nearest other item.
+    ///
+    /// Entry `i` of the result is the estimate for the item at
+    /// position `i` of the vector the tree was built from. Returns
+    /// `None` if `k` is zero, or if the tree stands for no more than
+    /// `k` input items.
+    pub fn knn_densities(&self, k: usize, dim: f64) -> Option<Vec<f64>> {
+        let n = self.input_len();
+        if k == 0 || n <= k {
+            return None;
+        }
+
+        let mut densities = vec![0.0; self.len()];
+        for ti in self.items() {
+            let r = self.neighbor_distances(ti, k).pop().expect("the tree stands for more than k items");
+            densities[ti.index] = density(k, n, r, dim);
+        }
+        Some(densities)
+    }
+
+    /// Return the number of input items the tree stands for, counting
+    /// collapsed duplicates.
+    fn input_len(&self) -> usize {
+        self.item_counts().into_iter().sum()
+    }
+
+    /// Return the core distance of every stored item: the distance to
+    /// its `k`th nearest item, counting itself.
+    ///
+    /// Entry `i` of the result belongs to the item at position `i` of
+    /// the vector the tree was built from. These are the core
+    /// distances of HDBSCAN and OPTICS, with `k` their minimum cluster
+    /// size, and the mutual reachability distance between two items
+    /// is the larger of their distance and both their core distances.
+    /// Collapsed duplicates count for as many input items as they
+    /// stand for.
+    ///
+    /// The neighbors are found by a single dual-tree search of the
+    /// tree against itself, as in `all_nearest_neighbors`. Items have
+    /// an infinite core distance if the tree stands for fewer than `k`
+    /// input items.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is 0.
+    pub fn core_distances(&self, k: usize) -> Vec<F> {
+        assert!(k > 0, "core distances need k of at least 1");
+        let counts = self.item_counts();
+        VPTree::all_nearest_neighbors(self, self, k).into_iter().map(|ns| {
+            let mut seen = 0;
+            ns.into_iter().find(|&(j, _)| {
+                seen += counts[j];
+                seen >= k
+            }).map_or_else(F::infinity, |(_, d)| d)
+        }).collect()
+    }
+}
+
+/// Return `k / (n * r^dim)`.
+fn density<F: Distance>(k: usize, n: usize, r: F, dim: f64) -> f64 {
+    k as f64 / (n as f64 * r.to_f64().powf(dim))
+}
@@ -0,0 +1,280 @@
+//! A tree whose versions share unchanged subtrees.
+
+use std::collections::BinaryHeap;
+use std::sync::Arc;
+use distance::Distance;
+
+use error::BuildError;
+use super::{push_nearest, query_distance, valid_distance, HeapElem, InnerNode, MetricItem, NearestHeap, TaggedItem, VPNode, VPTree};
+
+/// A vantage point tree with cheap copies.
+///
+/// Nodes are reference-counted rather than stored in an arena.
+/// Cloning the tree only copies a pointer to the root, and inserting
+/// into a clone copies just the nodes on the path to the new item, so
+/// every other subtree stays shared between the versions. Holding on
+/// to a clone gives a point-in-time snapshot that later inserts do
+/// not affect.
+///
+/// Like `VPTree::insert`, inserts never rebalance the tree. Convert a
+/// freshly built `VPTree` when a version has become badly unbalanced.
+///
+/// ```rust
+/// use vptree::{MetricItem, PersistentVPTree};
+///
+/// #[derive(Clone)]
+/// struct Point(f32);
+///
+/// impl MetricItem<f32> for Point {
+///     fn distance(&self, q: &Self) -> f32 {
+///         (self.0 - q.0).abs()
+///     }
+/// }
+///
+/// let mut tree = PersistentVPTree::new((0..100).map(|x| Point(x as f32)).collect());
+/// let snapshot = tree.clone();
+/// tree.insert(Point(100.0));
+/// assert_eq!(tree.len(), 101);
+/// assert_eq!(snapshot.len(), 100);
+/// ```
+pub struct PersistentVPTree<F: Distance, T: MetricItem<F>> {
+    root: Option<Arc<PNode<F, T>>>,
+}
+
+/// A node of a persistent tree, laid out like `VPNode`.
+#[derive(Clone)]
+struct PNode<F: Distance, T: MetricItem<F>> {
+    contents: Option<InnerNode<F, Arc<PNode<F, T>>>>,
+    center: TaggedItem<F, T>,
+    bucket: Vec<TaggedItem<F, T>>,
+    radius: F,
+    size: usize,
+}
+
+impl<F: Distance, T: MetricItem<F>> PNode<F, T> {
+    /// Return a lower bound on the distance from an object to
+    /// anything in this subtree, given the distance from the object to
+    /// the parent's vantage point.
+    fn parent_bound(&self, d_parent: F) -> F {
+        self.center.center_bound(d_parent).saturating_sub(self.radius)
+    }
+}
+
+impl<F: Distance, T: MetricItem<F>> PersistentVPTree<F, T> {
+    /// Construct a new tree from a set of elements with the default
+    /// options.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as `VPTree::new`.
+    pub fn new(items: Vec<T>) -> Self {
+        PersistentVPTree::from(VPTree::new(items))
+    }
+
+    /// Return the number of items in the tree.
+    pub fn len(&self) -> usize {
+        self.root.as_ref().map_or(0, |root| root.size)
+    }
+
+    /// Return true if the tree holds no items.
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    /// Insert `item` into this version of the tree.
+    ///
+    /// Nodes shared with other versions are copied before being
+    /// changed, so the other versions are unaffected.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the metric returns a negative or NaN distance.
+    pub fn insert(&mut self, item: T) where T: Clone {
+        let mut ti = TaggedItem::new(item, self.len());
+        let mut node = match self.root {
+            Some(ref mut root) => Arc::make_mut(root),
+            None => {
+                self.root = Some(Arc::new(leaf(ti)));
+                return;
+            }
+        };
+
+        loop {
+            let d = T::distance(&ti.item, &node.center.item);
+            if !valid_distance(d) {
+                panic!("failed to insert into PersistentVPTree: {}", BuildError::InvalidDistance);
+            }
+            ti.dist = d;
+            node.size += 1;
+            node.radius = node.radius.larger(d);
+
+            let c = match node.contents {
+                Some(ref mut c) => c,
+                None => {
+                    node.bucket.push(ti);
+                    return;
+                }
+            };
+
+            if d <= c.mu {
+                c.inner_min = c.inner_min.smaller(d);
+                node = Arc::make_mut(&mut c.inner);
+            } else {
+                c.outer_min = c.outer_min.smaller(d);
+                c.outer_max = c.outer_max.larger(d);
+                match c.outer {
+                    Some(ref mut outer) => node = Arc::make_mut(outer),
+                    None => {
+                        c.outer = Some(Arc::new(leaf(ti)));
+                        return;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Find the closest point in the tree to `query`.
+    ///
+    /// Returns `None` if the tree is empty.
+    pub fn nearest_neighbor(&self, query: &T) -> Option<&T> {
+        self.nearest_neighbors(query, 1, false).pop()
+    }
+
+    /// Find the `k` points in the tree closest to `query`.
+    ///
+    /// Behaves like `VPTree::nearest_neighbors`.
+    pub fn nearest_neighbors(&self, query: &T, k: usize, sorted: bool) -> Vec<&T> {
+        let mut heap: NearestHeap<F, T> = BinaryHeap::with_capacity(k);
+        let mut stack: Vec<(&PNode<F, T>, F)> = self.root.iter().map(|r| (&**r, F::zero())).collect();
+        if k == 0 {
+            stack.clear();
+        }
+
+        while let Some((node, bound)) = stack.pop() {
+            if heap.len() >= k && heap.peek().unwrap().dist <= bound {
+                continue;
+            }
+
+            let d_center = query_distance(query, &node.center.item);
+            push_nearest(&mut heap, k, &node.center, d_center);
+            for ti in &node.bucket {
+                if heap.len() >= k && ti.center_bound(d_center) >= heap.peek().unwrap().dist {
+                    continue;
+                }
+                push_nearest(&mut heap, k, ti, query_distance(query, &ti.item));
+            }
+
+            if let Some(ref c) = node.contents {
+                let (inner_bound, outer_bound) = c.bounds(d_center);
+                let mut children = [(Some(&*c.inner), inner_bound), (c.outer.as_deref(), outer_bound)];
+
+                // Search the side of the split the query is on first.
+                if d_center > c.mu {
+                    children.swap(0, 1);
+                }
+                for &(child, bound) in children.iter().rev() {
+                    if let Some(child) = child {
+                        stack.push((child, bound.larger(child.parent_bound(d_center))));
+                    }
+                }
+            }
+        }
+
+        let v = if sorted {
+            heap.into_sorted_vec()
+        } else {
+            heap.into_vec()
+        };
+        v.into_iter().map(|x| &x.item.item).collect()
+    }
+
+    /// Return all elements within a given radius of the target.
+    ///
+    /// Behaves like `VPTree::within_radius`.
+    pub fn within_radius(&self, query: &T, radius: F, sorted: bool) -> Vec<&T> {
+        let mut elems = Vec::new();
+        let mut stack: Vec<&PNode<F, T>> = self.root.iter().map(|r| &**r).collect();
+        while let Some(node) = stack.pop() {
+            let d_center = query_distance(query, &node.center.item);
+            if d_center < radius {
+                elems.push(HeapElem::new(d_center, &node.center));
+            }
+            for ti in &node.bucket {
+                if ti.center_bound(d_center) >= radius {
+                    continue;
+                }
+                let d = query_distance(query, &ti.item);
+                if d < radius {
+                    elems.push(HeapElem::new(d, ti));
+                }
+            }
+
+            if let Some(ref c) = node.contents {
+                let (inner_bound, outer_bound) = c.bounds(d_center);
+                if radius > inner_bound && radius > c.inner.parent_bound(d_center) {
+                    stack.push(&c.inner);
+                }
+                if let Some(ref outer) = c.outer {
+                    if radius > outer_bound && radius > outer.parent_bound(d_center) {
+                        stack.push(outer);
+                    }
+                }
+            }
+        }
+
+        if sorted {
+            elems.sort();
+        }
+        elems.into_iter().map(|x| &x.item.item).collect()
+    }
+}
+
+/// Create a leaf node holding only `ti`.
+fn leaf<F: Distance, T: MetricItem<F>>(ti: TaggedItem<F, T>) -> PNode<F, T> {
+    PNode { contents: None, center: ti, bucket: Vec::new(), radius: F::zero(), size: 1 }
+}
+
+impl<F: Distance, T: MetricItem<F>> From<VPTree<F, T>> for PersistentVPTree<F, T> {
+    /// Convert an arena-based tree, keeping its structure.
+    fn from(tree: VPTree<F, T>) -> Self {
+        // Every node is stored ahead of its subtrees, so walking
+        // backwards converts each subtree before its parent.
+        let mut converted: Vec<Option<Arc<PNode<F, T>>>> = (0..tree.nodes.len()).map(|_| None).collect();
+        for (id, node) in tree.nodes.into_iter().enumerate().rev() {
+            let VPNode { contents, center, bucket, radius, size } = node;
+            let contents = contents.map(|c| InnerNode {
+                mu: c.mu,
+                inner_min: c.inner_min,
+                outer_min: c.outer_min,
+                outer_max: c.outer_max,
+                inner: converted[c.inner as usize].take().expect("subtrees are converted first"),
+                outer: c.outer.map(|o| converted[o as usize].take().expect("subtrees are converted first")),
+            });
+            converted[id] = Some(Arc::new(PNode { contents, center, bucket, radius, size }));
+        }
+        PersistentVPTree { root: converted.into_iter().next().and_then(|root| root) }
+    }
+}
+
+impl<F: Distance, T: MetricItem<F>> Clone for PersistentVPTree<F, T> {
+    /// Return a new version sharing every node with this one.
+    fn clone(&self) -> Self {
+        PersistentVPTree { root: self.root.clone() }
+    }
+}
+
+impl<F: Distance, T: MetricItem<F>> Drop for PersistentVPTree<F, T> {
+    /// Release the nodes no other version shares, without recursing,
+    /// so that dropping a badly skewed tree cannot overflow the stack.
+    fn drop(&mut self) {
+        let mut stack: Vec<Arc<PNode<F, T>>> = self.root.take().into_iter().collect();
+        while let Some(node) = stack.pop() {
+            if let Ok(node) = Arc::try_unwrap(node) {
+                if let Some(c) = node.contents {
+                    stack.push(c.inner);
+                    stack.extend(c.outer);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,243 @@
+//! Low-level, read-only access to the nodes of a tree.
+//!
+//! The queries of `VPTree` cover exact searches. To build other
+//! strategies on the same structure, like beam searches, learned
+//! pruning rules or approximate searches with a budget, this module
+//! exposes each node's items, split distances and stored distances,
+//! along with the lower bounds the tree's own searches prune with.
+//!
+//! Nodes are addressed by their position in the tree's node storage,
+//! with the root at position 0 and every node ahead of its subtrees.
+//! This layout is an implementation detail, and may change between
+//! versions even where the rest of the API does not, which is why the
+//! module sits behind the `raw` feature.
+//!
+//! ```rust
+//! use vptree::{MetricItem, VPTree};
+//! use vptree::raw::RawTree;
+//!
+//! struct Point(f32);
+//!
+//! impl MetricItem<f32> for Point {
+//!     fn distance(&self, q: &Self) -> f32 {
+//!         (self.0 - q.0).abs()
+//!     }
+//! }
+//!
+//! /// A greedy search, following only the more promising subtree of
+//! /// each node.
+//! fn greedy<'a>(tree: RawTree<'a, f32, Point>, q: &Point) -> Option<&'a Point> {
+//!     let mut best: Option<(f32, &Point)> = None;
+//!     let mut node = tree.root();
+//!     while let Some(n) = node {
+//!         for item in n.items() {
+//!             let d = q.distance(item.item);
+//!             if best.is_none_or(|(b, _)| d < b) {
+//!                 best = Some((d, item.item));
+//!             }
+//!         }
+//!         node = n.split().map(|s| {
+//!             let (inner, outer) = s.bounds(q.distance(n.center().item));
+//!             match s.outer {
+//!                 Some(o) if outer < inner => tree.node(o),
+//!                 _ => tree.node(s.inner),
+//!             }
+//!         });
+//!     }
+//!     best.map(|(_, item)| item)
+//! }
+//!
+//! let tree = VPTree::new((0..1000).map(|x| Point(x as f32)).collect());
+//! assert_eq!(greedy(tree.raw(), &Point(123.4)).unwrap().0, 123.0);
+//! ```
+
+use distance::Distance;
+
+use super::{InnerNode, MetricItem, TaggedItem, VPNode, VPTree};
+
+/// Read-only access to the nodes of a tree, as returned by
+/// `VPTree::raw`.
+pub struct RawTree<'a, F: Distance + 'a, T: MetricItem<F> + 'a> {
+    nodes: &'a [VPNode<F, T>],
+}
+
+/// A node of a tree.
+///
+/// A node holds a vantage point, its center, and either a split into
+/// an inner and an optional outer subtree, or a leaf bucket of other
+/// items.
+pub struct RawNode<'a, F: Distance + 'a, T: MetricItem<F> + 'a> {
+    id: usize,
+    node: &'a VPNode<F, T>,
+}
+
+/// An item of a node, with what the tree stores alongside it.
+pub struct RawItem<'a, F: Distance + 'a, T: MetricItem<F> + 'a> {
+    /// The item itself.
+    pub item: &'a T,
+    /// Position of the item in the vector the tree was built from.
+    pub index: usize,
+    /// For a node's center, its distance to the vantage point of the
+    /// parent node, or zero for the root. For an item of a leaf
+    /// bucket, its distance to the leaf's center.
+    pub dist: F,
+    /// Number of input items the item stands for, which is more than
+    /// 1 only if the tree collapses duplicates.
+    pub count: usize,
+}
+
+/// The split of a non-leaf node into subtrees.
+///
+/// Every inner item lies between `inner_min` and `mu` from the
+/// center, and every outer item between `outer_min` and `outer_max`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RawSplit<F> {
+    /// Largest distance from the center to any inner item.
+    pub mu: F,
+    /// Smallest distance from the center to any inner item.
+    pub inner_min: F,
+    /// Smallest distance from the center to any outer item.
+    pub outer_min: F,
+    /// Largest distance from the center to any outer item.
+    pub outer_max: F,
+    /// Position of the inner subtree.
+    pub inner: usize,
+    /// Position of the outer subtree, if any items lie outside `mu`.
+    pub outer: Option<usize>,
+}
+
+impl<'a, F: Distance, T: MetricItem<F>> Clone for RawTree<'a, F, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, F: Distance, T: MetricItem<F>> Copy for RawTree<'a, F, T> {}
+
+impl<'a, F: Distance, T: MetricItem<F>> Clone for RawNode<'a, F, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, F: Distance, T: MetricItem<F>> Copy for RawNode<'a, F, T> {}
+
+impl<'a, F: Distance, T: MetricItem<F>> RawTree<'a, F, T> {
+    /// Return the number of nodes in the tree.
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Return the root node, or `None` if the tree is empty.
+    pub fn root(&self) -> Option<RawNode<'a, F, T>> {
+        self.get(0)
+    }
+
+    /// Return the node at position `id`, or `None` if there is none.
+    pub fn get(&self, id: usize) -> Option<RawNode<'a, F, T>> {
+        self.nodes.get(id).map(|node| RawNode { id, node })
+    }
+
+    /// Return the node at position `id`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no node at `id`.
+    pub fn node(&self, id: usize) -> RawNode<'a, F, T> {
+        self.get(id).expect("node position out of range")
+    }
+
+    /// Return every node, in storage order.
+    pub fn nodes(&self) -> impl Iterator<Item = RawNode<'a, F, T>> + 'a {
+        self.nodes.iter().enumerate().map(|(id, node)| RawNode { id, node })
+    }
+}
+
+/// Expose a stored item.
+fn raw_item<F: Distance, T: MetricItem<F>>(ti: &TaggedItem<F, T>) -> RawItem<'_, F, T> {
+    RawItem { item: &ti.item, index: ti.index, dist: ti.dist, count: ti.count as usize }
+}
+
+impl<'a, F: Distance, T: MetricItem<F>> RawNode<'a, F, T> {
+    /// Return the position of the node.
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Return the vantage point of the node.
+    pub fn center(&self) -> RawItem<'a, F, T> {
+        raw_item(&self.node.center)
+    }
+
+    /// Return the items of the node's leaf bucket, other than the
+    /// center. Non-leaf nodes have none.
+    pub fn bucket(&self) -> impl Iterator<Item = RawItem<'a, F, T>> + 'a {
+        self.node.bucket.iter().map(raw_item)
+    }
+
+    /// Return the center, followed by the items of the leaf bucket.
+    pub fn items(&self) -> impl Iterator<Item = RawItem<'a, F, T>> + 'a {
+        Some(&self.node.center).into_iter().chain(self.node.bucket.iter()).map(raw_item)
+    }
+
+    /// Return the distance from the center to the furthest item in
+    /// the subtree rooted at the node.
+    pub fn radius(&self) -> F {
+        self.node.radius
+    }
+
+    /// Return the number of items in the subtree rooted at the node,
+    /// including the center.
+    pub fn size(&self) -> usize {
+        self.node.size
+    }
+
+    /// Return the split of a non-leaf node, or `None` for a leaf.
+    pub fn split(&self) -> Option<RawSplit<F>> {
+        self.node.contents.as_ref().map(|c| {
+            let InnerNode { mu, inner_min, outer_min, outer_max, inner, outer } = *c;
+            RawSplit {
+                mu, inner_min, outer_min, outer_max,
+                inner: inner as usize,
+                outer: outer.map(|o| o as usize),
+            }
+        })
+    }
+
+    /// Return a lower bound on the distance from an object to any item
+    /// of the subtree rooted at the node, given the distance
+    /// `d_parent` from the object to the parent node's center.
+    ///
+    /// This costs no distance evaluations, since the distance between
+    /// the two centers is stored.
+    pub fn parent_bound(&self, d_parent: F) -> F {
+        self.node.parent_bound(d_parent)
+    }
+}
+
+impl<F: Distance> RawSplit<F> {
+    /// Return lower bounds on the distance from an object to any item
+    /// of the inner and outer subtrees, given the distance `d` from
+    /// the object to the node's center.
+    ///
+    /// The bounds may be negative, or zero for unsigned distances,
+    /// when the object lies within a subtree's range of distances.
+    pub fn bounds(&self, d: F) -> (F, F) {
+        (self.inner_min.saturating_sub(d).larger(d.saturating_sub(self.mu)),
+         self.outer_min.saturating_sub(d).larger(d.saturating_sub(self.outer_max)))
+    }
+}
+
+/// Return a lower bound on the distance from an object to an item of
+/// a leaf bucket, given the distance `d` from the object to the leaf's
+/// center and the item's stored distance `dist` to it.
+pub fn item_bound<F: Distance>(dist: F, d: F) -> F {
+    d.abs_diff(dist)
+}
+
+impl<F: Distance, T: MetricItem<F>> VPTree<F, T> {
+    /// Return low-level, read-only access to the nodes of the tree.
+    pub fn raw(&self) -> RawTree<'_, F, T> {
+        RawTree { nodes: &self.nodes }
+    }
+}
@@ -0,0 +1,124 @@
+//! Removing items from an existing tree.
+
+use std::collections::BinaryHeap;
+use std::mem;
+use distance::Distance;
+
+use super::{MetricItem, NearestHeap, TaggedItem, VPTree};
+
+impl<F: Distance, T: MetricItem<F>> VPTree<F, T> {
+    /// Remove every item for which `f` returns false.
+    ///
+    /// Items removed from leaf buckets are simply dropped, and a leaf
+    /// whose vantage point is removed promotes one of its remaining
+    /// items in its place. If the vantage point of an inner node is
+    /// removed, a leaf is emptied, or enough items have been removed
+    /// that the tree is likely to be badly unbalanced, the tree is
+    /// rebuilt from the remaining items instead.
+    ///
+    /// The remaining items keep their relative order, and are indexed
+    /// from 0 up again.
+    pub fn retain<P: Fn(&T) -> bool>(&mut self, f: P) {
+        self.retain_tagged(|ti| f(&ti.item));
+    }
+
+    /// Remove the nearest item to `query` from the tree and return it,
+    /// or return `None` if the tree is empty.
+    ///
+    /// The item is removed as with `retain`, so the search costs as
+    /// many distance evaluations as `nearest_neighbor`, but the
+    /// removal takes time linear in the size of the tree. If
+    /// duplicates were collapsed into the item, they are all removed
+    /// along with it.
+    pub fn pop_nearest(&mut self, query: &T) -> Option<T> {
+        let index = {
+            let mut heap: NearestHeap<F, T> = BinaryHeap::with_capacity(1);
+            self.root()?.nearest_neighbors(&self.nodes, query, 1, &mut heap);
+            heap.pop()?.item.index
+        };
+        self.retain_tagged(|ti| ti.index != index).pop().map(|ti| ti.item)
+    }
+
+    /// Like `retain`, but `f` is given each item tagged with its index
+    /// before any items are removed, and the removed items are
+    /// returned, in no particular order.
+    pub(super) fn retain_tagged<P: Fn(&TaggedItem<F, T>) -> bool>(&mut self, f: P) -> Vec<TaggedItem<F, T>> {
+        let mut keep = vec![true; self.len()];
+        let mut removed = 0;
+        let mut restructure = false;
+        for node in &self.nodes {
+            for ti in &node.bucket {
+                if !f(ti) {
+                    keep[ti.index] = false;
+                    removed += 1;
+                }
+            }
+            if !f(&node.center) {
+                keep[node.center.index] = false;
+                removed += 1;
+                restructure |= node.contents.is_some() || node.bucket.iter().all(|ti| !keep[ti.index]);
+            }
+        }
+        if removed == 0 {
+            return Vec::new();
+        }
+
+        // Indices stay dense, so each remaining item moves down by the
+        // number of removed items ahead of it.
+        let mut new_index = Vec::with_capacity(keep.len());
+        let mut next = 0;
+        for &k in &keep {
+            new_index.push(next);
+            next += k as usize;
+        }
+
+        self.modified += removed;
+        if restructure || self.heavily_modified() {
+            let (mut items, gone): (Vec<_>, Vec<_>) = self.take_items().into_iter().partition(|ti| keep[ti.index]);
+            for ti in &mut items {
+                ti.index = new_index[ti.index];
+            }
+            self.rebuild_from(items);
+            return gone;
+        }
+
+        let mut parent = vec![None; self.nodes.len()];
+        for (i, node) in self.nodes.iter().enumerate() {
+            if let Some(ref c) = node.contents {
+                parent[c.inner as usize] = Some(i);
+                if let Some(outer) = c.outer {
+                    parent[outer as usize] = Some(i);
+                }
+            }
+        }
+
+        let mut gone = Vec::with_capacity(removed);
+        for id in 0..self.nodes.len() {
+            let (bucket, dropped): (Vec<_>, Vec<_>) = mem::take(&mut self.nodes[id].bucket).into_iter()
+                .partition(|ti| keep[ti.index]);
+            self.nodes[id].bucket = bucket;
+            gone.extend(dropped);
+            if !keep[self.nodes[id].center.index] {
+                // Only leaves get here. The distances from the new
+                // vantage point replace those from the old one.
+                let mut center = self.nodes[id].bucket.swap_remove(0);
+                center.dist = parent[id].map_or(F::zero(), |p| T::distance(&center.item, &self.nodes[p].center.item));
+
+                let node = &mut self.nodes[id];
+                node.radius = F::zero();
+                for ti in &mut node.bucket {
+                    ti.dist = T::distance(&ti.item, &center.item);
+                    node.radius = node.radius.larger(ti.dist);
+                }
+                gone.push(mem::replace(&mut node.center, center));
+            }
+
+            let node = &mut self.nodes[id];
+            for ti in Some(&mut node.center).into_iter().chain(node.bucket.iter_mut()) {
+                ti.index = new_index[ti.index];
+            }
+        }
+        self.recompute_sizes();
+        gone
+    }
+}
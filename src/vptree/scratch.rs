@@ -0,0 +1,111 @@
+//! Reusable buffers for allocation-free queries.
+
+use std::collections::BinaryHeap;
+use distance::Distance;
+
+use super::{HeapElem, MetricItem, NearestHeap, TaggedItem, VPNode, VPTree};
+
+/// Buffers reused across queries on a tree.
+///
+/// Every call to `nearest_neighbors` or `within_radius` allocates its
+/// own working space and result vector. Passing the same scratch
+/// object to `nearest_neighbors_with` or `within_radius_with` instead
+/// lets the buffers grow to fit the largest query once, after which
+/// queries run without allocating.
+///
+/// ```rust
+/// use vptree::{MetricItem, QueryScratch, VPTree};
+///
+/// struct Point(f32);
+///
+/// impl MetricItem<f32> for Point {
+///     fn distance(&self, q: &Self) -> f32 {
+///         (self.0 - q.0).abs()
+///     }
+/// }
+///
+/// let tree = VPTree::new((0..100).map(|x| Point(x as f32)).collect());
+/// let mut scratch = QueryScratch::new();
+/// for x in 0..1000 {
+///     let nearest = tree.nearest_neighbors_with(&mut scratch, &Point(x as f32 / 10.0), 3, true);
+///     assert_eq!(nearest.len(), 3);
+/// }
+/// ```
+pub struct QueryScratch<'a, F: Distance + 'a, T: MetricItem<F> + 'a> {
+    heap: NearestHeap<'a, F, T>,
+    elems: Vec<HeapElem<'a, F, TaggedItem<F, T>>>,
+    nearest_stack: Vec<(&'a VPNode<F, T>, F)>,
+    radius_stack: Vec<&'a VPNode<F, T>>,
+    results: Vec<&'a T>,
+}
+
+impl<'a, F: Distance, T: MetricItem<F>> QueryScratch<'a, F, T> {
+    /// Create a set of empty buffers.
+    pub fn new() -> Self {
+        QueryScratch {
+            heap: BinaryHeap::new(),
+            elems: Vec::new(),
+            nearest_stack: Vec::new(),
+            radius_stack: Vec::new(),
+            results: Vec::new(),
+        }
+    }
+
+    /// Move the collected elements into the results, sorting them by
+    /// distance first if requested.
+    fn finish(&mut self, sorted: bool) -> &[&'a T] {
+        if sorted {
+            self.elems.sort_unstable();
+        }
+        self.results.clear();
+        self.results.extend(self.elems.drain(..).map(|x| &x.item.item));
+        &self.results
+    }
+}
+
+impl<'a, F: Distance, T: MetricItem<F>> Default for QueryScratch<'a, F, T> {
+    fn default() -> Self {
+        QueryScratch::new()
+    }
+}
+
+impl<F: Distance, T: MetricItem<F>> VPTree<F, T> {
+    /// Find the `k` points in the tree closest to `query`, using the
+    /// buffers in `scratch`.
+    ///
+    /// Otherwise behaves like `nearest_neighbors`. The results stay in
+    /// `scratch` until its next use.
+    pub fn nearest_neighbors_with<'a, 's>(&'a self, scratch: &'s mut QueryScratch<'a, F, T>, query: &T,
+                                          k: usize, sorted: bool) -> &'s [&'a T] {
+        scratch.heap.clear();
+        if let (Some(root), true) = (self.root(), k > 0) {
+            let found = root.nearest_neighbors_with_stack(&self.nodes, query, k, &|_, _| true,
+                                                          &mut scratch.heap, &mut scratch.nearest_stack);
+            if let Err(e) = found {
+                panic!("failed to query VPTree: {}", e);
+            }
+        }
+
+        scratch.elems.clear();
+        scratch.elems.extend(scratch.heap.drain());
+        scratch.finish(sorted)
+    }
+
+    /// Return all elements within a given radius of the target, using
+    /// the buffers in `scratch`.
+    ///
+    /// Otherwise behaves like `within_radius`. The results stay in
+    /// `scratch` until its next use.
+    pub fn within_radius_with<'a, 's>(&'a self, scratch: &'s mut QueryScratch<'a, F, T>, query: &T,
+                                      radius: F, sorted: bool) -> &'s [&'a T] {
+        scratch.elems.clear();
+        if let Some(root) = self.root() {
+            let found = root.within_radius_with_stack(&self.nodes, query, radius, &mut scratch.elems,
+                                                      &mut scratch.radius_stack);
+            if let Err(e) = found {
+                panic!("failed to query VPTree: {}", e);
+            }
+        }
+        scratch.finish(sorted)
+    }
+}
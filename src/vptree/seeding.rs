@@ -0,0 +1,184 @@
+//! Choosing well-spread sets of stored items, as cluster centers or
+//! samples.
+
+use rand::Rng;
+use rand::distributions::{IndependentSample, Range};
+
+use distance::Distance;
+
+use super::{MetricItem, VPNode, VPTree};
+
+/// The distance from every stored item to the nearest of a growing set
+/// of centers.
+///
+/// Alongside, we keep the largest of those distances within every
+/// subtree. A new center can only bring items closer if it lies
+/// within that distance of the subtree's bounding ball, so most
+/// subtrees are skipped once there are a few centers.
+struct NearestCenters<'a, F: Distance + 'a, T: MetricItem<F> + 'a> {
+    nodes: &'a [VPNode<F, T>],
+    /// Distance to the nearest center, by position.
+    distances: Vec<F>,
+    /// Largest distance to the nearest center within each subtree, by
+    /// node.
+    subtree_max: Vec<F>,
+}
+
+impl<'a, F: Distance, T: MetricItem<F>> NearestCenters<'a, F, T> {
+    fn new(tree: &'a VPTree<F, T>) -> Self {
+        NearestCenters {
+            nodes: &tree.nodes,
+            distances: vec![F::infinity(); tree.len()],
+            subtree_max: vec![F::infinity(); tree.nodes.len()],
+        }
+    }
+
+    fn add(&mut self, center: &T) {
+        if !self.nodes.is_empty() {
+            self.update(0, center);
+        }
+    }
+
+    /// Bring the items of the subtree at `id` closer to `center`.
+    fn update(&mut self, id: usize, center: &T) {
+        let nodes = self.nodes;
+        let node = &nodes[id];
+        let d_center = T::distance(center, &node.center.item);
+        let nearest = &mut self.distances[node.center.index];
+        *nearest = nearest.smaller(d_center);
+        let mut max = *nearest;
+
+        for ti in &node.bucket {
+            let nearest = &mut self.distances[ti.index];
+            if ti.center_bound(d_center) < *nearest {
+                *nearest = nearest.smaller(T::distance(center, &ti.item));
+            }
+            max = max.larger(*nearest);
+        }
+
+        if let Some(ref contents) = node.contents {
+            let (inner_bound, outer_bound) = contents.bounds(d_center);
+            let children = [(Some(contents.inner as usize), inner_bound), (contents.outer.map(|o| o as usize), outer_bound)];
+            for &(child, bound) in &children {
+                if let Some(child) = child {
+                    if bound.larger(nodes[child].parent_bound(d_center)) < self.subtree_max[child] {
+                        self.update(child, center);
+                    }
+                    max = max.larger(self.subtree_max[child]);
+                }
+            }
+        }
+        self.subtree_max[id] = max;
+    }
+
+    /// Return the position of an item furthest from its nearest
+    /// center, found by following the subtree maxima down from the
+    /// root.
+    fn furthest(&self) -> Option<usize> {
+        let mut node = self.nodes.first()?;
+        let max = self.subtree_max[0];
+        loop {
+            if let Some(ti) = Some(&node.center).into_iter().chain(node.bucket.iter()).find(|ti| self.distances[ti.index] == max) {
+                return Some(ti.index);
+            }
+            let contents = node.contents.as_ref().expect("the maximum lies in some subtree");
+            node = if self.subtree_max[contents.inner as usize] == max {
+                contents.inner_node(self.nodes)
+            } else {
+                contents.outer_node(self.nodes).expect("the maximum lies in some subtree")
+            };
+        }
+    }
+}
+
+impl<F: Distance, T: MetricItem<F>> VPTree<F, T> {
+    /// Choose up to `k` stored items as initial cluster centers, by
+    /// k-means++ seeding with random draws from `rng`.
+    ///
+    /// The first center is drawn uniformly, and each further center
+    /// with probability proportional to the squared distance to the
+    /// nearest center chosen so far, so that the centers spread out
+    /// over the data. The result lists the positions of the centers in
+    /// the vector the tree was built from, in the order they were
+    /// chosen. In a tree that collapses duplicates, each item weighs
+    /// as much as the input items it stands for.
+    ///
+    /// Adding a center only measures its distance to the items it
+    /// could bring closer, found by searching the tree, rather than to
+    /// every item. Fewer than `k` centers are returned if the tree
+    /// holds fewer than `k` distinct items.
+    pub fn kmeans_plus_plus<R: Rng>(&self, k: usize, rng: &mut R) -> Vec<usize> {
+        let items = self.items_by_position();
+        let counts = self.item_counts();
+        let mut nearest = NearestCenters::new(self);
+        let mut centers = Vec::with_capacity(k);
+        while centers.len() < k {
+            let weights: Vec<f64> = if centers.is_empty() {
+                counts.iter().map(|&c| c as f64).collect()
+            } else {
+                counts.iter().zip(&nearest.distances).map(|(&c, d)| c as f64 * d.to_f64().powi(2)).collect()
+            };
+            let total: f64 = weights.iter().sum();
+            if total <= 0.0 {
+                break;
+            }
+
+            let mut x = Range::new(0.0, total).ind_sample(rng);
+            // Rounding may leave `x` past the last weight, so fall back
+            // on the last item that can be drawn.
+            let mut i = weights.iter().rposition(|&w| w > 0.0).expect("some weight is positive");
+            for (j, &w) in weights.iter().enumerate() {
+                if x < w {
+                    i = j;
+                    break;
+                }
+                x -= w;
+            }
+            centers.push(i);
+            nearest.add(items[i]);
+        }
+        centers
+    }
+
+    /// Choose up to `m` stored items by farthest-point sampling,
+    /// starting from the item at position `start`.
+    ///
+    /// Each further item is the one furthest from all items chosen so
+    /// far, which greedily spreads the sample out as evenly as
+    /// possible: the smallest distance between two chosen items is at
+    /// least half of the largest possible. The result lists the
+    /// positions of the chosen items in the vector the tree was built
+    /// from, in the order they were chosen, and ties are broken
+    /// arbitrarily.
+    ///
+    /// As with `kmeans_plus_plus`, adding an item only measures its
+    /// distance to the items it could bring closer, and the furthest
+    /// item is found by descending the tree. Fewer than `m` items are
+    /// returned if the tree holds fewer than `m` distinct items.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `start` is not the position of an item, unless `m` is
+    /// 0.
+    pub fn farthest_point_sample(&self, m: usize, start: usize) -> Vec<usize> {
+        if m == 0 {
+            return Vec::new();
+        }
+        assert!(start < self.len(), "the first sample must be an item of the tree");
+
+        let items = self.items_by_position();
+        let mut nearest = NearestCenters::new(self);
+        let mut sample = vec![start];
+        nearest.add(items[start]);
+        while sample.len() < m {
+            match nearest.furthest() {
+                Some(i) if nearest.distances[i] > F::zero() => {
+                    sample.push(i);
+                    nearest.add(items[i]);
+                },
+                _ => break
+            }
+        }
+        sample
+    }
+}
@@ -0,0 +1,212 @@
+//! Splitting a tree into several smaller ones, and querying them
+//! together.
+
+use std::collections::BinaryHeap;
+use std::mem;
+use distance::Distance;
+
+use super::{MetricItem, TaggedItem, VPTree, VPTreeBuilder};
+
+impl<F: Distance, T: MetricItem<F>> VPTree<F, T> {
+    /// Consume the tree, partitioning its items into `n` trees whose
+    /// sizes differ by at most one.
+    ///
+    /// The items are taken in the order of a depth-first walk of the
+    /// tree, so each shard mostly holds whole subtrees, and items that
+    /// are close together tend to end up in the same shard. Each shard
+    /// is built with the options of the original tree, and keeps its
+    /// items in their original relative order. If there are fewer
+    /// items than shards, the last shards are empty.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is 0, or if the metric returns a negative or NaN
+    /// distance.
+    pub fn split_into(mut self, n: usize) -> Vec<VPTree<F, T>> {
+        assert!(n > 0, "cannot split a tree into 0 shards");
+
+        let mut slots: Vec<Option<_>> = mem::take(&mut self.nodes).into_iter().map(Some).collect();
+        let mut walk: Vec<TaggedItem<F, T>> = Vec::with_capacity(self.len());
+        let mut stack = if slots.is_empty() { Vec::new() } else { vec![0] };
+        while let Some(id) = stack.pop() {
+            let node = slots[id as usize].take().expect("every node is reached once");
+            if let Some(ref c) = node.contents {
+                stack.extend(c.outer);
+                stack.push(c.inner);
+            }
+            walk.push(node.center);
+            walk.extend(node.bucket);
+        }
+
+        let (base, extra) = (walk.len() / n, walk.len() % n);
+        let mut rest = walk.into_iter();
+        (0..n).map(|i| {
+            let mut items: Vec<TaggedItem<F, T>> = rest.by_ref().take(base + (i < extra) as usize).collect();
+            items.sort_by_key(|ti| ti.index);
+            for (index, ti) in items.iter_mut().enumerate() {
+                ti.index = index;
+            }
+            match VPTreeBuilder::from_config(self.config).build_tagged(items, Vec::new()) {
+                Ok(tree) => tree,
+                Err(e) => panic!("failed to build VPTree: {}", e)
+            }
+        }).collect()
+    }
+}
+
+/// A set of independent trees queried as one.
+///
+/// Each shard is an ordinary `VPTree`, so the shards can be built on
+/// separate threads, and a large index can be divided between
+/// processes with `split_into` and recombined with `from_shards`.
+/// Queries search every shard and merge the results by distance.
+///
+/// ```rust
+/// use vptree::{MetricItem, ShardedVPTree};
+///
+/// struct Point(f32);
+///
+/// impl MetricItem<f32> for Point {
+///     fn distance(&self, q: &Self) -> f32 {
+///         (self.0 - q.0).abs()
+///     }
+/// }
+///
+/// let index = ShardedVPTree::new((0..1000).map(|x| Point(x as f32)).collect(), 4);
+/// assert_eq!(index.shards().len(), 4);
+/// assert_eq!(index.nearest_neighbor(&Point(640.2)).unwrap().0, 640.0);
+/// ```
+pub struct ShardedVPTree<F: Distance, T: MetricItem<F>> {
+    shards: Vec<VPTree<F, T>>,
+}
+
+impl<F: Distance, T: MetricItem<F>> ShardedVPTree<F, T> {
+    /// Divide `items` into `n` shards whose sizes differ by at most
+    /// one, building each with the default options on its own thread,
+    /// or one after another on `wasm32-unknown-unknown`, which has no
+    /// threads.
+    ///
+    /// The items are divided in the order they are given, so the
+    /// first shard holds the first items.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is 0, or under the same conditions as
+    /// `VPTree::new`.
+    pub fn new(mut items: Vec<T>, n: usize) -> Self where F: Send, T: Send {
+        assert!(n > 0, "cannot split items into 0 shards");
+
+        let (base, extra) = (items.len() / n, items.len() % n);
+        let mut parts: Vec<Vec<T>> = (0..n).rev().map(|i| {
+            let start = i * base + i.min(extra);
+            items.split_off(start)
+        }).collect();
+        parts.reverse();
+
+        ShardedVPTree { shards: build_shards(parts) }
+    }
+
+    /// Combine existing trees into a single index.
+    pub fn from_shards(shards: Vec<VPTree<F, T>>) -> Self {
+        ShardedVPTree { shards }
+    }
+
+    /// Return the shards of the index.
+    pub fn shards(&self) -> &[VPTree<F, T>] {
+        &self.shards
+    }
+
+    /// Consume the index, returning its shards.
+    pub fn into_shards(self) -> Vec<VPTree<F, T>> {
+        self.shards
+    }
+
+    /// Return the total number of items in every shard.
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|s| s.len()).sum()
+    }
+
+    /// Return true if no shard holds any items.
+    pub fn is_empty(&self) -> bool {
+        self.shards.iter().all(|s| s.is_empty())
+    }
+
+    /// Find the closest point in any shard to `query`.
+    ///
+    /// Returns `None` if every shard is empty.
+    pub fn nearest_neighbor(&self, query: &T) -> Option<&T> {
+        self.nearest_neighbors(query, 1, false).pop()
+    }
+
+    /// Find the `k` points closest to `query` over all of the shards.
+    ///
+    /// Otherwise behaves like `VPTree::nearest_neighbors`. The shards
+    /// share a single result heap, so the neighbors found in earlier
+    /// shards prune the search of later ones.
+    pub fn nearest_neighbors(&self, query: &T, k: usize, sorted: bool) -> Vec<&T> {
+        let mut heap = BinaryHeap::with_capacity(k);
+        if k > 0 {
+            for shard in &self.shards {
+                if let Some(root) = shard.root() {
+                    root.nearest_neighbors(&shard.nodes, query, k, &mut heap);
+                }
+            }
+        }
+
+        let v = if sorted {
+            heap.into_sorted_vec()
+        } else {
+            heap.into_vec()
+        };
+        v.into_iter().map(|x| &x.item.item).collect()
+    }
+
+    /// Return all elements of any shard within a given radius of the
+    /// target.
+    ///
+    /// If `sorted` is true, the elements are sorted by ascending
+    /// distance from the query point.
+    pub fn within_radius(&self, query: &T, radius: F, sorted: bool) -> Vec<&T> {
+        let mut elems = Vec::new();
+        for shard in &self.shards {
+            if let Some(root) = shard.root() {
+                root.within_radius(&shard.nodes, query, radius, &mut elems);
+            }
+        }
+
+        if sorted {
+            elems.sort();
+        }
+        elems.into_iter().map(|x| &x.item.item).collect()
+    }
+
+    /// Return the number of elements of all shards within a given
+    /// radius of the target.
+    pub fn count_within_radius(&self, query: &T, radius: F) -> usize {
+        self.shards.iter().map(|s| s.count_within_radius(query, radius)).sum()
+    }
+
+    /// Return true if some shard has an element within a given
+    /// radius of the target.
+    pub fn any_within_radius(&self, query: &T, radius: F) -> bool {
+        self.shards.iter().any(|s| s.any_within_radius(query, radius))
+    }
+}
+
+/// Build a tree from each part on its own thread.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+fn build_shards<F, T>(parts: Vec<Vec<T>>) -> Vec<VPTree<F, T>>
+    where F: Distance + Send, T: MetricItem<F> + Send {
+    use std::{panic, thread};
+
+    thread::scope(|s| {
+        let handles: Vec<_> = parts.into_iter().map(|part| s.spawn(move || VPTree::new(part))).collect();
+        handles.into_iter().map(|h| h.join().unwrap_or_else(|e| panic::resume_unwind(e))).collect()
+    })
+}
+
+/// Build a tree from each part in turn.
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+fn build_shards<F: Distance, T: MetricItem<F>>(parts: Vec<Vec<T>>) -> Vec<VPTree<F, T>> {
+    parts.into_iter().map(VPTree::new).collect()
+}
@@ -0,0 +1,273 @@
+//! Trees over items that expire.
+
+use std::collections::BinaryHeap;
+use std::time::{Duration, Instant};
+use distance::Distance;
+
+use super::{MetricItem, NearestHeap, VPTree, VPTreeBuilder};
+
+/// A vantage point tree whose items expire.
+///
+/// Each item is inserted with the time at which it expires, in
+/// whatever units the caller likes, and the window moves forward
+/// with `advance`. Items expire once the current time reaches their
+/// expiration time, after which queries ignore them. Expired items
+/// stay in the tree until enough of them have built up, and are then
+/// evicted all at once.
+///
+/// ```rust
+/// use vptree::{MetricItem, SlidingVPTree};
+///
+/// struct Point(f32);
+///
+/// impl MetricItem<f32> for Point {
+///     fn distance(&self, q: &Self) -> f32 {
+///         (self.0 - q.0).abs()
+///     }
+/// }
+///
+/// let mut window = SlidingVPTree::new(0u64);
+/// window.insert(Point(1.0), 10);
+/// window.insert(Point(2.0), 20);
+/// assert_eq!(window.nearest_neighbor(&Point(0.0)).unwrap().0, 1.0);
+///
+/// window.advance(15);
+/// assert_eq!(window.nearest_neighbor(&Point(0.0)).unwrap().0, 2.0);
+/// ```
+pub struct SlidingVPTree<F: Distance, T: MetricItem<F>, O: Ord + Copy = u64> {
+    tree: VPTree<F, T>,
+    /// The expiration time of each item, by index.
+    expires: Vec<O>,
+    now: O,
+    /// Number of expired items still in the tree.
+    expired: usize,
+}
+
+impl<F: Distance, T: MetricItem<F>, O: Ord + Copy> SlidingVPTree<F, T, O> {
+    /// Create an empty window starting at time `now`.
+    pub fn new(now: O) -> Self {
+        SlidingVPTree { tree: VPTree::default(), expires: Vec::new(), now, expired: 0 }
+    }
+
+    /// Create an empty window starting at time `now`, whose tree is
+    /// built and rebuilt with the options of `builder`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `builder` collapses duplicates, since each item keeps
+    /// its own expiration time.
+    pub fn with_builder(builder: &VPTreeBuilder<F, T>, now: O) -> Self {
+        assert!(!builder.config.collapse_duplicates, "windows cannot collapse duplicate items");
+        SlidingVPTree { tree: builder.build(Vec::new()), expires: Vec::new(), now, expired: 0 }
+    }
+
+    /// Return the current time.
+    pub fn now(&self) -> O {
+        self.now
+    }
+
+    /// Return the number of items that have not expired.
+    pub fn len(&self) -> usize {
+        self.tree.len() - self.expired
+    }
+
+    /// Return true if every item has expired.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Insert `item`, to expire at time `expires`.
+    pub fn insert(&mut self, item: T, expires: O) {
+        self.tree.extend(Some(item));
+        self.expires.push(expires);
+        if expires <= self.now {
+            self.expired += 1;
+        }
+    }
+
+    /// Move the window forward to time `now`, evicting the expired
+    /// items if they make up more than a quarter of the tree.
+    ///
+    /// Moving the window backwards has no effect.
+    pub fn advance(&mut self, now: O) {
+        if now <= self.now {
+            return;
+        }
+        self.now = now;
+        self.expired = self.expires.iter().filter(|&&e| e <= now).count();
+        if self.expired * 4 > self.tree.len() {
+            self.compact();
+        }
+    }
+
+    /// Evict every expired item from the tree now.
+    pub fn compact(&mut self) {
+        if self.expired == 0 {
+            return;
+        }
+        let (expires, now) = (&self.expires, self.now);
+        self.tree.retain_tagged(|ti| expires[ti.index] > now);
+        self.expires.retain(|&e| e > now);
+        self.expired = 0;
+    }
+
+    /// Find the unexpired item closest to `query`.
+    pub fn nearest_neighbor(&self, query: &T) -> Option<&T> {
+        self.nearest_neighbors(query, 1, false).pop()
+    }
+
+    /// Find the `k` unexpired items closest to `query`.
+    ///
+    /// Otherwise behaves like `VPTree::nearest_neighbors`.
+    pub fn nearest_neighbors(&self, query: &T, k: usize, sorted: bool) -> Vec<&T> {
+        self.nearest_neighbors_at(query, k, sorted, self.now)
+    }
+
+    /// Return all unexpired items within a given radius of the target.
+    ///
+    /// Otherwise behaves like `VPTree::within_radius`.
+    pub fn within_radius(&self, query: &T, radius: F, sorted: bool) -> Vec<&T> {
+        self.within_radius_at(query, radius, sorted, self.now)
+    }
+
+    /// Return the number of items unexpired at time `now`.
+    fn len_at(&self, now: O) -> usize {
+        self.expires.iter().filter(|&&e| e > now).count()
+    }
+
+    /// Find the `k` items unexpired at time `now` closest to `query`.
+    fn nearest_neighbors_at(&self, query: &T, k: usize, sorted: bool, now: O) -> Vec<&T> {
+        let mut heap: NearestHeap<F, T> = BinaryHeap::with_capacity(k);
+        if let (Some(root), true) = (self.tree.root(), k > 0) {
+            root.nearest_neighbors_filtered(&self.tree.nodes, query, k,
+                                            &|ti, _| self.expires[ti.index] > now, &mut heap);
+        }
+
+        let v = if sorted {
+            heap.into_sorted_vec()
+        } else {
+            heap.into_vec()
+        };
+        v.into_iter().map(|x| &x.item.item).collect()
+    }
+
+    /// Return all items unexpired at time `now` within a given radius
+    /// of the target.
+    fn within_radius_at(&self, query: &T, radius: F, sorted: bool, now: O) -> Vec<&T> {
+        let mut elems = Vec::new();
+        if let Some(root) = self.tree.root() {
+            root.within_radius(&self.tree.nodes, query, radius, &mut elems);
+        }
+        elems.retain(|x| self.expires[x.item.index] > now);
+
+        if sorted {
+            elems.sort();
+        }
+        elems.into_iter().map(|x| &x.item.item).collect()
+    }
+}
+
+/// When an item of a `TtlVPTree` expires.
+///
+/// Items that never expire sort after every instant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Expiry {
+    At(Instant),
+    Never,
+}
+
+/// A vantage point tree whose items may be given a time to live.
+///
+/// Queries skip the items whose time to live has passed, by the
+/// system clock, and `purge_expired` removes them from the tree.
+/// Items inserted without a time to live never expire.
+///
+/// `wasm32-unknown-unknown` has no system clock, so there, use a
+/// `SlidingVPTree` with timestamps from the host instead.
+///
+/// ```rust
+/// use std::time::Duration;
+/// use vptree::{MetricItem, TtlVPTree};
+///
+/// struct Point(f32);
+///
+/// impl MetricItem<f32> for Point {
+///     fn distance(&self, q: &Self) -> f32 {
+///         (self.0 - q.0).abs()
+///     }
+/// }
+///
+/// let mut cache = TtlVPTree::new();
+/// cache.insert(Point(1.0), Some(Duration::from_secs(0)));
+/// cache.insert(Point(2.0), None);
+/// assert_eq!(cache.nearest_neighbor(&Point(0.0)).unwrap().0, 2.0);
+/// ```
+pub struct TtlVPTree<F: Distance, T: MetricItem<F>> {
+    window: SlidingVPTree<F, T, Expiry>,
+}
+
+impl<F: Distance, T: MetricItem<F>> TtlVPTree<F, T> {
+    /// Create an empty tree.
+    pub fn new() -> Self {
+        TtlVPTree { window: SlidingVPTree::new(Expiry::At(Instant::now())) }
+    }
+
+    /// Create an empty tree, which is built and rebuilt with the
+    /// options of `builder`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `builder` collapses duplicates, as
+    /// `SlidingVPTree::with_builder` does.
+    pub fn with_builder(builder: &VPTreeBuilder<F, T>) -> Self {
+        TtlVPTree { window: SlidingVPTree::with_builder(builder, Expiry::At(Instant::now())) }
+    }
+
+    /// Return the number of items that have not expired.
+    pub fn len(&self) -> usize {
+        self.window.len_at(Expiry::At(Instant::now()))
+    }
+
+    /// Return true if every item has expired.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Insert `item`, to expire once `ttl` has passed, or never if
+    /// `ttl` is `None`.
+    pub fn insert(&mut self, item: T, ttl: Option<Duration>) {
+        let expires = ttl.map_or(Expiry::Never, |ttl| Expiry::At(Instant::now() + ttl));
+        self.window.insert(item, expires);
+    }
+
+    /// Remove every expired item from the tree.
+    pub fn purge_expired(&mut self) {
+        self.window.advance(Expiry::At(Instant::now()));
+        self.window.compact();
+    }
+
+    /// Find the unexpired item closest to `query`.
+    pub fn nearest_neighbor(&self, query: &T) -> Option<&T> {
+        self.nearest_neighbors(query, 1, false).pop()
+    }
+
+    /// Find the `k` unexpired items closest to `query`.
+    ///
+    /// Otherwise behaves like `VPTree::nearest_neighbors`.
+    pub fn nearest_neighbors(&self, query: &T, k: usize, sorted: bool) -> Vec<&T> {
+        self.window.nearest_neighbors_at(query, k, sorted, Expiry::At(Instant::now()))
+    }
+
+    /// Return all unexpired items within a given radius of the target.
+    ///
+    /// Otherwise behaves like `VPTree::within_radius`.
+    pub fn within_radius(&self, query: &T, radius: F, sorted: bool) -> Vec<&T> {
+        self.window.within_radius_at(query, radius, sorted, Expiry::At(Instant::now()))
+    }
+}
+
+impl<F: Distance, T: MetricItem<F>> Default for TtlVPTree<F, T> {
+    fn default() -> Self {
+        TtlVPTree::new()
+    }
+}
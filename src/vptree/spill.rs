@@ -0,0 +1,298 @@
+//! Approximate searches over a tree whose subtrees overlap.
+
+use std::collections::HashMap;
+
+use rand::Rng;
+use rand::distributions::{IndependentSample, Range};
+use distance::Distance;
+
+use error::BuildError;
+use super::{query_distance, valid_distance, MetricItem};
+use super::builder::{unseeded_rng, DEFAULT_LEAF_SIZE};
+
+/// A vantage point tree whose two sides of every split overlap, for
+/// fast approximate nearest neighbor and radius queries.
+///
+/// In high dimensions, the exact searches of a `VPTree` can rarely
+/// rule out a subtree, and end up visiting most of the tree. A spill
+/// tree instead follows a single path from the root to a leaf, on the
+/// side of each split the query falls on, and only looks at the items
+/// of that leaf and the vantage points on the way. Items near a split
+/// are the ones a query on the other side is likely to need, so every
+/// split copies them into both sides: the inner side holds the items
+/// nearest to the vantage point, up to a fraction `0.5 + overlap` of
+/// them, and the outer side as many of the furthest.
+///
+/// More overlap means better answers, and more memory. Each split
+/// stores `1 + 2 * overlap` times its node's items across its two
+/// sides, so over the `log n` levels of the tree, the number of stored
+/// positions grows roughly as `n^(1 + e)`: `e` is about 0.36 for an
+/// overlap of 0.1, and 0.94 for 0.2. To keep this in check, as in
+/// hybrid spill trees, neither side of a split may hold more than 70%
+/// of its node's items, which caps the overlap at 0.2, and trees that
+/// would store more than 64 positions per item are rejected. The
+/// items themselves are stored once, and leaves refer to them by
+/// position.
+///
+/// ```rust
+/// use vptree::{MetricItem, SpillTree};
+///
+/// struct Point(f32);
+///
+/// impl MetricItem<f32> for Point {
+///     fn distance(&self, q: &Self) -> f32 {
+///         (self.0 - q.0).abs()
+///     }
+/// }
+///
+/// let tree = SpillTree::new((0..1000).map(|x| Point(x as f32)).collect(), 0.1);
+/// assert_eq!(tree.nearest_neighbor(&Point(499.8)).unwrap().0, 500.0);
+/// assert!(tree.entry_count() > tree.len());
+/// ```
+pub struct SpillTree<F: Distance, T: MetricItem<F>> {
+    items: Vec<T>,
+    /// Every node, with the root first.
+    nodes: Vec<SpillNode<F>>,
+}
+
+/// The largest overlap, at which either side of a split holds 70% of
+/// its node's items.
+const MAX_OVERLAP: f64 = 0.2;
+
+/// The most positions a tree may store per item.
+const MAX_ENTRIES_PER_ITEM: usize = 64;
+
+enum SpillNode<F> {
+    Split {
+        /// Position of the vantage point.
+        vantage: u32,
+        /// Median distance from the vantage point. Queries no further
+        /// than this descend into the inner side.
+        mu: F,
+        inner: u32,
+        outer: u32,
+    },
+    /// Positions of the items of a leaf.
+    Leaf(Vec<u32>),
+}
+
+impl<F: Distance, T: MetricItem<F>> SpillTree<F, T> {
+    /// Construct a tree from `items`, with sides of each split
+    /// overlapping by `overlap`, the default leaf size of 16, and
+    /// vantage points drawn from the thread-local random number
+    /// generator.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `overlap` is out of range, or if the metric returns
+    /// a negative or NaN distance during construction.
+    pub fn new(items: Vec<T>, overlap: f64) -> Self {
+        match SpillTree::try_new_with_rng(items, overlap, DEFAULT_LEAF_SIZE, &mut unseeded_rng()) {
+            Ok(tree) => tree,
+            Err(e) => panic!("failed to build SpillTree: {}", e)
+        }
+    }
+
+    /// Construct a tree from `items`, with sides of each split
+    /// overlapping by `overlap`, splitting only subtrees of more than
+    /// `leaf_size` items, and drawing vantage points from `rng`.
+    ///
+    /// `overlap` is the fraction of a subtree's items, beyond half,
+    /// that each side of its split takes, and must be at least 0 and
+    /// at most 0.2. With no overlap, this is an ordinary vantage point
+    /// tree, searched without backtracking.
+    ///
+    /// Returns `BuildError::InvalidParameter` if `overlap` or
+    /// `leaf_size` is out of range, or if the tree would store more
+    /// than 64 positions per item, and `BuildError::InvalidDistance`
+    /// if the metric returns a negative or NaN distance. An empty set
+    /// of items is allowed.
+    pub fn try_new_with_rng<R: Rng>(items: Vec<T>, overlap: f64, leaf_size: usize, rng: &mut R)
+                                    -> Result<Self, BuildError> {
+        if !(0.0..=MAX_OVERLAP).contains(&overlap) {
+            return Err(BuildError::InvalidParameter("overlap must be at least 0 and at most 0.2"));
+        }
+        if leaf_size == 0 {
+            return Err(BuildError::InvalidParameter("leaf size must be positive"));
+        }
+        if items.len() > u32::MAX as usize {
+            return Err(BuildError::InvalidParameter("too many items for a single tree"));
+        }
+        // The shape of the tree depends only on the number of items, so
+        // its size is known before measuring any distances.
+        if planned_entries(items.len(), overlap, leaf_size, &mut HashMap::new()) > items.len().saturating_mul(MAX_ENTRIES_PER_ITEM) {
+            return Err(BuildError::InvalidParameter("overlap would store too many copies of the items"));
+        }
+
+        let mut nodes = Vec::new();
+        let mut work = if items.is_empty() { Vec::new() } else { vec![((0..items.len() as u32).collect::<Vec<_>>(), None)] };
+        while let Some((positions, parent)) = work.pop() {
+            let id = nodes.len() as u32;
+            if let Some((p, is_inner)) = parent {
+                if let SpillNode::Split { ref mut inner, ref mut outer, .. } = nodes[p as usize] {
+                    if is_inner {
+                        *inner = id;
+                    } else {
+                        *outer = id;
+                    }
+                }
+            }
+            if positions.len() <= leaf_size {
+                nodes.push(SpillNode::Leaf(positions));
+                continue;
+            }
+
+            let vantage = positions[Range::new(0, positions.len()).ind_sample(rng)];
+            let mut by_distance = Vec::with_capacity(positions.len() - 1);
+            for i in positions {
+                if i == vantage {
+                    continue;
+                }
+                let d = items[i as usize].distance(&items[vantage as usize]);
+                if !valid_distance(d) {
+                    return Err(BuildError::InvalidDistance);
+                }
+                by_distance.push((d, i));
+            }
+            by_distance.sort_by(|a, b| a.0.partial_cmp(&b.0).expect("distances were validated"));
+
+            let n = by_distance.len();
+            let (inner_len, outer_len) = side_lengths(n, overlap);
+            let mu = by_distance[(n - 1) / 2].0;
+            let outer = by_distance[n - outer_len..].iter().map(|&(_, i)| i).collect();
+            let inner = by_distance[..inner_len].iter().map(|&(_, i)| i).collect();
+            nodes.push(SpillNode::Split { vantage, mu, inner: 0, outer: 0 });
+            work.push((outer, Some((id, false))));
+            work.push((inner, Some((id, true))));
+        }
+        Ok(SpillTree { items, nodes })
+    }
+
+    /// Return the number of items in the tree.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Return true if the tree holds no items.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Return the number of positions stored across all nodes.
+    ///
+    /// This exceeds `len` by the copies of items spilled into both
+    /// sides of splits, and measures the memory the overlap costs.
+    pub fn entry_count(&self) -> usize {
+        self.nodes.iter().map(|node| match *node {
+            SpillNode::Leaf(ref positions) => positions.len(),
+            SpillNode::Split { .. } => 1,
+        }).sum()
+    }
+
+    /// Return the positions of the items a query looks at, and their
+    /// distances to `query`: the vantage points on the path down to a
+    /// leaf, and the items of the leaf.
+    fn candidates(&self, query: &T) -> Vec<(F, u32)> {
+        let mut candidates = Vec::new();
+        let mut id = 0;
+        while let Some(node) = self.nodes.get(id) {
+            match *node {
+                SpillNode::Split { vantage, mu, inner, outer } => {
+                    let d = query_distance(query, &self.items[vantage as usize]);
+                    candidates.push((d, vantage));
+                    id = if d <= mu { inner } else { outer } as usize;
+                },
+                SpillNode::Leaf(ref positions) => {
+                    candidates.extend(positions.iter().map(|&i| (query_distance(query, &self.items[i as usize]), i)));
+                    break;
+                },
+            }
+        }
+        candidates
+    }
+
+    /// Find an item close to `query`: the nearest of the items a
+    /// query looks at.
+    ///
+    /// Returns `None` if the tree is empty.
+    pub fn nearest_neighbor(&self, query: &T) -> Option<&T> {
+        self.nearest_neighbors(query, 1, false).pop()
+    }
+
+    /// Find up to `k` items close to `query`, by descending to a
+    /// single leaf.
+    ///
+    /// These are the `k` nearest of the vantage points on the way and
+    /// the items of the leaf, which are often, but not always, the `k`
+    /// nearest items in the tree. If `sorted` is true, the items are
+    /// sorted by ascending distance from `query`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the metric returns a negative or NaN distance to
+    /// `query`.
+    pub fn nearest_neighbors(&self, query: &T, k: usize, sorted: bool) -> Vec<&T> {
+        let mut candidates = self.candidates(query);
+        if candidates.len() > k {
+            if k == 0 {
+                return Vec::new();
+            }
+            candidates.select_nth_unstable_by(k - 1, |a, b| a.0.partial_cmp(&b.0).expect("distances were validated"));
+            candidates.truncate(k);
+        }
+        if sorted {
+            candidates.sort_by(|a, b| a.0.partial_cmp(&b.0).expect("distances were validated"));
+        }
+        candidates.into_iter().map(|(_, i)| &self.items[i as usize]).collect()
+    }
+
+    /// Find items within `radius` of `query`, by descending to a
+    /// single leaf.
+    ///
+    /// These are the vantage points on the way and the items of the
+    /// leaf within `radius`, which may miss items further from
+    /// `query` than the split it falls closest to. If `sorted` is
+    /// true, the items are sorted by ascending distance from `query`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the metric returns a negative or NaN distance to
+    /// `query`.
+    pub fn within_radius(&self, query: &T, radius: F, sorted: bool) -> Vec<&T> {
+        let mut candidates = self.candidates(query);
+        candidates.retain(|&(d, _)| d < radius);
+        if sorted {
+            candidates.sort_by(|a, b| a.0.partial_cmp(&b.0).expect("distances were validated"));
+        }
+        candidates.into_iter().map(|(_, i)| &self.items[i as usize]).collect()
+    }
+}
+
+/// Return how many of the `n` items below a split, not counting its
+/// vantage point, go to its inner and outer sides.
+///
+/// Both sides are smaller than their node, which also holds the
+/// vantage point, so the splits end. With no overlap, the sides split
+/// the items evenly.
+fn side_lengths(n: usize, overlap: f64) -> (usize, usize) {
+    let inner = ((n as f64 * (0.5 + overlap)).ceil() as usize).clamp(1, n);
+    let outer = ((n as f64 * (0.5 + overlap)).floor() as usize).min(n);
+    (inner, outer)
+}
+
+/// Return the number of positions a tree over `n` items stores,
+/// remembering the count for each subtree size in `memo`.
+fn planned_entries(n: usize, overlap: f64, leaf_size: usize, memo: &mut HashMap<usize, usize>) -> usize {
+    if n <= leaf_size {
+        return n;
+    }
+    if let Some(&entries) = memo.get(&n) {
+        return entries;
+    }
+    let (inner, outer) = side_lengths(n - 1, overlap);
+    let entries = planned_entries(inner, overlap, leaf_size, memo)
+        .saturating_add(planned_entries(outer, overlap, leaf_size, memo))
+        .saturating_add(1);
+    memo.insert(n, entries);
+    entries
+}
@@ -0,0 +1,79 @@
+//! Summary statistics describing the shape of a tree.
+
+use distance::Distance;
+
+use super::{MetricItem, VPNode, VPTree};
+
+/// Structural statistics for a `VPTree`, as returned by
+/// `VPTree::stats`.
+///
+/// Depths count levels from the root, which is at depth 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreeStats<F> {
+    /// Total number of nodes. With a leaf size of 1, this is also the
+    /// number of items.
+    pub node_count: usize,
+    /// Number of nodes without subtrees.
+    pub leaf_count: usize,
+    /// Fraction of all nodes that are leaves.
+    pub leaf_fraction: f64,
+    /// Depth of the deepest leaf.
+    pub max_depth: usize,
+    /// Mean depth over all leaves.
+    pub mean_leaf_depth: f64,
+    /// Ratio of `max_depth` to the depth of a perfectly balanced tree
+    /// with the same number of nodes. A value of 1 is ideal.
+    pub balance: f64,
+    /// Split distance of every non-leaf node, in ascending order.
+    pub mu_values: Vec<F>,
+}
+
+impl<F: Distance, T: MetricItem<F>> VPTree<F, T> {
+    /// Compute structural statistics for the tree.
+    ///
+    /// This walks the entire tree, and is intended for diagnosing
+    /// datasets that produce poorly-balanced trees.
+    pub fn stats(&self) -> TreeStats<F> {
+        let mut stats = TreeStats {
+            node_count: 0,
+            leaf_count: 0,
+            leaf_fraction: 0.0,
+            max_depth: 0,
+            mean_leaf_depth: 0.0,
+            balance: 0.0,
+            mu_values: Vec::new(),
+        };
+
+        if let Some(root) = self.root() {
+            let mut total_leaf_depth = 0;
+            collect(&self.nodes, root, 1, &mut stats, &mut total_leaf_depth);
+
+            let n = stats.node_count as f64;
+            stats.leaf_fraction = stats.leaf_count as f64 / n;
+            stats.mean_leaf_depth = total_leaf_depth as f64 / stats.leaf_count as f64;
+            stats.balance = stats.max_depth as f64 / (n + 1.0).log2().ceil();
+            stats.mu_values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        }
+        stats
+    }
+}
+
+fn collect<F: Distance, T: MetricItem<F>>(nodes: &[VPNode<F, T>], node: &VPNode<F, T>, depth: usize,
+                                       stats: &mut TreeStats<F>, total_leaf_depth: &mut usize) {
+    stats.node_count += 1;
+    stats.max_depth = stats.max_depth.max(depth);
+
+    match node.contents {
+        Some(ref c) => {
+            stats.mu_values.push(c.mu);
+            collect(nodes, c.inner_node(nodes), depth + 1, stats, total_leaf_depth);
+            if let Some(outer) = c.outer_node(nodes) {
+                collect(nodes, outer, depth + 1, stats, total_leaf_depth);
+            }
+        },
+        None => {
+            stats.leaf_count += 1;
+            *total_leaf_depth += depth;
+        }
+    }
+}
@@ -0,0 +1,200 @@
+//! Recording the steps of a search, to explain its results.
+
+use std::collections::BinaryHeap;
+use distance::Distance;
+
+use super::{MetricItem, TaggedItem, TreeView, VPNode, VPTree};
+
+/// A step of a search through a tree, as recorded in a `SearchTrace`.
+///
+/// Items and subtrees are skipped when a lower bound on their distance
+/// to the query, derived from distances already measured through the
+/// triangle inequality, shows they cannot be close enough: it is at
+/// least the `limit`, which is the distance of the current `k`th
+/// nearest item for nearest neighbor searches, and the radius for
+/// radius searches. If the metric breaks the triangle inequality, the
+/// bounds are wrong, and a trace shows where an expected item was
+/// wrongly skipped.
+#[derive(Debug)]
+pub enum TraceEvent<'a, F: Distance + 'a, T: MetricItem<F> + 'a> {
+    /// A node was visited, and the distance to its vantage point
+    /// measured.
+    Visit {
+        /// The vantage point of the node.
+        center: &'a T,
+        /// The distance from the query to the vantage point.
+        distance: F,
+    },
+    /// The distance to an item of a leaf bucket was measured.
+    Evaluate {
+        /// The item.
+        item: &'a T,
+        /// The distance from the query to the item.
+        distance: F,
+    },
+    /// An item of a leaf bucket was skipped without measuring its
+    /// distance.
+    Skip {
+        /// The item.
+        item: &'a T,
+        /// A lower bound on the distance from the query to the item.
+        bound: F,
+        /// The distance the item would have had to beat.
+        limit: F,
+    },
+    /// A subtree was skipped without visiting any of its nodes.
+    Prune {
+        /// The skipped subtree.
+        subtree: TreeView<'a, F, T>,
+        /// A lower bound on the distance from the query to any item of
+        /// the subtree.
+        bound: F,
+        /// The distance an item would have had to beat.
+        limit: F,
+    },
+}
+
+/// The steps of a search, in the order they were taken.
+///
+/// ```rust
+/// use vptree::{MetricItem, TraceEvent, VPTree};
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Point(f32);
+///
+/// impl MetricItem<f32> for Point {
+///     fn distance(&self, q: &Self) -> f32 {
+///         (self.0 - q.0).abs()
+///     }
+/// }
+///
+/// let tree = VPTree::new((0..1000).map(|x| Point(x as f32)).collect());
+/// let (found, trace) = tree.nearest_neighbors_traced(&Point(10.2), 3, true);
+/// assert_eq!(found, vec![&Point(10.0), &Point(11.0), &Point(9.0)]);
+/// assert!(trace.distance_evaluations() < 1000);
+///
+/// // Find out why 500 was not returned.
+/// let fate = trace.events.iter().find(|e| match **e {
+///     TraceEvent::Visit { center: p, .. } | TraceEvent::Evaluate { item: p, .. } => p.0 == 500.0,
+///     TraceEvent::Skip { item: p, .. } => p.0 == 500.0,
+///     TraceEvent::Prune { subtree, .. } => subtree.items().any(|p| p.0 == 500.0),
+/// });
+/// match *fate.unwrap() {
+///     TraceEvent::Prune { bound, limit, .. } | TraceEvent::Skip { bound, limit, .. } => assert!(bound >= limit),
+///     TraceEvent::Visit { distance, .. } | TraceEvent::Evaluate { distance, .. } => assert!(distance > 1.2),
+/// }
+/// ```
+#[derive(Debug)]
+pub struct SearchTrace<'a, F: Distance + 'a, T: MetricItem<F> + 'a> {
+    /// The steps of the search.
+    pub events: Vec<TraceEvent<'a, F, T>>,
+}
+
+impl<'a, F: Distance, T: MetricItem<F>> SearchTrace<'a, F, T> {
+    fn new() -> Self {
+        SearchTrace { events: Vec::new() }
+    }
+
+    /// Return the vantage points visited, in order.
+    pub fn visited(&self) -> impl Iterator<Item = &'a T> + '_ {
+        self.events.iter().filter_map(|e| match *e {
+            TraceEvent::Visit { center, .. } => Some(center),
+            _ => None,
+        })
+    }
+
+    /// Return the number of distances measured from the query.
+    pub fn distance_evaluations(&self) -> usize {
+        self.events.iter().filter(|e| matches!(e, TraceEvent::Visit { .. } | TraceEvent::Evaluate { .. })).count()
+    }
+}
+
+/// Receives the steps of a search as it runs.
+///
+/// Every method does nothing by default, and `()` ignores everything,
+/// so untraced searches pay nothing.
+pub(super) trait Tracer<'a, F: Distance, T: MetricItem<F>> {
+    /// The distance from the query to a node's vantage point was
+    /// measured.
+    fn visit(&mut self, _center: &'a TaggedItem<F, T>, _d: F) {}
+
+    /// The distance from the query to an item of a leaf bucket was
+    /// measured.
+    fn evaluate(&mut self, _ti: &'a TaggedItem<F, T>, _d: F) {}
+
+    /// An item of a leaf bucket was skipped.
+    fn skip(&mut self, _ti: &'a TaggedItem<F, T>, _bound: F, _limit: F) {}
+
+    /// The subtree rooted at `node` was skipped.
+    fn prune(&mut self, _nodes: &'a [VPNode<F, T>], _node: &'a VPNode<F, T>, _bound: F, _limit: F) {}
+}
+
+impl<'a, F: Distance, T: MetricItem<F>> Tracer<'a, F, T> for () {}
+
+impl<'a, F: Distance, T: MetricItem<F>> Tracer<'a, F, T> for SearchTrace<'a, F, T> {
+    fn visit(&mut self, center: &'a TaggedItem<F, T>, d: F) {
+        self.events.push(TraceEvent::Visit { center: &center.item, distance: d });
+    }
+
+    fn evaluate(&mut self, ti: &'a TaggedItem<F, T>, d: F) {
+        self.events.push(TraceEvent::Evaluate { item: &ti.item, distance: d });
+    }
+
+    fn skip(&mut self, ti: &'a TaggedItem<F, T>, bound: F, limit: F) {
+        self.events.push(TraceEvent::Skip { item: &ti.item, bound, limit });
+    }
+
+    fn prune(&mut self, nodes: &'a [VPNode<F, T>], node: &'a VPNode<F, T>, bound: F, limit: F) {
+        self.events.push(TraceEvent::Prune { subtree: TreeView { nodes, node }, bound, limit });
+    }
+}
+
+impl<F: Distance, T: MetricItem<F>> VPTree<F, T> {
+    /// Like `nearest_neighbors`, but also return the steps the search
+    /// took.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as `nearest_neighbors`.
+    pub fn nearest_neighbors_traced(&self, query: &T, k: usize, sorted: bool) -> (Vec<&T>, SearchTrace<'_, F, T>) {
+        let mut trace = SearchTrace::new();
+        let mut heap = BinaryHeap::with_capacity(k);
+        if let (Some(root), true) = (self.root(), k > 0) {
+            let searched = root.nearest_neighbors_traced(&self.nodes, query, k, &|_, _| true, &mut heap,
+                                                         &mut Vec::new(), &mut trace);
+            if let Err(e) = searched {
+                panic!("failed to query VPTree: {}", e);
+            }
+        }
+
+        let v = if sorted {
+            heap.into_sorted_vec()
+        } else {
+            heap.into_vec()
+        };
+        (v.into_iter().map(|x| &x.item.item).collect(), trace)
+    }
+
+    /// Like `within_radius`, but also return the steps the search
+    /// took.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as `within_radius`.
+    pub fn within_radius_traced(&self, query: &T, radius: F, sorted: bool) -> (Vec<&T>, SearchTrace<'_, F, T>) {
+        let mut trace = SearchTrace::new();
+        let mut elems = Vec::new();
+        if let Some(root) = self.root() {
+            let searched = root.within_radius_traced(&self.nodes, query, radius, &mut elems, &mut Vec::new(),
+                                                     &mut trace);
+            if let Err(e) = searched {
+                panic!("failed to query VPTree: {}", e);
+            }
+        }
+
+        if sorted {
+            elems.sort();
+        }
+        (elems.into_iter().map(|x| &x.item.item).collect(), trace)
+    }
+}
@@ -0,0 +1,112 @@
+//! Strategies for choosing the vantage point of each node.
+
+use rand::Rng;
+use rand::distributions::{Range, IndependentSample};
+use std::cmp::Ordering;
+use distance::Distance;
+
+use super::{MetricItem, TaggedItem};
+
+/// How a vantage point is chosen from the items of each subtree
+/// during construction.
+///
+/// Good vantage points spread the remaining items over a wide range
+/// of distances, which makes the split distance a more effective
+/// filter at query time. The strategies trade construction time for
+/// the quality of the chosen points.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VantageStrategy {
+    /// Pick an item uniformly at random.
+    Random,
+    /// Pick the item furthest from a randomly-selected item. This is
+    /// the default, and costs one distance evaluation per item.
+    #[default]
+    FurthestFromRandom,
+    /// Consider every item, picking the one whose distances to all of
+    /// the others have the largest variance. This costs a number of
+    /// distance evaluations quadratic in the number of items, and is
+    /// only practical for small inputs.
+    MaxSpread,
+    /// Like `MaxSpread`, but only consider the given number of
+    /// randomly-selected candidates.
+    Sampled(usize),
+    /// The heuristic from Yianilos' original VP-tree paper. Each of
+    /// `candidates` randomly-selected items is compared against the
+    /// same random sample of `sample_size` items, and the candidate
+    /// whose distances have the largest second moment about their
+    /// median is picked. The cost is independent of the number of
+    /// items.
+    Yianilos { candidates: usize, sample_size: usize },
+}
+
+impl VantageStrategy {
+    /// Return the index of the selected vantage point in `items`.
+    pub(super) fn select<F: Distance, T: MetricItem<F>, R: Rng>(self, items: &[TaggedItem<F, T>], rng: &mut R) -> usize {
+        let range = Range::new(0, items.len());
+
+        match self {
+            VantageStrategy::Random => range.ind_sample(rng),
+            VantageStrategy::FurthestFromRandom => {
+                let i = range.ind_sample(rng);
+                let random_item = &items[i];
+
+                let min_d = (F::zero(), i);
+
+                // The vantage point will be the point furthest from the
+                // selected one.
+                items.iter().enumerate().fold(min_d, |acc, (i, y)| {
+                    let d = T::distance(&random_item.item, &y.item);
+                    if d > acc.0 { (d, i) } else { acc }
+                }).1
+            },
+            VantageStrategy::MaxSpread => max_spread(items, 0..items.len()),
+            VantageStrategy::Sampled(n) => {
+                let candidates: Vec<usize> = (0..n.max(1)).map(|_| range.ind_sample(rng)).collect();
+                max_spread(items, candidates.into_iter())
+            },
+            VantageStrategy::Yianilos { candidates, sample_size } => {
+                let sample: Vec<usize> = (0..sample_size.max(1)).map(|_| range.ind_sample(rng)).collect();
+
+                let mut best = (f64::NEG_INFINITY, 0);
+                for _ in 0..candidates.max(1) {
+                    let c = range.ind_sample(rng);
+                    let ds: Vec<f64> = sample.iter().map(|&j| T::distance(&items[c].item, &items[j].item).to_f64()).collect();
+                    let spread = median_spread(ds);
+                    if spread > best.0 {
+                        best = (spread, c);
+                    }
+                }
+                best.1
+            }
+        }
+    }
+}
+
+/// Return the candidate whose distances to all of `items` have the
+/// largest variance.
+fn max_spread<F: Distance, T: MetricItem<F>, I: Iterator<Item = usize>>(items: &[TaggedItem<F, T>], candidates: I) -> usize {
+    let mut best = (f64::NEG_INFINITY, 0);
+    for c in candidates {
+        let spread = variance(items.iter().map(|y| T::distance(&items[c].item, &y.item).to_f64()));
+        if spread > best.0 {
+            best = (spread, c);
+        }
+    }
+    best.1
+}
+
+/// Second moment of the distances about their median.
+fn median_spread(mut ds: Vec<f64>) -> f64 {
+    ds.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+    let median = ds[ds.len() / 2];
+    let n = ds.len() as f64;
+    ds.into_iter().fold(0.0, |acc, d| acc + (d - median) * (d - median)) / n
+}
+
+fn variance<I: Iterator<Item = f64>>(ds: I) -> f64 {
+    let (n, sum, sum_sq) = ds.fold((0.0, 0.0, 0.0), |(n, s, sq), d| {
+        (n + 1.0, s + d, sq + d * d)
+    });
+    let mean = sum / n;
+    sum_sq / n - mean * mean
+}
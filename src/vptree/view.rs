@@ -0,0 +1,247 @@
+//! Read-only navigation of the nodes of a tree.
+
+use std::fmt::{self, Debug, Display, Formatter};
+use std::ops::ControlFlow;
+use distance::Distance;
+
+use super::{MetricItem, VPNode, VPTree};
+use super::debug::NodeDebug;
+
+/// A node of a `VPTree`, with access to its subtrees.
+///
+/// Each node has a vantage point, its center. A non-leaf node splits
+/// the other items of its subtree by their distance to the center: an
+/// inner subtree holds the items up to `mu` away, and an optional
+/// outer subtree the items further than that. A leaf node instead
+/// keeps the other items in a flat bucket.
+///
+/// ```rust
+/// use vptree::{MetricItem, VPTree};
+///
+/// struct Point(f32);
+///
+/// impl MetricItem<f32> for Point {
+///     fn distance(&self, q: &Self) -> f32 {
+///         (self.0 - q.0).abs()
+///     }
+/// }
+///
+/// let tree = VPTree::builder().leaf_size(1).build((0..7).map(|x| Point(x as f32)).collect());
+/// let root = tree.view().unwrap();
+/// assert_eq!(root.size(), 7);
+///
+/// let inner = root.inner().unwrap();
+/// assert!(inner.items().all(|p| (p.0 - root.center().0).abs() <= root.mu().unwrap()));
+/// assert_eq!(inner.size() + root.outer().unwrap().size(), 6);
+/// ```
+pub struct TreeView<'a, F: Distance + 'a, T: MetricItem<F> + 'a> {
+    pub(super) nodes: &'a [VPNode<F, T>],
+    pub(super) node: &'a VPNode<F, T>,
+}
+
+/// A node reached by `VPTree::visit`, with its position in the tree.
+#[derive(Debug)]
+pub struct NodeInfo<'a, F: Distance + 'a, T: MetricItem<F> + 'a> {
+    /// Level of the node, counting the root as level 1.
+    pub depth: usize,
+    /// Number of items in the subtree rooted at the node.
+    pub size: usize,
+    /// Split distance of the node, or `None` for leaves.
+    pub mu: Option<F>,
+    /// The node itself.
+    pub node: TreeView<'a, F, T>,
+}
+
+impl<'a, F: Distance, T: MetricItem<F>> Clone for NodeInfo<'a, F, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, F: Distance, T: MetricItem<F>> Copy for NodeInfo<'a, F, T> {}
+
+impl<'a, F: Distance, T: MetricItem<F>> Clone for TreeView<'a, F, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, F: Distance, T: MetricItem<F>> Copy for TreeView<'a, F, T> {}
+
+impl<'a, F: Distance, T: MetricItem<F>> TreeView<'a, F, T> {
+    /// Return the vantage point of the node.
+    pub fn center(&self) -> &'a T {
+        &self.node.center.item
+    }
+
+    /// Return the items in the leaf bucket of the node, other than
+    /// the center. Non-leaf nodes have none.
+    pub fn bucket(&self) -> impl Iterator<Item = &'a T> + 'a {
+        self.node.bucket.iter().map(|ti| &ti.item)
+    }
+
+    /// Return every item of the subtree rooted at the node, in
+    /// pre-order: each node's center and bucket, then its inner
+    /// subtree, then its outer subtree. This matches the storage order
+    /// only under `NodeLayout::PreOrder`.
+    pub fn items(&self) -> impl Iterator<Item = &'a T> + 'a {
+        let nodes = self.nodes;
+        let mut stack = vec![self.node];
+        let mut pending: Vec<&'a T> = Vec::new();
+        ::std::iter::from_fn(move || {
+            loop {
+                if let Some(item) = pending.pop() {
+                    return Some(item);
+                }
+                let node = stack.pop()?;
+                if let Some(ref c) = node.contents {
+                    stack.extend(c.outer_node(nodes));
+                    stack.push(c.inner_node(nodes));
+                }
+                pending.extend(node.bucket.iter().rev().map(|ti| &ti.item));
+                pending.push(&node.center.item);
+            }
+        })
+    }
+
+    /// Return the number of items in the subtree rooted at the node,
+    /// including the center.
+    pub fn size(&self) -> usize {
+        self.node.size
+    }
+
+    /// Return the distance from the center to the furthest item in
+    /// the subtree.
+    pub fn radius(&self) -> F {
+        self.node.radius
+    }
+
+    /// Return true if the node has no subtrees.
+    pub fn is_leaf(&self) -> bool {
+        self.node.contents.is_none()
+    }
+
+    /// Return the split distance of a non-leaf node: the largest
+    /// distance from the center to any item of the inner subtree.
+    pub fn mu(&self) -> Option<F> {
+        self.node.contents.as_ref().map(|c| c.mu)
+    }
+
+    /// Return the smallest and largest distances from the center to
+    /// the items of the inner subtree.
+    pub fn inner_range(&self) -> Option<(F, F)> {
+        self.node.contents.as_ref().map(|c| (c.inner_min, c.mu))
+    }
+
+    /// Return the smallest and largest distances from the center to
+    /// the items of the outer subtree, if there is one.
+    pub fn outer_range(&self) -> Option<(F, F)> {
+        self.node.contents.as_ref().and_then(|c| c.outer.map(|_| (c.outer_min, c.outer_max)))
+    }
+
+    /// Return the inner subtree of a non-leaf node.
+    pub fn inner(&self) -> Option<TreeView<'a, F, T>> {
+        self.node.contents.as_ref().map(|c| TreeView { nodes: self.nodes, node: c.inner_node(self.nodes) })
+    }
+
+    /// Return the outer subtree of a non-leaf node, if any items lie
+    /// further than `mu` from the center.
+    pub fn outer(&self) -> Option<TreeView<'a, F, T>> {
+        self.node.contents.as_ref()
+            .and_then(|c| c.outer_node(self.nodes))
+            .map(|node| TreeView { nodes: self.nodes, node })
+    }
+}
+
+impl<'a, F: Distance + Display, T: MetricItem<F> + Debug> TreeView<'a, F, T> {
+    /// Return a pretty-printed recursive description of the subtree,
+    /// as `VPTree::dump` does for the whole tree.
+    pub fn dump(&self, prefix: &str) -> String {
+        let mut s: String = format!("{}elem: {:?}", prefix, self.center());
+        if !self.node.bucket.is_empty() {
+            let bucket: Vec<&T> = self.bucket().collect();
+            s += &format!(", bucket: {:?}", bucket);
+        }
+        if let (Some(mu), Some(inner)) = (self.mu(), self.inner()) {
+            s += &format!(", mu: {}\n", mu);
+            let new_prefix = format!("{}  ", prefix);
+
+            s += &format!("{}{}", prefix, inner.dump(&new_prefix));
+            if let Some(outer) = self.outer() {
+                s += &format!("{}{}", prefix, outer.dump(&new_prefix));
+            }
+        }
+        s
+    }
+}
+
+impl<'a, F: Distance + Debug, T: MetricItem<F> + Debug> Debug for TreeView<'a, F, T> {
+    /// Show the node and the top levels of its subtrees, as the
+    /// `Debug` format of `VPTree` does.
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        NodeDebug { nodes: self.nodes, node: self.node, depth: 1 }.fmt(f)
+    }
+}
+
+impl<F: Distance, T: MetricItem<F>> VPTree<F, T> {
+    /// Return a view of the root node, for navigating the structure
+    /// of the tree.
+    ///
+    /// Returns `None` if the tree is empty.
+    pub fn view(&self) -> Option<TreeView<'_, F, T>> {
+        self.root().map(|node| TreeView { nodes: &self.nodes, node })
+    }
+
+    /// Call `visitor` on every node of the tree in pre-order, each
+    /// node ahead of its inner subtree, and the inner subtree ahead of
+    /// the outer one.
+    ///
+    /// The traversal stops as soon as `visitor` returns
+    /// `ControlFlow::Break`, and returns the break value.
+    ///
+    /// ```rust
+    /// use std::ops::ControlFlow;
+    /// use vptree::{MetricItem, VPTree};
+    ///
+    /// struct Point(f32);
+    ///
+    /// impl MetricItem<f32> for Point {
+    ///     fn distance(&self, q: &Self) -> f32 {
+    ///         (self.0 - q.0).abs()
+    ///     }
+    /// }
+    ///
+    /// let tree = VPTree::builder().leaf_size(1).build((0..15).map(|x| Point(x as f32)).collect());
+    ///
+    /// let mut splits = Vec::new();
+    /// let _ = tree.visit(|info| -> ControlFlow<()> {
+    ///     splits.extend(info.mu.map(|mu| (info.depth, mu)));
+    ///     ControlFlow::Continue(())
+    /// });
+    /// assert_eq!(splits.len(), 7);
+    ///
+    /// let deep = tree.visit(|info| if info.depth == 3 { ControlFlow::Break(info.size) } else { ControlFlow::Continue(()) });
+    /// assert_eq!(deep, ControlFlow::Break(3));
+    /// ```
+    pub fn visit<B, V>(&self, mut visitor: V) -> ControlFlow<B>
+        where V: FnMut(NodeInfo<'_, F, T>) -> ControlFlow<B> {
+        let mut stack: Vec<(TreeView<F, T>, usize)> = self.view().map(|v| (v, 1)).into_iter().collect();
+        while let Some((node, depth)) = stack.pop() {
+            visitor(NodeInfo { depth, size: node.size(), mu: node.mu(), node })?;
+            stack.extend(node.outer().map(|v| (v, depth + 1)));
+            stack.extend(node.inner().map(|v| (v, depth + 1)));
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+impl<F: Distance + Display, T: MetricItem<F> + Debug> VPTree<F, T> {
+    /// Return a pretty-printed recursive description of the entire tree.
+    ///
+    /// This function is mainly intended for debugging. Use `view` to
+    /// inspect the tree programmatically.
+    #[inline]
+    pub fn dump(&self) -> String {
+        self.view().map_or(String::new(), |root| root.dump(""))
+    }
+}
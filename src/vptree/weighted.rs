@@ -0,0 +1,215 @@
+//! Trees whose items carry weights.
+
+use std::collections::BinaryHeap;
+use std::ops::Div;
+use distance::Distance;
+
+use error::BuildError;
+use super::{push_nearest, query_distance, MetricItem, NearestHeap, VPTree, VPTreeBuilder};
+
+/// A vantage point tree with a positive weight attached to each item.
+///
+/// Besides the usual queries, available through `tree`, a weighted
+/// tree can rank items by their distance divided by their weight, so
+/// that heavier items reach further, and can total the weight within
+/// a radius. Each node keeps the largest and total weight of its
+/// subtree, so both queries prune subtrees as the unweighted ones do.
+///
+/// ```rust
+/// use vptree::{MetricItem, WeightedVPTree};
+///
+/// struct Point(f32);
+///
+/// impl MetricItem<f32> for Point {
+///     fn distance(&self, q: &Self) -> f32 {
+///         (self.0 - q.0).abs()
+///     }
+/// }
+///
+/// let tree = WeightedVPTree::new(vec![(Point(1.0), 1.0), (Point(4.0), 10.0)]);
+/// // 3.0 / 10.0 beats 1.0 / 1.0.
+/// assert_eq!(tree.weighted_nearest_neighbor(&Point(0.0)).unwrap().0 .0, 4.0);
+/// assert_eq!(tree.weight_within_radius(&Point(0.0), 5.0), 11.0);
+/// ```
+pub struct WeightedVPTree<F: Distance, T: MetricItem<F>> {
+    tree: VPTree<F, T>,
+    /// The weight of each item, by index.
+    weights: Vec<F>,
+    /// The largest weight in each subtree, by node position.
+    max_weight: Vec<F>,
+    /// The total weight of each subtree, by node position.
+    total_weight: Vec<F>,
+}
+
+impl<F: Distance + Div<Output = F>, T: MetricItem<F>> WeightedVPTree<F, T> {
+    /// Construct a tree from items paired with their weights, using
+    /// the default options.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any weight is not positive and finite, or under the
+    /// same conditions as `VPTree::new`.
+    pub fn new(items: Vec<(T, F)>) -> Self {
+        match WeightedVPTree::try_with_builder(&VPTreeBuilder::new(), items) {
+            Ok(tree) => tree,
+            Err(e) => panic!("failed to build WeightedVPTree: {}", e)
+        }
+    }
+
+    /// Construct a tree from items paired with their weights, using
+    /// the options of `builder`.
+    ///
+    /// Returns `BuildError::InvalidParameter` if any weight is not
+    /// positive and finite, or if `builder` collapses duplicates,
+    /// which would merge items of different weights. Otherwise fails
+    /// as `try_build` does, except that an empty set of items is
+    /// allowed.
+    pub fn try_with_builder(builder: &VPTreeBuilder<F, T>, items: Vec<(T, F)>) -> Result<Self, BuildError> {
+        if builder.config.collapse_duplicates {
+            return Err(BuildError::InvalidParameter("weighted trees cannot collapse duplicate items"));
+        }
+        if items.iter().any(|&(_, w)| !(w > F::zero() && w < F::infinity())) {
+            return Err(BuildError::InvalidParameter("weights must be positive and finite"));
+        }
+        let (items, weights): (Vec<T>, Vec<F>) = items.into_iter().unzip();
+        let tree = builder.try_build_allowing_empty(items)?;
+
+        // Every node is stored ahead of its subtrees, so walking
+        // backwards finds each subtree's weights before its parent's.
+        let n = tree.nodes.len();
+        let (mut max_weight, mut total_weight) = (vec![F::zero(); n], vec![F::zero(); n]);
+        for (id, node) in tree.nodes.iter().enumerate().rev() {
+            let (mut max, mut total) = (F::zero(), F::zero());
+            for ti in Some(&node.center).into_iter().chain(node.bucket.iter()) {
+                max = max.larger(weights[ti.index]);
+                total = total.saturating_add(weights[ti.index]);
+            }
+            if let Some(ref c) = node.contents {
+                for child in Some(c.inner).into_iter().chain(c.outer) {
+                    max = max.larger(max_weight[child as usize]);
+                    total = total.saturating_add(total_weight[child as usize]);
+                }
+            }
+            max_weight[id] = max;
+            total_weight[id] = total;
+        }
+
+        Ok(WeightedVPTree { tree, weights, max_weight, total_weight })
+    }
+
+    /// Return the underlying tree, for unweighted queries.
+    pub fn tree(&self) -> &VPTree<F, T> {
+        &self.tree
+    }
+
+    /// Return the number of items in the tree.
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Return true if the tree holds no items.
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// Return the total weight of every item in the tree.
+    pub fn total_weight(&self) -> F {
+        self.total_weight.first().cloned().unwrap_or_else(F::zero)
+    }
+
+    /// Find the item with the smallest distance to `query` divided by
+    /// its weight, along with its weight.
+    ///
+    /// Returns `None` if the tree is empty.
+    pub fn weighted_nearest_neighbor(&self, query: &T) -> Option<(&T, F)> {
+        self.weighted_nearest_neighbors(query, 1, false).pop()
+    }
+
+    /// Find the `k` items with the smallest distance to `query`
+    /// divided by their weight, each along with its weight.
+    ///
+    /// If `sorted` is true, the items are sorted by ascending score.
+    pub fn weighted_nearest_neighbors(&self, query: &T, k: usize, sorted: bool) -> Vec<(&T, F)> {
+        let nodes = &self.tree.nodes;
+        let mut heap: NearestHeap<F, T> = BinaryHeap::with_capacity(k);
+        let mut stack = if nodes.is_empty() || k == 0 { Vec::new() } else { vec![(0, F::zero())] };
+
+        while let Some((id, bound)) = stack.pop() {
+            // Scores are bounded below by distances divided by the
+            // heaviest weight in the subtree.
+            if heap.len() >= k && heap.peek().unwrap().dist <= bound / self.max_weight[id] {
+                continue;
+            }
+
+            let node = &nodes[id];
+            let d_center = query_distance(query, &node.center.item);
+            push_nearest(&mut heap, k, &node.center, d_center / self.weights[node.center.index]);
+            for ti in &node.bucket {
+                if heap.len() >= k && ti.center_bound(d_center) / self.max_weight[id] >= heap.peek().unwrap().dist {
+                    continue;
+                }
+                let d = query_distance(query, &ti.item);
+                push_nearest(&mut heap, k, ti, d / self.weights[ti.index]);
+            }
+
+            if let Some(ref c) = node.contents {
+                let (inner_bound, outer_bound) = c.bounds(d_center);
+                let mut children = [(Some(c.inner), inner_bound), (c.outer, outer_bound)];
+                if d_center > c.mu {
+                    children.swap(0, 1);
+                }
+                for &(child, bound) in children.iter().rev() {
+                    if let Some(child) = child {
+                        let bound = bound.larger(nodes[child as usize].parent_bound(d_center)).larger(F::zero());
+                        stack.push((child as usize, bound));
+                    }
+                }
+            }
+        }
+
+        let v = if sorted {
+            heap.into_sorted_vec()
+        } else {
+            heap.into_vec()
+        };
+        v.into_iter().map(|x| (&x.item.item, self.weights[x.item.index])).collect()
+    }
+
+    /// Return the total weight of the items within a given radius of
+    /// the target.
+    ///
+    /// Subtrees lying entirely within the radius are counted without
+    /// visiting their items.
+    pub fn weight_within_radius(&self, query: &T, radius: F) -> F {
+        let nodes = &self.tree.nodes;
+        let mut total = F::zero();
+        let mut stack = if nodes.is_empty() { Vec::new() } else { vec![0] };
+        while let Some(id) = stack.pop() {
+            let node = &nodes[id];
+            let d_center = query_distance(query, &node.center.item);
+            if d_center.saturating_add(node.radius) < radius {
+                total = total.saturating_add(self.total_weight[id]);
+                continue;
+            }
+
+            if d_center < radius {
+                total = total.saturating_add(self.weights[node.center.index]);
+            }
+            for ti in &node.bucket {
+                if ti.center_bound(d_center) < radius && query_distance(query, &ti.item) < radius {
+                    total = total.saturating_add(self.weights[ti.index]);
+                }
+            }
+
+            if let Some(ref c) = node.contents {
+                let (inner_bound, outer_bound) = c.bounds(d_center);
+                for (child, bound) in Some((c.inner, inner_bound)).into_iter().chain(c.outer.map(|o| (o, outer_bound))) {
+                    if radius > bound && radius > nodes[child as usize].parent_bound(d_center) {
+                        stack.push(child as usize);
+                    }
+                }
+            }
+        }
+        total
+    }
+}
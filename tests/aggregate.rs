@@ -0,0 +1,49 @@
+extern crate vptree;
+
+use vptree::{Aggregate, MetricItem, VPTree};
+
+#[derive(Debug, PartialEq, Clone)]
+struct Point {
+    x: f32,
+    y: f32
+}
+
+impl MetricItem<f32> for Point {
+    fn distance(&self, q: &Self) -> f32 {
+        let dx = self.x - q.x;
+        let dy = self.y - q.y;
+        (dx*dx + dy*dy).sqrt()
+    }
+}
+
+fn lattice_points(n: usize) -> Vec<Point> {
+    (0..n).flat_map(|i| {
+        (0..n).map(move |j| Point { x: i as f32, y: j as f32 })
+    }).collect()
+}
+
+fn brute_force(points: &[Point], queries: &[Point], agg: Aggregate) -> f32 {
+    points.iter().map(|p| {
+        let ds = queries.iter().map(|q| p.distance(q));
+        match agg {
+            Aggregate::Sum => ds.sum(),
+            Aggregate::Max => ds.fold(0.0, f32::max),
+        }
+    }).fold(f32::INFINITY, f32::min)
+}
+
+#[test]
+fn aggregate_matches_brute_force() {
+    let points = lattice_points(15);
+    let tree = VPTree::new(points.clone());
+
+    let queries = vec![Point { x: 1.2, y: 3.7 }, Point { x: 9.9, y: 2.1 }, Point { x: 4.4, y: 12.5 }];
+    for &agg in &[Aggregate::Sum, Aggregate::Max] {
+        let p = tree.aggregate_nearest_neighbor(&queries, agg).unwrap();
+        let score = brute_force(std::slice::from_ref(p), &queries, agg);
+        assert_eq!(score, brute_force(&points, &queries, agg));
+    }
+
+    let single = [Point { x: 3.1, y: 7.8 }];
+    assert_eq!(tree.aggregate_nearest_neighbor(&single, Aggregate::Max), Some(&Point { x: 3.0, y: 8.0 }));
+}
@@ -0,0 +1,295 @@
+extern crate rand;
+extern crate vptree;
+
+use rand::{Rng, SeedableRng, XorShiftRng};
+use vptree::{MetricItem, VPTree};
+use vptree::metrics::Euclidean;
+
+#[derive(Debug, PartialEq, Clone)]
+struct Point(f64);
+
+impl MetricItem<f64> for Point {
+    fn distance(&self, a: &Self) -> f64 {
+        (self.0 - a.0).abs()
+    }
+}
+
+#[test]
+fn distance_histogram() {
+    let tree = VPTree::new((0..100).map(|x| Point(x as f64)).collect());
+    let h = tree.distance_histogram(&Point(0.0), 10);
+    assert_eq!((h.min, h.max), (0.0, 99.0));
+    assert_eq!(h.total(), 100);
+    assert_eq!(h.counts, vec![10; 10]);
+    assert_eq!(h.bin_edges(1), (9.9, 19.8));
+    assert!((h.quantile(0.5).unwrap() - 49.5).abs() < 1.0);
+    assert_eq!(h.quantile(0.0), Some(0.0));
+    assert_eq!(h.quantile(1.0), Some(99.0));
+
+    // Identical distances all fall in one bin.
+    let same = VPTree::new(vec![Point(1.0); 5]);
+    assert_eq!(same.distance_histogram(&Point(0.0), 3).counts, vec![5, 0, 0]);
+
+    let empty: VPTree<f64, Point> = VPTree::new(Vec::new());
+    let h = empty.distance_histogram(&Point(0.0), 4);
+    assert_eq!(h.counts, vec![0; 4]);
+    assert_eq!(h.quantile(0.5), None);
+}
+
+#[test]
+fn sampled_distance_histogram() {
+    let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+    let tree = VPTree::new((0..1000).map(|x| Point(x as f64 / 1000.0)).collect());
+    let h = tree.sample_distance_histogram(20000, 10, &mut rng);
+    assert_eq!(h.total(), 20000);
+    assert!(h.min > 0.0 && h.max < 1.0);
+
+    // Distances between uniform points on a line have a triangular
+    // density, 2 * (1 - d).
+    for (i, &c) in h.counts.iter().enumerate() {
+        let (lo, hi) = h.bin_edges(i);
+        let expected = 20000.0 * ((1.0 - lo).powi(2) - (1.0 - hi).powi(2));
+        assert!((c as f64 - expected).abs() < 0.1 * expected + 50.0, "bin {}: {} vs {}", i, c, expected);
+    }
+
+    let single = VPTree::new(vec![Point(0.0)]);
+    assert_eq!(single.sample_distance_histogram(10, 2, &mut rng).total(), 0);
+}
+
+#[test]
+fn intrinsic_dim() {
+    let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+
+    let line = VPTree::new((0..2000).map(|_| Point(rng.gen())).collect());
+    let d = line.estimate_intrinsic_dim(1000, &mut rng).unwrap();
+    assert!((d - 1.0).abs() < 0.15, "{}", d);
+
+    // Points on a plane in 3 dimensions.
+    let plane: Vec<_> = (0..2000).map(|_| {
+        let (x, y): (f64, f64) = (rng.gen(), rng.gen());
+        Euclidean(vec![x, y, x - y])
+    }).collect();
+    let d = VPTree::new(plane).estimate_intrinsic_dim(1000, &mut rng).unwrap();
+    assert!((d - 2.0).abs() < 0.3, "{}", d);
+
+    // Duplicates are skipped, and a tree with fewer than three items
+    // gives no estimate.
+    assert_eq!(VPTree::new(vec![Point(0.0); 10]).estimate_intrinsic_dim(10, &mut rng), None);
+    assert_eq!(VPTree::new(vec![Point(0.0), Point(1.0)]).estimate_intrinsic_dim(10, &mut rng), None);
+}
+
+#[test]
+fn suggested_radius() {
+    let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+    let tree = VPTree::new((0..1000).map(|x| Point(x as f64)).collect());
+    // Away from the ends, the 4th nearest neighbor is 2 away.
+    assert_eq!(tree.suggest_radius(4, 1000, &mut rng), Some(2.0));
+    assert_eq!(tree.suggest_radius(4, 50, &mut rng), Some(2.0));
+
+    let r = tree.suggest_radius(10, 100, &mut rng).unwrap();
+    assert_eq!(r, 5.0);
+    assert_eq!(tree.count_within_radius(&Point(500.0), r + 0.5), 11);
+
+    assert_eq!(tree.suggest_radius(0, 10, &mut rng), None);
+    assert_eq!(tree.suggest_radius(1000, 10, &mut rng), None);
+}
+
+#[test]
+fn knn_density() {
+    let tree = VPTree::new((0..100).map(|x| Point(x as f64)).chain((0..100).map(|x| Point(1000.0 + x as f64 / 10.0))).collect());
+    assert_eq!(tree.knn_density(&Point(50.0), 4, 1.0), Some(4.0 / (200.0 * 2.0)));
+    let dense = tree.knn_density(&Point(1005.0), 4, 1.0).unwrap();
+    assert!((dense - 4.0 / (200.0 * 0.2)).abs() < 1e-9);
+    assert_eq!(tree.knn_density(&Point(50.0), 0, 1.0), None);
+    assert_eq!(tree.knn_density(&Point(50.0), 201, 1.0), None);
+
+    // Stored items don't count as their own neighbors.
+    let ds = tree.knn_densities(2, 1.0).unwrap();
+    assert_eq!(ds.len(), 200);
+    assert_eq!(ds[50], 2.0 / 200.0);
+    assert!((ds[150] - 2.0 / (200.0 * 0.1)).abs() < 1e-6);
+    assert!(ds[..100].iter().all(|&d| d < ds[150]));
+    assert_eq!(tree.knn_densities(200, 1.0), None);
+
+    let same = VPTree::new(vec![Point(0.0); 3]);
+    assert_eq!(same.knn_densities(2, 1.0), Some(vec![f64::INFINITY; 3]));
+
+    // Collapsed duplicates count for every input item they stand for.
+    let points: Vec<Point> = vec![0.0, 0.0, 0.0, 0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0].into_iter().map(Point).collect();
+    let tree = VPTree::new(points.clone());
+    let collapsed = VPTree::builder().collapse_duplicates(true).build(points);
+    assert_eq!(collapsed.knn_densities(2, 1.0).unwrap()[4], tree.knn_densities(2, 1.0).unwrap()[7]);
+    assert_eq!(collapsed.knn_densities(2, 1.0).unwrap()[4], 0.2);
+    assert_eq!(collapsed.knn_densities(2, 1.0).unwrap()[0], f64::INFINITY);
+    assert_eq!(collapsed.knn_density(&Point(0.0), 2, 1.0), Some(f64::INFINITY));
+    assert_eq!(collapsed.knn_density(&Point(0.0), 5, 1.0), tree.knn_density(&Point(0.0), 5, 1.0));
+    assert_eq!(collapsed.knn_density(&Point(0.0), 11, 1.0), None);
+    assert_eq!(collapsed.knn_densities(9, 1.0).map(|ds| ds.len()), Some(7));
+    assert_eq!(collapsed.knn_densities(10, 1.0), None);
+}
+
+/// Compute local outlier factors by brute force.
+fn brute_force_lof(points: &[Point], k: usize) -> Vec<f64> {
+    let knn: Vec<Vec<(f64, usize)>> = (0..points.len()).map(|i| {
+        let mut ds: Vec<_> = (0..points.len()).filter(|&j| j != i).map(|j| (points[i].distance(&points[j]), j)).collect();
+        ds.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        ds.truncate(k);
+        ds
+    }).collect();
+    let lrd: Vec<f64> = knn.iter().map(|ns| {
+        k as f64 / ns.iter().map(|&(d, j)| d.max(knn[j][k - 1].0)).sum::<f64>()
+    }).collect();
+    knn.iter().enumerate().map(|(i, ns)| ns.iter().map(|&(_, j)| lrd[j]).sum::<f64>() / (k as f64 * lrd[i])).collect()
+}
+
+#[test]
+fn local_outlier_factors() {
+    let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+    let mut points: Vec<_> = (0..300).map(|_| Point(rng.gen())).collect();
+    points.push(Point(2.0));
+    let tree = VPTree::new(points.clone());
+
+    let lof = tree.local_outlier_factors(5).unwrap();
+    assert_eq!(lof.neighbors.len(), 301);
+    assert!(lof.neighbors.iter().enumerate().all(|(i, ns)| ns.len() == 5 && !ns.contains(&i)));
+    assert_eq!(lof.reachability_distance(300, 0.0), lof.k_distances[300]);
+    for (a, b) in lof.scores.iter().zip(brute_force_lof(&points, 5)) {
+        assert!((a - b).abs() < 1e-9, "{} vs {}", a, b);
+    }
+    assert!(lof.scores[300] > 10.0);
+    assert!(lof.scores[..300].iter().all(|&s| s < 3.0));
+    assert_eq!(tree.lof_scores(5), Some(lof.scores));
+
+    // Items with more than `k` duplicates have infinite densities.
+    let same = VPTree::new(vec![Point(0.0); 4]);
+    assert_eq!(same.lof_scores(2), Some(vec![1.0; 4]));
+    assert_eq!(same.local_outlier_factors(2).unwrap().densities, vec![f64::INFINITY; 4]);
+    assert_eq!(same.lof_scores(4), None);
+    assert_eq!(same.lof_scores(0), None);
+
+    // Collapsed trees are scored by their distinct items.
+    let mut dups = points.clone();
+    dups.extend(points[..100].iter().cloned());
+    let collapsed = VPTree::builder().collapse_duplicates(true).build(dups);
+    assert_eq!(collapsed.len(), 301);
+    let collapsed_lof = collapsed.local_outlier_factors(5).unwrap();
+    for (a, b) in collapsed_lof.scores.iter().zip(brute_force_lof(&points, 5)) {
+        assert!((a - b).abs() < 1e-9, "{} vs {}", a, b);
+    }
+    assert!(collapsed_lof.densities.iter().all(|d| d.is_finite()));
+}
+
+#[test]
+fn core_distances() {
+    let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+    let points: Vec<_> = (0..500).map(|_| Point(rng.gen())).collect();
+    let tree = VPTree::new(points.clone());
+    for &k in &[1, 2, 7] {
+        let cds = tree.core_distances(k);
+        for (p, &cd) in points.iter().zip(&cds) {
+            assert_eq!(Some(cd), tree.kth_nearest_distance(p, k));
+        }
+    }
+    assert!(tree.core_distances(1).iter().all(|&d| d == 0.0));
+    assert_eq!(tree.core_distances(501), vec![f64::INFINITY; 500]);
+
+    // Collapsed duplicates count once for each input item.
+    let points = vec![Point(0.0), Point(0.0), Point(1.0), Point(3.0)];
+    let collapsed = VPTree::builder().collapse_duplicates(true).build(points.clone());
+    assert_eq!(collapsed.core_distances(2), vec![0.0, 1.0, 2.0]);
+    assert_eq!(collapsed.core_distances(3), vec![1.0, 1.0, 3.0]);
+    assert_eq!(collapsed.core_distances(5), vec![f64::INFINITY; 3]);
+    assert_eq!(VPTree::new(points).core_distances(2), vec![0.0, 0.0, 1.0, 2.0]);
+}
+
+#[test]
+fn farthest_point_sample() {
+    let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+    let points: Vec<_> = (0..1000).map(|_| Euclidean(vec![rng.gen::<f64>(), rng.gen::<f64>()])).collect();
+    let tree = VPTree::builder().leaf_size(4).build(points.clone());
+    let sample = tree.farthest_point_sample(50, 17);
+
+    // Compare with a linear scan at every step.
+    let mut nearest = vec![f64::INFINITY; points.len()];
+    let mut expected = vec![17];
+    while expected.len() < 50 {
+        let last = &points[*expected.last().unwrap()];
+        for (n, p) in nearest.iter_mut().zip(&points) {
+            *n = n.min(p.distance(last));
+        }
+        let next = (0..points.len()).max_by(|&a, &b| nearest[a].partial_cmp(&nearest[b]).unwrap()).unwrap();
+        expected.push(next);
+    }
+    assert_eq!(sample, expected);
+
+    let line = VPTree::new(vec![Point(0.0), Point(1.0), Point(10.0), Point(1.0), Point(4.0)]);
+    assert_eq!(line.farthest_point_sample(3, 1), vec![1, 2, 4]);
+    // Duplicates are never chosen twice.
+    assert_eq!(line.farthest_point_sample(10, 0).len(), 4);
+    assert!(line.farthest_point_sample(0, 100).is_empty());
+}
+
+/// Choose up to `k` points near `query`, at least `delta` apart, by
+/// brute force.
+fn brute_force_diverse<T: MetricItem<f64>>(points: &[T], query: &T, k: usize, delta: f64) -> Vec<f64> {
+    let mut order: Vec<(f64, usize)> = points.iter().enumerate().map(|(i, p)| (query.distance(p), i)).collect();
+    order.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let mut taken: Vec<usize> = Vec::new();
+    for (_, i) in order {
+        if taken.len() < k && taken.iter().all(|&j| points[i].distance(&points[j]) >= delta) {
+            taken.push(i);
+        }
+    }
+    taken.into_iter().map(|i| query.distance(&points[i])).collect()
+}
+
+#[test]
+fn diverse_nearest_neighbors() {
+    let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+    let points: Vec<_> = (0..2000).map(|_| Euclidean(vec![rng.gen::<f64>(), rng.gen::<f64>()])).collect();
+    let tree = VPTree::builder().leaf_size(4).build(points.clone());
+    for _ in 0..20 {
+        let query = Euclidean(vec![rng.gen::<f64>(), rng.gen::<f64>()]);
+        for &(k, delta) in &[(1, 0.1), (10, 0.0), (10, 0.05), (30, 0.2), (100, 0.3)] {
+            let found = tree.diverse_nearest_neighbors(&query, k, delta);
+            for (i, a) in found.iter().enumerate() {
+                assert!(found[i + 1..].iter().all(|b| a.distance(b) >= delta));
+            }
+            let ds: Vec<f64> = found.iter().map(|p| query.distance(p)).collect();
+            assert_eq!(ds, brute_force_diverse(&points, &query, k, delta));
+        }
+    }
+
+    let line = VPTree::new((0..10).map(|x| Point(x as f64)).collect());
+    let found = line.diverse_nearest_neighbors(&Point(4.2), 3, 2.0);
+    assert_eq!(found, vec![&Point(4.0), &Point(6.0), &Point(2.0)]);
+    assert_eq!(line.diverse_nearest_neighbors(&Point(0.0), 10, 4.0).len(), 3);
+    assert!(line.diverse_nearest_neighbors(&Point(0.0), 0, 1.0).is_empty());
+}
+
+#[test]
+fn classify() {
+    // Negative points are labelled false, non-negative points true.
+    let tree = VPTree::new(vec![Point(-3.0), Point(-2.0), Point(-1.5), Point(0.0), Point(0.5), Point(4.0)]);
+    let label = |p: &Point| p.0 >= 0.0;
+    assert_eq!(tree.classify(&Point(-0.2), 1, label), Some(true));
+    assert_eq!(tree.classify(&Point(-0.2), 3, label), Some(true));
+    assert_eq!(tree.classify(&Point(-0.2), 5, label), Some(false));
+    // Ties go to the label of the nearest point.
+    assert_eq!(tree.classify(&Point(-0.2), 4, label), Some(true));
+    assert_eq!(tree.classify(&Point(-1.0), 4, label), Some(false));
+
+    // Weighted by inverse distance, the two nearest points outvote
+    // the three further ones.
+    assert_eq!(tree.classify_weighted(&Point(-0.2), 5, label), Some(true));
+    assert_eq!(tree.classify_weighted(&Point(0.5), 6, label), Some(true));
+    assert_eq!(tree.classify_weighted(&Point(-2.0), 6, label), Some(false));
+
+    // Collapsed duplicates vote once for each input item.
+    let collapsed = VPTree::builder().collapse_duplicates(true).build(vec![Point(-1.0), Point(-1.0), Point(-1.0), Point(0.0), Point(0.5)]);
+    assert_eq!(collapsed.classify(&Point(0.0), 3, label), Some(false));
+
+    assert_eq!(tree.classify(&Point(0.0), 0, label), None);
+    let empty: VPTree<f64, Point> = VPTree::new(Vec::new());
+    assert_eq!(empty.classify(&Point(0.0), 3, label), None);
+}
@@ -0,0 +1,64 @@
+#![cfg(feature = "ndarray")]
+
+extern crate ndarray;
+extern crate rand;
+extern crate vptree;
+
+use ndarray::{Array1, Array2};
+use rand::{Rng, SeedableRng, XorShiftRng};
+
+use vptree::{BuildError, VPTree};
+use vptree::array::{ArrayVPTree, Norm};
+
+#[test]
+fn rows_match_brute_force() {
+    let mut rng = XorShiftRng::from_seed([3, 3, 7, 7]);
+    let data = Array2::from_shape_fn((300, 4), |_| rng.gen_range(-1.0, 1.0));
+    let queries = Array2::from_shape_fn((20, 4), |_| rng.gen_range(-1.0, 1.0));
+
+    for &norm in &[Norm::L1, Norm::L2, Norm::LInf, Norm::Lp(3.0)] {
+        let tree = ArrayVPTree::with_builder(&VPTree::builder().leaf_size(4).seed(9), data.view(), norm).unwrap();
+        assert_eq!(tree.len(), 300);
+        for q in queries.outer_iter() {
+            let distance = |i: usize| -> f64 {
+                let ds = data.row(i).iter().zip(q.iter()).map(|(x, y)| (x - y).abs()).collect::<Vec<f64>>();
+                match norm {
+                    Norm::L1 => ds.iter().sum(),
+                    Norm::L2 => ds.iter().map(|d| d * d).sum::<f64>().sqrt(),
+                    Norm::LInf => ds.iter().cloned().fold(0.0, f64::max),
+                    Norm::Lp(p) => ds.iter().map(|d| d.powf(p)).sum::<f64>().powf(1.0 / p),
+                }
+            };
+            let mut expected: Vec<usize> = (0..300).collect();
+            expected.sort_by(|&a, &b| distance(a).partial_cmp(&distance(b)).unwrap());
+
+            assert_eq!(tree.nearest_neighbor(q), Some(expected[0]));
+            assert_eq!(tree.nearest_neighbors(q, 5, true), &expected[..5]);
+            let n = expected.iter().filter(|&&i| distance(i) < 0.5).count();
+            assert_eq!(tree.within_radius(q, 0.5, true), &expected[..n]);
+        }
+    }
+}
+
+#[test]
+fn invalid_norms_and_empty_matrices() {
+    let data = Array2::<f64>::zeros((0, 3));
+    assert!(ArrayVPTree::new(data.view(), Norm::Lp(0.5)).is_err());
+    let tree = ArrayVPTree::new(data.view(), Norm::L2).unwrap();
+    assert!(tree.is_empty());
+    assert_eq!(tree.nearest_neighbor(Array1::zeros(3).view()), None);
+
+    let err = ArrayVPTree::with_builder(&VPTree::builder().leaf_size(0), data.view(), Norm::L2).err();
+    assert_eq!(err, Some(BuildError::InvalidParameter("leaf size must be positive")));
+}
+
+#[test]
+fn owned_matrices() {
+    let data = Array2::from_shape_fn((50, 2), |(i, j)| (i * (j + 1)) as f64);
+    let borrowed = ArrayVPTree::new(data.view(), Norm::L2).unwrap();
+    let owned = ArrayVPTree::from_array(&VPTree::builder(), data.clone(), Norm::L2).unwrap();
+    assert_eq!(owned.dim(), 2);
+    let q = Array1::from(vec![10.2, 19.0]);
+    assert_eq!(owned.nearest_neighbor(q.view()), Some(10));
+    assert_eq!(owned.nearest_neighbors(q.view(), 4, true), borrowed.nearest_neighbors(q.view(), 4, true));
+}
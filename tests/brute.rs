@@ -0,0 +1,51 @@
+extern crate rand;
+extern crate vptree;
+
+use rand::{Rng, SeedableRng, XorShiftRng};
+
+use vptree::{MetricItem, VPTree};
+
+#[derive(Debug, PartialEq, Clone)]
+struct Point(f32, f32);
+
+impl MetricItem<f32> for Point {
+    fn distance(&self, a: &Self) -> f32 {
+        ((self.0 - a.0).powi(2) + (self.1 - a.1).powi(2)).sqrt()
+    }
+}
+
+fn random_points<R: Rng>(rng: &mut R, n: usize) -> Vec<Point> {
+    (0..n).map(|_| Point(rng.gen_range(0.0, 100.0), rng.gen_range(0.0, 100.0))).collect()
+}
+
+/// Distances from `q` to every point, in ascending order.
+fn sorted_distances(points: &[Point], q: &Point) -> Vec<f32> {
+    let mut ds: Vec<f32> = points.iter().map(|p| p.distance(q)).collect();
+    ds.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    ds
+}
+
+#[test]
+fn queries_match_brute_force() {
+    let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+    let points = random_points(&mut rng, 500);
+    let queries = random_points(&mut rng, 50);
+
+    for &leaf_size in &[1, 4, 16] {
+        let vp = VPTree::builder().leaf_size(leaf_size).seed(11).build(points.clone());
+
+        for q in &queries {
+            let expected = sorted_distances(&points, q);
+
+            let nearest: Vec<f32> = vp.nearest_neighbors(q, 10, true).iter().map(|p| p.distance(q)).collect();
+            assert_eq!(nearest, &expected[..10]);
+
+            for &r in &[0.5, 5.0, 20.0] {
+                let n = expected.iter().filter(|&&d| d < r).count();
+                assert_eq!(vp.within_radius(q, r, false).len(), n);
+                assert_eq!(vp.count_within_radius(q, r), n);
+                assert_eq!(vp.any_within_radius(q, r), n > 0);
+            }
+        }
+    }
+}
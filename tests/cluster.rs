@@ -0,0 +1,337 @@
+extern crate rand;
+extern crate vptree;
+
+use rand::{Rng, SeedableRng, XorShiftRng};
+use rand::distributions::{IndependentSample, Range};
+use vptree::{DistanceCounter, MetricItem, VPTree};
+use vptree::cluster::{dbscan, k_medoids, optics, single_linkage, ClusterLabel};
+use vptree::metrics::Euclidean;
+
+#[derive(Debug, PartialEq, Clone)]
+struct Point(f64);
+
+impl MetricItem<f64> for Point {
+    fn distance(&self, a: &Self) -> f64 {
+        (self.0 - a.0).abs()
+    }
+}
+
+/// Generate `n` points in the plane, around a few centers and
+/// scattered uniformly.
+fn blobs(rng: &mut XorShiftRng, n: usize) -> Vec<Euclidean<Vec<f64>>> {
+    let centers = [(0.0, 0.0), (5.0, 5.0), (0.0, 8.0)];
+    (0..n).map(|i| {
+        let (x, y) = if i % 5 == 0 {
+            (rng.gen_range(-5.0, 10.0), rng.gen_range(-5.0, 13.0))
+        } else {
+            let (cx, cy) = centers[i % 3];
+            (cx + rng.gen_range(-1.0, 1.0), cy + rng.gen_range(-1.0, 1.0))
+        };
+        Euclidean(vec![x, y])
+    }).collect()
+}
+
+/// Cluster `points` with DBSCAN by brute force.
+fn brute_force_dbscan<T: MetricItem<f64>>(points: &[T], eps: f64, min_pts: usize) -> Vec<ClusterLabel> {
+    let n = points.len();
+    let near = |i: usize| (0..n).filter(move |&j| points[i].distance(&points[j]) < eps);
+    let core: Vec<bool> = (0..n).map(|i| near(i).count() >= min_pts).collect();
+
+    let mut labels = vec![ClusterLabel::Noise; n];
+    let mut next = 0;
+    for i in 0..n {
+        if !core[i] || labels[i] != ClusterLabel::Noise {
+            continue;
+        }
+        let mut frontier = vec![i];
+        labels[i] = ClusterLabel::Cluster(next);
+        while let Some(p) = frontier.pop() {
+            for q in near(p) {
+                if labels[q] == ClusterLabel::Noise {
+                    labels[q] = ClusterLabel::Cluster(next);
+                    if core[q] {
+                        frontier.push(q);
+                    }
+                }
+            }
+        }
+        next += 1;
+    }
+    labels
+}
+
+#[test]
+fn dbscan_matches_brute_force() {
+    let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+    let points = blobs(&mut rng, 600);
+    let tree = VPTree::new(points.clone());
+    for &(eps, min_pts) in &[(0.3, 4), (0.5, 8), (1.0, 1), (2.0, 30)] {
+        assert_eq!(dbscan(&tree, eps, min_pts), brute_force_dbscan(&points, eps, min_pts), "eps {}, min_pts {}", eps, min_pts);
+    }
+
+    let labels = dbscan(&tree, 0.5, 8);
+    let clusters = labels.iter().filter_map(|l| l.cluster()).max().unwrap() + 1;
+    assert_eq!(clusters, 3);
+    assert!(labels.iter().any(|l| l.is_noise()));
+}
+
+#[test]
+fn dbscan_edge_cases() {
+    let empty: VPTree<f64, Point> = VPTree::new(Vec::new());
+    assert!(dbscan(&empty, 1.0, 2).is_empty());
+
+    // Neighbors must be strictly within `eps`.
+    let tree = VPTree::new(vec![Point(0.0), Point(1.0), Point(2.0)]);
+    assert_eq!(dbscan(&tree, 1.0, 2), vec![ClusterLabel::Noise; 3]);
+    assert_eq!(dbscan(&tree, 1.5, 3), vec![ClusterLabel::Cluster(0); 3]);
+
+    // Collapsed duplicates count once for each input item.
+    let points = vec![Point(0.0), Point(0.0), Point(0.0), Point(0.5), Point(3.0)];
+    let collapsed = VPTree::builder().collapse_duplicates(true).build(points.clone());
+    assert_eq!(collapsed.len(), 3);
+    assert_eq!(dbscan(&collapsed, 1.0, 4),
+               vec![ClusterLabel::Cluster(0), ClusterLabel::Cluster(0), ClusterLabel::Noise]);
+    assert_eq!(dbscan(&VPTree::new(points.clone()), 1.0, 4), brute_force_dbscan(&points, 1.0, 4));
+}
+
+#[test]
+fn optics_ordering() {
+    let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+    let points = blobs(&mut rng, 600);
+    let tree = VPTree::new(points.clone());
+    let result = optics(&tree, 2.0, 8);
+
+    let mut sorted = result.ordering.clone();
+    sorted.sort();
+    assert_eq!(sorted, (0..600).collect::<Vec<_>>());
+    assert_eq!(result.reachability_plot().len(), 600);
+    assert_eq!(result.reachability[result.ordering[0]], None);
+
+    for (i, p) in points.iter().enumerate() {
+        let mut ds: Vec<f64> = points.iter().map(|q| p.distance(q)).collect();
+        ds.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let expected = if ds[7] < 2.0 { Some(ds[7]) } else { None };
+        assert_eq!(result.core_distances[i], expected);
+    }
+
+    // Extracted clusters group the core items as DBSCAN does.
+    for &eps in &[0.5, 1.0, 2.0] {
+        let extracted = result.extract_dbscan(eps);
+        let labels = dbscan(&tree, eps, 8);
+        let core: Vec<usize> = (0..600).filter(|&i| result.core_distances[i].is_some_and(|d| d < eps)).collect();
+        assert!(!core.is_empty());
+        for &i in &core {
+            assert!(!extracted[i].is_noise());
+            for &j in &core {
+                assert_eq!(extracted[i] == extracted[j], labels[i] == labels[j]);
+            }
+        }
+    }
+}
+
+#[test]
+fn optics_reachability() {
+    // Two groups of different densities.
+    let tree = VPTree::new(vec![Point(0.0), Point(1.0), Point(2.0), Point(10.0), Point(10.1), Point(10.2)]);
+    let result = optics(&tree, 5.0, 2);
+    assert_eq!(result.ordering, vec![0, 1, 2, 3, 4, 5]);
+    assert_eq!(result.core_distances[0], Some(1.0));
+    let plot = result.reachability_plot();
+    let expected = [None, Some(1.0), Some(1.0), None, Some(0.1), Some(0.1)];
+    for (r, e) in plot.iter().zip(&expected) {
+        assert_eq!(r.is_some(), e.is_some());
+        if let (Some(r), Some(e)) = (*r, *e) {
+            assert!((r - e).abs() < 1e-9, "{:?}", plot);
+        }
+    }
+    assert_eq!(result.extract_dbscan(0.5), vec![
+        ClusterLabel::Noise, ClusterLabel::Noise, ClusterLabel::Noise,
+        ClusterLabel::Cluster(0), ClusterLabel::Cluster(0), ClusterLabel::Cluster(0),
+    ]);
+    assert_eq!(result.extract_dbscan(1.5), vec![
+        ClusterLabel::Cluster(0), ClusterLabel::Cluster(0), ClusterLabel::Cluster(0),
+        ClusterLabel::Cluster(1), ClusterLabel::Cluster(1), ClusterLabel::Cluster(1),
+    ]);
+}
+
+/// Return the total length of a minimum spanning tree over `points`,
+/// by Prim's algorithm.
+fn brute_force_mst_length<T: MetricItem<f64>>(points: &[T]) -> f64 {
+    let mut dist: Vec<f64> = points.iter().map(|p| points[0].distance(p)).collect();
+    let mut done = vec![false; points.len()];
+    done[0] = true;
+    let mut total = 0.0;
+    for _ in 1..points.len() {
+        let next = (0..points.len()).filter(|&i| !done[i])
+            .min_by(|&a, &b| dist[a].partial_cmp(&dist[b]).unwrap()).unwrap();
+        total += dist[next];
+        done[next] = true;
+        for i in 0..points.len() {
+            dist[i] = dist[i].min(points[next].distance(&points[i]));
+        }
+    }
+    total
+}
+
+/// Assert that `edges` form a spanning tree over `n` items.
+fn assert_spanning_tree(edges: &[(usize, usize, f64)], n: usize) {
+    assert_eq!(edges.len(), n.saturating_sub(1));
+    assert!(edges.windows(2).all(|w| w[0].2 <= w[1].2));
+    let mut component: Vec<usize> = (0..n).collect();
+    for &(i, j, _) in edges {
+        assert!(i < j);
+        let (a, b) = (component[i], component[j]);
+        assert_ne!(a, b, "edges form a cycle");
+        for c in component.iter_mut() {
+            if *c == b {
+                *c = a;
+            }
+        }
+    }
+}
+
+#[test]
+fn minimum_spanning_tree() {
+    let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+    let points = blobs(&mut rng, 500);
+    let tree = VPTree::new(points.clone());
+    let edges = tree.minimum_spanning_tree();
+    assert_spanning_tree(&edges, 500);
+    for &(i, j, d) in &edges {
+        assert_eq!(points[i].distance(&points[j]), d);
+    }
+    let total: f64 = edges.iter().map(|e| e.2).sum();
+    assert!((total - brute_force_mst_length(&points)).abs() < 1e-9);
+
+    // A lattice has many edges of equal length.
+    let lattice: Vec<_> = (0..20).flat_map(|x| (0..20).map(move |y| Euclidean(vec![x as f64, y as f64]))).collect();
+    let edges = VPTree::builder().leaf_size(3).build(lattice).minimum_spanning_tree();
+    assert_spanning_tree(&edges, 400);
+    assert!(edges.iter().all(|e| e.2 == 1.0));
+
+    let points = vec![Point(0.0), Point(0.0), Point(2.0), Point(0.0), Point(3.0)];
+    let edges = VPTree::new(points.clone()).minimum_spanning_tree();
+    assert_spanning_tree(&edges, 5);
+    assert_eq!(edges.iter().map(|e| e.2).collect::<Vec<_>>(), vec![0.0, 0.0, 1.0, 2.0]);
+    let collapsed = VPTree::builder().collapse_duplicates(true).build(points);
+    assert_eq!(collapsed.minimum_spanning_tree(), vec![(1, 2, 1.0), (0, 1, 2.0)]);
+
+    let empty: VPTree<f64, Point> = VPTree::new(Vec::new());
+    assert!(empty.minimum_spanning_tree().is_empty());
+    assert!(VPTree::new(vec![Point(1.0)]).minimum_spanning_tree().is_empty());
+}
+
+#[test]
+fn single_linkage_clusters() {
+    let tree = VPTree::new(vec![Point(0.0), Point(10.0), Point(1.0), Point(11.0), Point(30.0), Point(2.0)]);
+    use ClusterLabel::Cluster;
+    assert_eq!(single_linkage(&tree, 1), vec![Cluster(0); 6]);
+    assert_eq!(single_linkage(&tree, 2), vec![Cluster(0), Cluster(0), Cluster(0), Cluster(0), Cluster(1), Cluster(0)]);
+    assert_eq!(single_linkage(&tree, 3), vec![Cluster(0), Cluster(1), Cluster(0), Cluster(1), Cluster(2), Cluster(0)]);
+    assert_eq!(single_linkage(&tree, 10), (0..6).map(Cluster).collect::<Vec<_>>());
+}
+
+#[test]
+fn k_medoids_clusters() {
+    let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+    let points: Vec<_> = blobs(&mut rng, 600).into_iter().enumerate().filter(|&(i, _)| i % 5 != 0).map(|(_, p)| p).collect();
+    let tree = VPTree::new(points.clone());
+
+    let start = k_medoids(&tree, 3, 0, &mut XorShiftRng::from_seed([5, 6, 7, 8]));
+    let result = k_medoids(&tree, 3, 20, &mut XorShiftRng::from_seed([5, 6, 7, 8]));
+    assert_eq!(start.iterations, 0);
+    assert!(result.iterations < 20);
+    assert!(result.cost() < start.cost());
+
+    // Updates only find a local optimum, so keep the best of a few
+    // starts.
+    let result = (0..5).map(|s| k_medoids(&tree, 3, 20, &mut XorShiftRng::from_seed([s, 6, 7, 8])))
+        .min_by(|a, b| a.cost().partial_cmp(&b.cost()).unwrap()).unwrap();
+    assert_eq!(result.labels().len(), points.len());
+
+    // Every item is assigned to its nearest medoid.
+    for (i, p) in points.iter().enumerate() {
+        let nearest = result.medoids.iter().map(|&m| p.distance(&points[m])).fold(f64::INFINITY, f64::min);
+        assert_eq!(result.distances[i], nearest);
+        assert_eq!(result.distances[i], p.distance(&points[result.medoids[result.assignments[i]]]));
+    }
+
+    // Each medoid lies in a different blob, and minimizes the total
+    // distance within its cluster.
+    let mut blobs: Vec<usize> = result.medoids.iter().map(|&m| if points[m].0[0] > 2.5 { 1 } else if points[m].0[1] > 4.0 { 2 } else { 0 }).collect();
+    blobs.sort();
+    assert_eq!(blobs, vec![0, 1, 2]);
+    for (c, &m) in result.medoids.iter().enumerate() {
+        let members: Vec<_> = (0..points.len()).filter(|&i| result.assignments[i] == c).collect();
+        let cost = |x: usize| members.iter().map(|&j| points[x].distance(&points[j])).sum::<f64>();
+        assert!(members.iter().all(|&x| cost(m) <= cost(x) + 1e-9));
+    }
+}
+
+#[test]
+fn k_medoids_edge_cases() {
+    let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+    let tree = VPTree::new(vec![Point(0.0), Point(1.0), Point(5.0)]);
+    let result = k_medoids(&tree, 5, 10, &mut rng);
+    let mut medoids = result.medoids.clone();
+    medoids.sort();
+    assert_eq!(medoids, vec![0, 1, 2]);
+    assert_eq!(result.cost(), 0.0);
+
+    let result = k_medoids(&tree, 1, 10, &mut rng);
+    assert_eq!(result.medoids, vec![1]);
+    assert_eq!(result.distances, vec![1.0, 0.0, 4.0]);
+
+    // Collapsed duplicates weigh as much as the items they stand for.
+    let collapsed = VPTree::builder().collapse_duplicates(true).build(vec![Point(0.0), Point(0.0), Point(0.0), Point(1.0), Point(1.5)]);
+    assert_eq!(k_medoids(&collapsed, 1, 10, &mut rng).medoids, vec![0]);
+
+    let empty: VPTree<f64, Point> = VPTree::new(Vec::new());
+    assert!(k_medoids(&empty, 2, 10, &mut rng).medoids.is_empty());
+}
+
+/// Choose `k` centers among `points` by k-means++ seeding, by brute
+/// force.
+fn brute_force_kmeans_plus_plus<T: MetricItem<f64>>(points: &[T], k: usize, rng: &mut XorShiftRng) -> Vec<usize> {
+    let mut nearest = vec![f64::INFINITY; points.len()];
+    let mut centers = Vec::new();
+    while centers.len() < k {
+        let weights: Vec<f64> = if centers.is_empty() { vec![1.0; points.len()] } else { nearest.iter().map(|d| d * d).collect() };
+        let mut x = Range::new(0.0, weights.iter().sum()).ind_sample(rng);
+        let i = weights.iter().position(|&w| {
+            x -= w;
+            x < 0.0
+        }).unwrap();
+        centers.push(i);
+        for (n, p) in nearest.iter_mut().zip(points) {
+            *n = n.min(p.distance(&points[i]));
+        }
+    }
+    centers
+}
+
+#[test]
+fn kmeans_plus_plus_seeding() {
+    let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+    let points = blobs(&mut rng, 1000);
+    let counter = DistanceCounter::new();
+    let tree = VPTree::new(points.iter().cloned().map(|p| counter.wrap(p)).collect());
+    for &k in &[1, 3, 20] {
+        counter.reset();
+        let centers = tree.kmeans_plus_plus(k, &mut XorShiftRng::from_seed([5, 6, 7, 8]));
+        assert_eq!(centers, brute_force_kmeans_plus_plus(&points, k, &mut XorShiftRng::from_seed([5, 6, 7, 8])));
+        if k == 20 {
+            assert!(counter.count() < 20 * 1000 / 2, "{} distance evaluations", counter.count());
+        }
+    }
+
+    // There can't be more centers than distinct items.
+    let tree = VPTree::new(vec![Point(0.0), Point(1.0), Point(0.0), Point(1.0)]);
+    let mut centers = tree.kmeans_plus_plus(4, &mut rng);
+    assert_eq!(centers.len(), 2);
+    centers.sort_by_key(|&i| i % 2);
+    assert_eq!(centers.iter().map(|&i| i % 2).collect::<Vec<_>>(), vec![0, 1]);
+
+    let empty: VPTree<f64, Point> = VPTree::new(Vec::new());
+    assert!(empty.kmeans_plus_plus(3, &mut rng).is_empty());
+}
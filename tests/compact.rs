@@ -0,0 +1,54 @@
+extern crate rand;
+extern crate vptree;
+
+use rand::{Rng, SeedableRng, XorShiftRng};
+use vptree::{CompactVPTree, MetricItem, VPTree};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct Point(f64, f64);
+
+impl MetricItem<f64> for Point {
+    fn distance(&self, a: &Self) -> f64 {
+        (self.0 - a.0).hypot(self.1 - a.1)
+    }
+}
+
+#[test]
+fn matches_full_precision_tree() {
+    // Points far from the origin, within a fraction of an `f32` ulp of
+    // each other's distances, so that rounding matters everywhere.
+    let mut rng = XorShiftRng::from_seed([4, 3, 2, 1]);
+    let mut point = || Point(1e6 + rng.gen_range(0.0, 0.5), rng.gen_range(0.0, 0.5));
+    let points: Vec<Point> = (0..1000).map(|_| point()).collect();
+    let queries: Vec<Point> = (0..50).map(|_| point()).collect();
+
+    let tree = VPTree::builder().leaf_size(8).seed(3).build(points.clone());
+    let compact = CompactVPTree::from(VPTree::builder().leaf_size(8).seed(3).build(points.clone()));
+    assert_eq!(compact.len(), 1000);
+
+    for q in &queries {
+        let dists = |v: Vec<&Point>| v.into_iter().map(|p| p.distance(q)).collect::<Vec<f64>>();
+        assert_eq!(dists(compact.nearest_neighbors(q, 10, true)), dists(tree.nearest_neighbors(q, 10, true)));
+
+        // Use radii that fall exactly on stored distances.
+        for p in &points[..5] {
+            let r = p.distance(q);
+            assert_eq!(compact.within_radius(q, r, true), tree.within_radius(q, r, true));
+        }
+    }
+}
+
+#[test]
+fn distances_beyond_f32() {
+    let points: Vec<Point> = (0..100).map(|i| Point(i as f64 * 1e300, 0.0)).collect();
+    let compact = CompactVPTree::from(VPTree::builder().leaf_size(2).build(points.clone()));
+    assert_eq!(compact.nearest_neighbor(&Point(41.2e300, 0.0)), Some(&points[41]));
+    assert_eq!(compact.within_radius(&Point(0.0, 0.0), 2.5e300, true), &[&points[0], &points[1], &points[2]]);
+}
+
+#[test]
+fn empty() {
+    let compact: CompactVPTree<Point> = CompactVPTree::from(VPTree::new(vec![]));
+    assert!(compact.is_empty());
+    assert_eq!(compact.nearest_neighbor(&Point(0.0, 0.0)), None);
+}
@@ -0,0 +1,148 @@
+#![cfg(feature = "concurrent")]
+
+extern crate vptree;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use vptree::{ConcurrentVPTree, MetricItem, VPTree};
+
+#[derive(Debug, PartialEq, Clone)]
+struct Point(f32);
+
+impl MetricItem<f32> for Point {
+    fn distance(&self, a: &Self) -> f32 {
+        (self.0 - a.0).abs()
+    }
+}
+
+#[test]
+fn readers_and_writers() {
+    let index = Arc::new(ConcurrentVPTree::new((0..100).map(|x| Point(x as f32)).collect()));
+    let before = index.snapshot();
+
+    let writers: Vec<_> = (0..4).map(|w| {
+        let index = index.clone();
+        thread::spawn(move || {
+            for x in 0..50 {
+                index.insert(Point((100 + w * 50 + x) as f32));
+            }
+        })
+    }).collect();
+    let readers: Vec<_> = (0..4).map(|_| {
+        let index = index.clone();
+        thread::spawn(move || {
+            for x in 0..100 {
+                let q = Point(x as f32 + 0.2);
+                assert_eq!(index.read(|tree| tree.nearest_neighbor(&q).cloned()), Some(Point(x as f32)));
+            }
+        })
+    }).collect();
+    for t in writers.into_iter().chain(readers) {
+        t.join().unwrap();
+    }
+
+    assert_eq!(index.len(), 300);
+    assert_eq!(index.read(|tree| tree.nearest_neighbor(&Point(287.9)).cloned()), Some(Point(288.0)));
+
+    // Snapshots are unaffected by later writes.
+    assert_eq!(before.len(), 100);
+
+    index.retain(|p| p.0 < 10.0);
+    assert_eq!(index.len(), 10);
+    assert_eq!(index.update(|tree| tree.nearest_neighbors(&Point(100.0), 1, false)[0].clone()), Point(9.0));
+}
+
+#[test]
+fn background_rebuild() {
+    let index = Arc::new(ConcurrentVPTree::from_tree(VPTree::builder().leaf_size(2).build(Vec::new())));
+    for x in 0..100 {
+        index.update(|tree| tree.insert(Point(x as f32)));
+    }
+    assert_eq!(index.read(|tree| tree.leaf_count()), 1);
+    let old = index.snapshot();
+
+    let rebuild = index.rebuild_in_background_if_needed().expect("tree is unbalanced");
+    // Queries and writes carry on while the rebuild runs.
+    assert_eq!(index.read(|tree| tree.nearest_neighbor(&Point(40.1)).cloned()), Some(Point(40.0)));
+    index.insert(Point(100.0));
+    rebuild.join().unwrap();
+
+    assert_eq!(index.len(), 101);
+    assert!(index.read(|tree| tree.leaf_count()) > 1);
+    assert!(index.rebuild_in_background_if_needed().is_none());
+    assert_eq!(old.leaf_count(), 1);
+}
+
+static CLONES: AtomicUsize = AtomicUsize::new(0);
+
+/// A point that counts how often it is cloned.
+#[derive(Debug, PartialEq)]
+struct Counted(f32);
+
+impl Clone for Counted {
+    fn clone(&self) -> Self {
+        CLONES.fetch_add(1, Ordering::SeqCst);
+        Counted(self.0)
+    }
+}
+
+impl MetricItem<f32> for Counted {
+    fn distance(&self, a: &Self) -> f32 {
+        (self.0 - a.0).abs()
+    }
+}
+
+#[test]
+fn inserts_replay_instead_of_copying() {
+    let index = ConcurrentVPTree::new((0..1000).map(|x| Counted(x as f32)).collect());
+    CLONES.store(0, Ordering::SeqCst);
+    for x in 1000..2000 {
+        index.insert(Counted(x as f32));
+    }
+    // One copy of the tree for the first insert, and one clone of each
+    // item inserted.
+    assert!(CLONES.load(Ordering::SeqCst) <= 2 * 1000 + 10);
+    assert_eq!(index.len(), 2000);
+
+    // A snapshot held across a write forces a copy, but no write is
+    // lost.
+    let held = index.snapshot();
+    index.insert(Counted(2000.0));
+    index.insert(Counted(2001.0));
+    index.update(|tree| tree.insert(Counted(2002.0)));
+    index.insert(Counted(2003.0));
+    assert_eq!(held.len(), 2000);
+    assert_eq!(index.len(), 2004);
+    for x in 0..2004 {
+        let q = Counted(x as f32 + 0.1);
+        assert_eq!(index.read(|tree| tree.nearest_neighbor(&q).map(|p| p.0)), Some(x as f32));
+    }
+}
+
+#[test]
+fn writes_during_background_rebuild() {
+    for &retain in &[false, true] {
+        let index = Arc::new(ConcurrentVPTree::from_tree(VPTree::builder().leaf_size(2).build(Vec::new())));
+        for x in 0..2000 {
+            index.update(|tree| tree.insert(Point(x as f32)));
+        }
+
+        let rebuild = index.rebuild_in_background();
+        for x in 2000..2100 {
+            index.insert(Point(x as f32));
+        }
+        if retain {
+            index.retain(|p| p.0 >= 10.0);
+        }
+        rebuild.join().unwrap();
+
+        let expected = if retain { 2090 } else { 2100 };
+        assert_eq!(index.len(), expected);
+        assert!(index.read(|tree| tree.leaf_count()) > 1);
+        for x in (if retain { 10 } else { 0 })..2100 {
+            let q = Point(x as f32 + 0.2);
+            assert_eq!(index.read(|tree| tree.nearest_neighbor(&q).cloned()), Some(Point(x as f32)));
+        }
+    }
+}
@@ -0,0 +1,35 @@
+extern crate vptree;
+
+use vptree::{MetricItem, VPTree};
+
+#[derive(Debug, PartialEq, Clone)]
+struct Point(f32);
+
+impl MetricItem<f32> for Point {
+    fn distance(&self, a: &Self) -> f32 {
+        (self.0 - a.0).abs()
+    }
+}
+
+#[test]
+fn count_matches_within_radius() {
+    let points: Vec<_> = (0..200).map(|x| Point(x as f32)).collect();
+    let vp = VPTree::new(points);
+
+    for &(q, r) in &[(0.0, 0.5), (10.3, 4.0), (100.0, 50.0), (-5.0, 1000.0), (500.0, 1.0)] {
+        let q = Point(q);
+        assert_eq!(vp.count_within_radius(&q, r), vp.within_radius(&q, r, false).len());
+    }
+    assert_eq!(vp.count_within_radius(&Point(100.0), 1000.0), 200);
+}
+
+#[test]
+fn any_matches_within_radius() {
+    let points: Vec<_> = (0..200).map(|x| Point(2.0 * x as f32)).collect();
+    let vp = VPTree::new(points);
+
+    for &(q, r) in &[(1.0, 0.5), (1.0, 1.5), (-3.0, 2.0), (-3.0, 4.0), (401.0, 3.0), (250.0, 0.0)] {
+        let q = Point(q);
+        assert_eq!(vp.any_within_radius(&q, r), !vp.within_radius(&q, r, false).is_empty());
+    }
+}
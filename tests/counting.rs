@@ -0,0 +1,69 @@
+extern crate vptree;
+
+use vptree::{DistanceCounter, MetricItem, VPTree};
+
+#[derive(Debug, PartialEq, Clone)]
+struct Point(f32);
+
+impl MetricItem<f32> for Point {
+    fn distance(&self, a: &Self) -> f32 {
+        (self.0 - a.0).abs()
+    }
+}
+
+#[test]
+fn counts_distance_calls() {
+    let counter = DistanceCounter::new();
+    let points: Vec<_> = (0..1000).map(|x| counter.wrap(Point(x as f32))).collect();
+
+    let vp = VPTree::new(points);
+    assert!(counter.count() > 0);
+
+    counter.reset();
+    let q = counter.wrap(Point(500.2));
+    assert_eq!(vp.nearest_neighbor(&q).map(|p| p.item()), Some(&Point(500.0)));
+
+    // The search evaluates far fewer distances than a linear scan.
+    let n = counter.reset();
+    assert!(n > 0 && n < 1000);
+    assert_eq!(counter.count(), 0);
+}
+
+#[test]
+fn leaf_scans_skip_distant_items() {
+    let counter = DistanceCounter::new();
+    let points: Vec<_> = (0..100).map(|x| counter.wrap(Point(x as f32))).collect();
+
+    // A single bucket, where every item's distance to the center is
+    // known in advance.
+    let vp = VPTree::builder().leaf_size(100).build(points);
+    counter.reset();
+
+    // The query is far from everything, so only the center needs an
+    // exact distance.
+    let q = counter.wrap(Point(500.0));
+    assert!(vp.within_radius(&q, 1.0, false).is_empty());
+    assert_eq!(counter.reset(), 1);
+    assert_eq!(vp.count_within_radius(&q, 1.0), 0);
+    assert_eq!(counter.reset(), 1);
+}
+
+#[test]
+fn membership_is_cheaper_than_nearest_neighbor() {
+    let counter = DistanceCounter::new();
+    let points: Vec<_> = (0..1000).map(|x| counter.wrap(Point(x as f32))).collect();
+    let vp = VPTree::builder().leaf_size(4).build(points);
+
+    let mut found = 0;
+    let mut nearest = 0;
+    for x in 0..200 {
+        let q = counter.wrap(Point(x as f32 * 5.0 + 0.5 * (x % 2) as f32));
+        counter.reset();
+        assert_eq!(vp.contains(&q), x % 2 == 0);
+        assert_eq!(vp.find(&q).map(|p| p.item().0), if x % 2 == 0 { Some(x as f32 * 5.0) } else { None });
+        found += counter.reset();
+        vp.nearest_neighbor(&q);
+        nearest += counter.reset() * 2;
+    }
+    assert!(found < nearest);
+}
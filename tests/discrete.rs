@@ -0,0 +1,64 @@
+extern crate rand;
+extern crate vptree;
+
+use rand::{Rng, SeedableRng, XorShiftRng};
+
+use vptree::{MetricItem, VPTree};
+use vptree::metrics::HammingBits;
+
+/// A 32-bit word under the Hamming distance.
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct Word(u32);
+
+impl MetricItem<u32> for Word {
+    fn distance(&self, a: &Self) -> u32 {
+        (self.0 ^ a.0).count_ones()
+    }
+}
+
+#[test]
+fn integer_distances_match_brute_force() {
+    let mut rng = XorShiftRng::from_seed([5, 6, 7, 8]);
+    let words: Vec<Word> = (0..500).map(|_| Word(rng.gen())).collect();
+    let queries: Vec<Word> = (0..50).map(|_| Word(rng.gen())).collect();
+
+    for &leaf_size in &[1, 4, 16] {
+        let vp: VPTree<u32, Word> = VPTree::builder().leaf_size(leaf_size).seed(3).build(words.clone());
+        for q in &queries {
+            let mut expected: Vec<u32> = words.iter().map(|w| w.distance(q)).collect();
+            expected.sort();
+
+            let nearest: Vec<u32> = vp.nearest_neighbors(q, 10, true).iter().map(|w| w.distance(q)).collect();
+            assert_eq!(nearest, &expected[..10]);
+
+            for &r in &[0, 8, 14, 32] {
+                let n = expected.iter().filter(|&&d| d < r).count();
+                assert_eq!(vp.within_radius(q, r, false).len(), n);
+                assert_eq!(vp.count_within_radius(q, r), n);
+            }
+        }
+
+        let far: Vec<u32> = vp.farthest_neighbors(&Word(0), 3, true).iter().map(|w| w.0.count_ones()).collect();
+        let mut ones: Vec<u32> = words.iter().map(|w| w.0.count_ones()).collect();
+        ones.sort_by(|a, b| b.cmp(a));
+        assert_eq!(far, &ones[..3]);
+    }
+}
+
+#[test]
+fn hamming_bits() {
+    assert_eq!(HammingBits(0b1011u64).distance(&HammingBits(0b0110)), 3);
+    assert_eq!(HammingBits([!0u64, 0]).distance(&HammingBits([0, 1])), 65);
+
+    // Words and multi-word blocks agree with the hand-written metric.
+    let mut rng = XorShiftRng::from_seed([1, 3, 5, 7]);
+    let blocks: Vec<Vec<u64>> = (0..300).map(|_| vec![rng.gen(), rng.gen(), rng.gen()]).collect();
+    let vp = VPTree::builder().leaf_size(8).seed(2).build(blocks.iter().map(|b| HammingBits(&b[..])).collect());
+    for q in (0..20).map(|_| [rng.gen(), rng.gen(), rng.gen()]) {
+        let q = HammingBits(&q[..]);
+        let mut expected: Vec<u32> = blocks.iter().map(|b| HammingBits(&b[..]).distance(&q)).collect();
+        expected.sort();
+        let found: Vec<u32> = vp.nearest_neighbors(&q, 5, true).iter().map(|b| b.distance(&q)).collect();
+        assert_eq!(found, &expected[..5]);
+    }
+}
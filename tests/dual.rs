@@ -0,0 +1,72 @@
+extern crate vptree;
+
+use vptree::{MetricItem, VPTree};
+
+#[derive(Debug, PartialEq, Clone)]
+struct Point(f32);
+
+impl MetricItem<f32> for Point {
+    fn distance(&self, a: &Self) -> f32 {
+        (self.0 - a.0).abs()
+    }
+}
+
+#[test]
+fn all_nearest_neighbors_matches_brute_force() {
+    let queries: Vec<_> = (0..80).map(|x| Point((x as f32 * 0.37).sin() * 10.0)).collect();
+    let data: Vec<_> = (1..120).map(|x| Point(10.0 / (x as f32) - 3.0)).collect();
+
+    let query_tree = VPTree::new(queries.clone());
+    let data_tree = VPTree::new(data.clone());
+
+    let k = 3;
+    let result = VPTree::all_nearest_neighbors(&query_tree, &data_tree, k);
+    assert_eq!(result.len(), queries.len());
+
+    for (q, neighbors) in queries.iter().zip(result.iter()) {
+        let mut expected: Vec<f32> = data.iter().map(|p| p.distance(q)).collect();
+        expected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        expected.truncate(k);
+
+        let ds: Vec<f32> = neighbors.iter().map(|&(_, d)| d).collect();
+        assert_eq!(ds, expected);
+        for &(i, d) in neighbors {
+            assert_eq!(data[i].distance(q), d);
+        }
+    }
+}
+
+#[test]
+fn hausdorff_distance() {
+    let a = VPTree::new((0..50).map(|x| Point(x as f32)).collect());
+    let b = VPTree::new((0..10).map(|x| Point(5.0 * x as f32 + 0.5)).collect());
+
+    // Point 49 is 3.5 away from 45.5; point 0 is only 0.5 from 0.5.
+    assert_eq!(a.directed_hausdorff_distance(&b), 3.5);
+    assert_eq!(b.directed_hausdorff_distance(&a), 0.5);
+    assert_eq!(a.hausdorff_distance(&b), 3.5);
+    assert_eq!(b.hausdorff_distance(&a), 3.5);
+    assert_eq!(a.hausdorff_distance(&a), 0.0);
+}
+
+#[test]
+fn knn_graph_matches_brute_force() {
+    // Every value appears twice, and zero four times, so some points
+    // have more duplicates than `k`.
+    let points: Vec<_> = (0..200).map(|x| Point(if x % 50 == 0 { 0.0 } else { ((x % 100) as f32 * 0.61).sin() * 7.0 })).collect();
+    let tree = VPTree::builder().leaf_size(4).build(points.clone());
+
+    for &k in &[1, 3, 8] {
+        let graph = tree.knn_graph(k);
+        assert_eq!(graph.len(), points.len());
+        for (i, neighbors) in graph.iter().enumerate() {
+            let mut expected: Vec<f32> = points.iter().enumerate().filter(|&(j, _)| j != i).map(|(_, p)| p.distance(&points[i])).collect();
+            expected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            expected.truncate(k);
+
+            let ds: Vec<f32> = neighbors.iter().map(|&(_, d)| d).collect();
+            assert_eq!(ds, expected);
+            assert!(neighbors.iter().all(|&(j, d)| j != i && points[j].distance(&points[i]) == d));
+        }
+    }
+}
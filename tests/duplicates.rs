@@ -0,0 +1,58 @@
+extern crate vptree;
+
+use vptree::{MetricItem, VPTree};
+
+/// A point with a label that the metric ignores.
+#[derive(Debug, PartialEq, Clone)]
+struct Labeled(f32, usize);
+
+impl MetricItem<f32> for Labeled {
+    fn distance(&self, a: &Self) -> f32 {
+        (self.0 - a.0).abs()
+    }
+}
+
+fn items() -> Vec<Labeled> {
+    (0..1000).map(|i| Labeled(((i * 7) % 10) as f32, i)).collect()
+}
+
+#[test]
+fn duplicates_collapse() {
+    let builders = vec![
+        VPTree::builder().collapse_duplicates(true),
+        VPTree::builder().collapse_duplicates(true).leaf_size(1),
+        VPTree::builder().collapse_duplicates(true).leaf_size(4).median_sample_size(3),
+        VPTree::builder().collapse_duplicates(true).leaf_size(1).max_depth(2),
+    ];
+    for builder in builders {
+        let vp = builder.build(items());
+        assert_eq!(vp.len(), 10);
+
+        let counted = vp.within_radius_counted(&Labeled(4.2, 0), 1.0, true);
+        assert_eq!(counted, vec![(&Labeled(4.0, 2), 100), (&Labeled(5.0, 5), 100)]);
+        assert_eq!(vp.nearest_neighbors_counted(&Labeled(-1.0, 0), 1, false), vec![(&Labeled(0.0, 0), 100)]);
+
+        // The first of each set of duplicates is kept, in input order.
+        let kept: Vec<usize> = vp.into_items().into_iter().map(|x| x.1).collect();
+        assert_eq!(kept, (0..10).collect::<Vec<_>>());
+    }
+}
+
+#[test]
+fn inserted_duplicates_are_counted() {
+    let mut vp = VPTree::builder().collapse_duplicates(true).leaf_size(2).build(items());
+    vp.insert(Labeled(3.0, 1000));
+    vp.insert(Labeled(3.5, 1001));
+    assert_eq!(vp.len(), 11);
+    assert_eq!(vp.within_radius_counted(&Labeled(3.0, 0), 0.1, false), vec![(&Labeled(3.0, 9), 101)]);
+
+    // Counts survive a rebuild.
+    vp.rebuild();
+    assert_eq!(vp.nearest_neighbors_counted(&Labeled(3.4, 0), 2, true),
+               vec![(&Labeled(3.5, 1001), 1), (&Labeled(3.0, 9), 101)]);
+
+    // Without the option, duplicates are kept apart.
+    let vp = VPTree::new(items());
+    assert_eq!(vp.len(), 1000);
+    assert_eq!(vp.within_radius_counted(&Labeled(3.0, 0), 0.1, false).len(), 100);
+}
@@ -0,0 +1,62 @@
+#![cfg(feature = "edit-distance")]
+
+extern crate rand;
+extern crate vptree;
+
+use rand::{Rng, SeedableRng, XorShiftRng};
+
+use vptree::{MetricItem, VPTree};
+use vptree::metrics::EditDistance;
+
+/// The textbook quadratic Levenshtein distance.
+fn reference(a: &str, b: &str) -> u32 {
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    let mut row: Vec<u32> = (0..=b.len() as u32).collect();
+    for i in 1..=a.len() {
+        let mut diag = row[0];
+        row[0] = i as u32;
+        for j in 1..=b.len() {
+            let next = (diag + (a[i - 1] != b[j - 1]) as u32).min(row[j] + 1).min(row[j - 1] + 1);
+            diag = row[j];
+            row[j] = next;
+        }
+    }
+    row[b.len()]
+}
+
+fn random_word<R: Rng>(rng: &mut R) -> String {
+    let len = rng.gen_range(0, 9);
+    (0..len).map(|_| *rng.choose(&['a', 'b', 'c', 'é', 'ü']).unwrap()).collect()
+}
+
+#[test]
+fn matches_reference() {
+    assert_eq!(EditDistance("kitten").distance(&EditDistance("sitting")), 3);
+    assert_eq!(EditDistance("").distance(&EditDistance("abc")), 3);
+    assert_eq!(EditDistance("flaw").distance(&EditDistance("lawn")), 2);
+    assert_eq!(EditDistance("naïve").distance(&EditDistance("naive")), 1);
+
+    let mut rng = XorShiftRng::from_seed([2, 4, 6, 8]);
+    for _ in 0..500 {
+        let (a, b) = (random_word(&mut rng), random_word(&mut rng));
+        let d = reference(&a, &b);
+        let (a, b) = (EditDistance(a), EditDistance(b));
+        assert_eq!(a.distance(&b), d);
+        for max in 0..6 {
+            assert_eq!(a.distance_within(&b, max), if d <= max { Some(d) } else { None });
+        }
+    }
+}
+
+#[test]
+fn fuzzy_lookup() {
+    let mut rng = XorShiftRng::from_seed([1, 1, 2, 3]);
+    let words: Vec<String> = (0..400).map(|_| random_word(&mut rng)).collect();
+    let vp = VPTree::builder().seed(5).build(words.iter().map(|w| EditDistance(w.as_str())).collect());
+
+    for _ in 0..30 {
+        let q = random_word(&mut rng);
+        let expected = words.iter().filter(|w| reference(w, &q) < 3).count();
+        assert_eq!(vp.within_radius(&EditDistance(q.as_str()), 3, false).len(), expected);
+    }
+}
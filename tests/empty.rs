@@ -0,0 +1,51 @@
+extern crate vptree;
+
+use vptree::{Aggregate, Exclude, MetricItem, VPTree};
+
+#[derive(Debug, PartialEq, Clone)]
+struct Point(f32);
+
+impl MetricItem<f32> for Point {
+    fn distance(&self, a: &Self) -> f32 {
+        (self.0 - a.0).abs()
+    }
+}
+
+#[test]
+fn empty_tree_queries() {
+    let vp: VPTree<f32, Point> = VPTree::new(Vec::new());
+    let q = Point(1.0);
+
+    assert_eq!(vp.nearest_neighbor(&q), None);
+    assert!(vp.nearest_neighbors(&q, 3, true).is_empty());
+    assert_eq!(vp.nearest_neighbor_excluding(&q, Exclude::Zero), None);
+    assert_eq!(vp.farthest_neighbor(&q), None);
+    assert!(vp.farthest_neighbors(&q, 3, true).is_empty());
+    assert!(vp.within_radius(&q, 10.0, true).is_empty());
+    assert_eq!(vp.count_within_radius(&q, 10.0), 0);
+    assert!(!vp.any_within_radius(&q, 10.0));
+    assert_eq!(vp.kth_nearest_distance(&q, 1), None);
+    assert!(vp.reverse_nearest_neighbors(&q, 1).is_empty());
+    assert_eq!(vp.aggregate_nearest_neighbor(&[q], Aggregate::Sum), None);
+    assert!(vp.knn_graph(2).is_empty());
+    assert!(vp.pairs_within(1.0).is_empty());
+    assert_eq!(vp.dump(), "");
+}
+
+#[test]
+fn empty_tree_joins() {
+    let empty: VPTree<f32, Point> = VPTree::default();
+    let full = VPTree::new(vec![Point(0.0), Point(1.0)]);
+
+    assert!(VPTree::all_nearest_neighbors(&empty, &full, 1).is_empty());
+    assert_eq!(VPTree::all_nearest_neighbors(&full, &empty, 1), vec![vec![], vec![]]);
+
+    assert_eq!(empty.directed_hausdorff_distance(&full), 0.0);
+    assert_eq!(full.directed_hausdorff_distance(&empty), f32::INFINITY);
+}
+
+#[test]
+fn zero_neighbors() {
+    let vp = VPTree::new(vec![Point(0.0), Point(1.0)]);
+    assert!(vp.nearest_neighbors(&Point(0.5), 0, true).is_empty());
+}
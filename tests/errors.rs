@@ -0,0 +1,122 @@
+extern crate vptree;
+
+use std::panic::{self, AssertUnwindSafe};
+use vptree::{Aggregate, BuildError, CompactVPTree, MetricItem, PersistentVPTree, QueryError, SpillTree, VPTree, WeightedVPTree};
+
+#[derive(Debug, PartialEq, Clone)]
+struct Point(f32);
+
+impl MetricItem<f32> for Point {
+    fn distance(&self, a: &Self) -> f32 {
+        (self.0 - a.0).abs()
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+struct Point64(f64);
+
+impl MetricItem<f64> for Point64 {
+    fn distance(&self, a: &Self) -> f64 {
+        (self.0 - a.0).abs()
+    }
+}
+
+/// A broken metric that goes negative for points on opposite sides
+/// of zero.
+#[derive(Debug, PartialEq, Clone)]
+struct Signed(f32);
+
+impl MetricItem<f32> for Signed {
+    fn distance(&self, a: &Self) -> f32 {
+        self.0 * a.0
+    }
+}
+
+#[test]
+fn try_new_errors() {
+    assert_eq!(VPTree::<f32, Point>::try_new(Vec::new()).err(), Some(BuildError::Empty));
+
+    let nan = vec![Point(0.0), Point(f32::NAN), Point(2.0)];
+    assert_eq!(VPTree::try_new(nan).err(), Some(BuildError::InvalidDistance));
+
+    let signed = vec![Signed(-1.0), Signed(2.0), Signed(3.0)];
+    assert_eq!(VPTree::try_new(signed).err(), Some(BuildError::InvalidDistance));
+
+    let ok = VPTree::try_new(vec![Point(0.0), Point(1.0)]).unwrap();
+    assert_eq!(ok.nearest_neighbor(&Point(0.7)), Some(&Point(1.0)));
+}
+
+#[test]
+#[should_panic]
+fn new_panics_on_nan() {
+    VPTree::new(vec![Point(0.0), Point(f32::NAN), Point(2.0)]);
+}
+
+#[test]
+fn try_queries_errors() {
+    let tree = VPTree::new((0..100).map(|x| Point(x as f32)).collect());
+    let nan = Point(f32::NAN);
+    assert_eq!(tree.try_nearest_neighbors(&nan, 3, true).err(), Some(QueryError::InvalidDistance));
+    assert_eq!(tree.try_within_radius(&nan, 5.0, true).err(), Some(QueryError::InvalidDistance));
+
+    assert_eq!(tree.try_nearest_neighbors(&Point(10.2), 2, true).unwrap(), vec![&Point(10.0), &Point(11.0)]);
+    assert_eq!(tree.try_within_radius(&Point(10.2), 1.0, true).unwrap(), vec![&Point(10.0), &Point(11.0)]);
+    assert_eq!(tree.try_nearest_neighbors(&nan, 0, true).unwrap(), Vec::<&Point>::new());
+}
+
+#[test]
+#[should_panic(expected = "negative or NaN distance to the query")]
+fn nearest_neighbors_panics_on_nan() {
+    let tree = VPTree::new((0..100).map(|x| Point(x as f32)).collect());
+    tree.nearest_neighbors(&Point(f32::NAN), 3, true);
+}
+
+#[test]
+#[should_panic(expected = "negative or NaN distance to the query")]
+fn within_radius_panics_on_negative() {
+    let tree = VPTree::new((1..100).map(|x| Signed(x as f32)).collect());
+    tree.within_radius(&Signed(-1.0), 5.0, false);
+}
+
+/// Return the message `f` panics with, if it panics.
+fn panic_message<R, Q: FnOnce() -> R>(f: Q) -> Option<String> {
+    let err = panic::catch_unwind(AssertUnwindSafe(f)).err()?;
+    err.downcast_ref::<String>().cloned().or_else(|| err.downcast_ref::<&str>().map(|s| s.to_string()))
+}
+
+#[test]
+fn every_query_panics_clearly_on_nan() {
+    let expected = Some("failed to query VPTree: metric returned a negative or NaN distance to the query".to_string());
+    let items = || (0..100).map(|x| Point(x as f32)).collect::<Vec<_>>();
+    let nan = Point(f32::NAN);
+
+    let tree = VPTree::new(items());
+    assert_eq!(panic_message(|| tree.count_within_radius(&nan, 5.0)), expected);
+    assert_eq!(panic_message(|| tree.any_within_radius(&nan, 5.0)), expected);
+    assert_eq!(panic_message(|| tree.farthest_neighbors(&nan, 3, true)), expected);
+    assert_eq!(panic_message(|| tree.aggregate_nearest_neighbor(&[Point(1.0), nan.clone()], Aggregate::Sum)), expected);
+    assert_eq!(panic_message(|| VPTree::new(items()).into_sorted_by_distance_from(&nan)), expected);
+    assert_eq!(panic_message(|| tree.diverse_nearest_neighbors(&nan, 3, 2.0)), expected);
+    let signed = VPTree::new((1..100).map(|x| Signed(x as f32)).collect());
+    assert_eq!(panic_message(|| signed.diverse_nearest_neighbors(&Signed(-1.0), 3, 2.0)), expected);
+    assert_eq!(panic_message(|| tree.distance_histogram(&nan, 10)), expected);
+    assert_eq!(panic_message(|| signed.distance_histogram(&Signed(-1.0), 10)), expected);
+
+    let weighted = WeightedVPTree::new(items().into_iter().map(|p| (p, 1.0)).collect());
+    assert_eq!(panic_message(|| weighted.weighted_nearest_neighbors(&nan, 3, true)), expected);
+    assert_eq!(panic_message(|| weighted.weight_within_radius(&nan, 5.0)), expected);
+
+    let persistent = PersistentVPTree::new(items());
+    assert_eq!(panic_message(|| persistent.nearest_neighbors(&nan, 3, true)), expected);
+    assert_eq!(panic_message(|| persistent.within_radius(&nan, 5.0, true)), expected);
+
+    let spill = SpillTree::new(items(), 0.1);
+    assert_eq!(panic_message(|| spill.nearest_neighbors(&nan, 3, true)), expected);
+    assert_eq!(panic_message(|| spill.within_radius(&nan, 5.0, true)), expected);
+    let signed = SpillTree::new((1..100).map(|x| Signed(x as f32)).collect(), 0.1);
+    assert_eq!(panic_message(|| signed.within_radius(&Signed(-1.0), 5.0, true)), expected);
+
+    let compact = CompactVPTree::new((0..100).map(|x| Point64(x as f64)).collect());
+    assert_eq!(panic_message(|| compact.nearest_neighbors(&Point64(f64::NAN), 3, true)), expected);
+    assert_eq!(panic_message(|| compact.within_radius(&Point64(f64::NAN), 5.0, true)), expected);
+}
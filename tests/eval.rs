@@ -0,0 +1,68 @@
+extern crate vptree;
+
+use vptree::{BruteForce, DistanceCounter, MetricItem, NearestNeighborIndex, VPTree};
+use vptree::eval::evaluate;
+
+#[derive(Debug, PartialEq, Clone)]
+struct Point(f64);
+
+impl MetricItem<f64> for Point {
+    fn distance(&self, a: &Self) -> f64 {
+        (self.0 - a.0).abs()
+    }
+}
+
+/// An index that only ever searches its even-numbered items.
+struct EvensOnly(BruteForce<Point>);
+
+impl NearestNeighborIndex<f64, Point> for EvensOnly {
+    fn build(items: Vec<Point>) -> Self {
+        EvensOnly(BruteForce::new(items.into_iter().step_by(2).collect()))
+    }
+
+    fn len(&self) -> usize {
+        2 * self.0.items().len()
+    }
+
+    fn nearest_neighbors(&self, query: &Point, k: usize, sorted: bool) -> Vec<&Point> {
+        self.0.nearest_neighbors(query, k, sorted)
+    }
+
+    fn within_radius(&self, query: &Point, radius: f64, sorted: bool) -> Vec<&Point> {
+        self.0.within_radius(query, radius, sorted)
+    }
+}
+
+#[test]
+fn exact_trees() {
+    let counter = DistanceCounter::new();
+    let points: Vec<_> = (0..500).map(|x| counter.wrap(Point(x as f64))).collect();
+    let tree = VPTree::builder().leaf_size(4).seed(1).build(points.clone());
+    let reference = BruteForce::new(points);
+    let queries: Vec<_> = (0..10).map(|x| counter.wrap(Point(x as f64 * 49.7))).collect();
+
+    let result = evaluate(&tree, &reference, &queries, 10, Some(&counter));
+    assert_eq!((result.queries, result.k), (10, 10));
+    assert_eq!(result.recall, 1.0);
+    assert_eq!(result.mean_distance_error, 0.0);
+    assert_eq!(result.max_distance_error, 0.0);
+    let evaluations = result.mean_distance_evaluations.unwrap();
+    assert!((10.0..500.0).contains(&evaluations), "{}", evaluations);
+
+    assert_eq!(evaluate(&tree, &reference, &queries, 10, None).mean_distance_evaluations, None);
+}
+
+#[test]
+fn lossy_index() {
+    let points: Vec<Point> = (0..100).map(|x| Point(x as f64)).collect();
+    let index = EvensOnly::build(points.clone());
+    let reference = BruteForce::new(points);
+
+    // The 4 nearest to 50, at distances 0, 1, 1 and 2, are 50, 49, 51
+    // and 48 or 52. The evens found are at distances 0, 2, 2 and 4, so
+    // three count as true neighbors.
+    let result = evaluate(&index, &reference, &[Point(50.0)], 4, None);
+    assert_eq!(result.recall, 0.75);
+    assert_eq!(result.mean_distance_error, 1.0);
+    assert_eq!(result.max_distance_error, 2.0);
+}
@@ -0,0 +1,36 @@
+extern crate vptree;
+
+use vptree::{Exclude, MetricItem, VPTree};
+
+#[derive(Debug, PartialEq, Clone)]
+struct Point(f32);
+
+impl MetricItem<f32> for Point {
+    fn distance(&self, a: &Self) -> f32 {
+        (self.0 - a.0).abs()
+    }
+}
+
+#[test]
+fn exclude_zero() {
+    let points: Vec<_> = vec![0.0, 1.0, 1.0, 3.0, 6.0, 10.0].into_iter().map(Point).collect();
+    let vp = VPTree::new(points);
+
+    assert_eq!(vp.nearest_neighbor_excluding(&Point(3.0), Exclude::Zero), Some(&Point(1.0)));
+    assert_eq!(vp.nearest_neighbors_excluding(&Point(1.0), 2, true, Exclude::Zero),
+               vec![&Point(0.0), &Point(3.0)]);
+}
+
+#[test]
+fn exclude_index() {
+    let points: Vec<_> = vec![0.0, 1.0, 1.0, 3.0, 6.0, 10.0].into_iter().map(Point).collect();
+    let vp = VPTree::new(points);
+
+    // The duplicate of the excluded point is still found.
+    assert_eq!(vp.nearest_neighbors_excluding(&Point(1.0), 2, true, Exclude::Index(1)),
+               vec![&Point(1.0), &Point(0.0)]);
+    assert_eq!(vp.nearest_neighbors_excluding(&Point(6.0), 10, false, Exclude::Index(4)).len(), 5);
+
+    let single = VPTree::new(vec![Point(2.0)]);
+    assert_eq!(single.nearest_neighbor_excluding(&Point(2.0), Exclude::Index(0)), None);
+}
@@ -0,0 +1,30 @@
+extern crate vptree;
+
+use vptree::{MetricItem, VPTree};
+
+#[derive(Debug, PartialEq, Clone)]
+struct Point(f32);
+
+impl MetricItem<f32> for Point {
+    fn distance(&self, a: &Self) -> f32 {
+        (self.0 - a.0).abs()
+    }
+}
+
+#[test]
+fn farthest_linear() {
+    let points: Vec<_> = (0..100).map(|x| Point(x as f32)).collect();
+    let vp = VPTree::new(points);
+
+    assert_eq!(vp.farthest_neighbor(&Point(10.0)), Some(&Point(99.0)));
+    assert_eq!(vp.farthest_neighbor(&Point(80.0)), Some(&Point(0.0)));
+
+    let ps = vp.farthest_neighbors(&Point(30.0), 4, true);
+    assert_eq!(ps, vec![&Point(99.0), &Point(98.0), &Point(97.0), &Point(96.0)]);
+
+    let ps = vp.farthest_neighbors(&Point(49.6), 3, true);
+    assert_eq!(ps, vec![&Point(0.0), &Point(99.0), &Point(1.0)]);
+
+    assert_eq!(vp.farthest_neighbors(&Point(0.0), 500, false).len(), 100);
+    assert!(vp.farthest_neighbors(&Point(0.0), 0, false).is_empty());
+}
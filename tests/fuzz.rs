@@ -0,0 +1,73 @@
+#![cfg(feature = "arbitrary")]
+
+extern crate arbitrary;
+extern crate vptree;
+
+use arbitrary::{Arbitrary, Unstructured};
+use vptree::fuzz::{fuzz_check, FuzzInput};
+use vptree::{NodeLayout, VantageStrategy};
+
+/// Return `len` bytes from a linear congruential generator.
+fn bytes(seed: u64, len: usize) -> Vec<u8> {
+    let mut state = seed;
+    (0..len).map(|_| {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (state >> 56) as u8
+    }).collect()
+}
+
+#[test]
+fn decoded_inputs() {
+    for seed in 0..200 {
+        let data = bytes(seed, 50 + 10 * seed as usize);
+        let input = FuzzInput::arbitrary(&mut Unstructured::new(&data)).unwrap();
+
+        let dim = input.queries[0].len();
+        assert!(input.points.iter().chain(&input.queries).all(|p| p.len() == dim));
+        fuzz_check(&input);
+    }
+}
+
+#[test]
+fn short_inputs() {
+    for len in 0..16 {
+        let data = bytes(len as u64, len);
+        if let Ok(input) = FuzzInput::arbitrary(&mut Unstructured::new(&data)) {
+            fuzz_check(&input);
+        }
+    }
+}
+
+#[test]
+fn duplicate_points() {
+    let input = FuzzInput {
+        points: vec![vec![1.0, 1.0]; 40].into_iter().chain(vec![vec![2.0, 1.0]; 40]).collect(),
+        queries: vec![vec![1.5, 1.0], vec![1.0, 1.0]],
+        k: 10,
+        radius: 0.5,
+        strategy: VantageStrategy::Random,
+        leaf_size: 1,
+        median_sample_size: Some(3),
+        max_depth: None,
+        layout: NodeLayout::BreadthFirst,
+        seed: 7,
+    };
+    fuzz_check(&input);
+}
+
+#[test]
+fn empty_points() {
+    let input = FuzzInput {
+        points: Vec::new(),
+        queries: vec![vec![0.0, 0.0]],
+        k: 3,
+        radius: 1.0,
+        strategy: VantageStrategy::MaxSpread,
+        leaf_size: 2,
+        median_sample_size: None,
+        max_depth: Some(2),
+        layout: NodeLayout::VanEmdeBoas,
+        seed: 3,
+    };
+    fuzz_check(&input);
+}
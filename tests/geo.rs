@@ -0,0 +1,44 @@
+#![cfg(feature = "geo")]
+
+extern crate rand;
+extern crate vptree;
+
+use rand::{Rng, SeedableRng, XorShiftRng};
+
+use vptree::{MetricItem, VPTree};
+use vptree::metrics::GeoPoint;
+
+#[test]
+fn known_distances() {
+    let paris = GeoPoint::new(48.8566, 2.3522);
+    let london = GeoPoint::new(51.5074, -0.1278);
+    let d = paris.distance(&london);
+    assert!((d - 343_500.0).abs() < 1000.0, "{}", d);
+
+    // Antipodes, where the haversine formula is least precise, and
+    // points across the date line.
+    let angle = GeoPoint::new(10.0, 20.0).central_angle(&GeoPoint::new(-10.0, -160.0));
+    assert!((angle - std::f64::consts::PI).abs() < 1e-6);
+    let d = GeoPoint::new(0.0, 179.5).distance(&GeoPoint::new(0.0, -179.5));
+    assert!((d - GeoPoint::radians_to_meters(1f64.to_radians())).abs() < 1e-6);
+    assert_eq!(GeoPoint::meters_to_radians(GeoPoint::radians_to_meters(0.25)), 0.25);
+}
+
+#[test]
+fn nearest_points_of_interest() {
+    let mut rng = XorShiftRng::from_seed([6, 5, 4, 3]);
+    let mut random_point = || GeoPoint::new(rng.gen_range(-90.0, 90.0), rng.gen_range(-180.0, 180.0));
+    let points: Vec<GeoPoint> = (0..500).map(|_| random_point()).collect();
+    let queries: Vec<GeoPoint> = (0..20).map(|_| random_point()).collect();
+
+    let vp = VPTree::builder().seed(1).build(points.clone());
+    for q in &queries {
+        let mut expected: Vec<f64> = points.iter().map(|p| p.distance(q)).collect();
+        expected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let found: Vec<f64> = vp.nearest_neighbors(q, 5, true).iter().map(|p| p.distance(q)).collect();
+        assert_eq!(found, &expected[..5]);
+
+        let n = expected.iter().filter(|&&d| d < 1_000_000.0).count();
+        assert_eq!(vp.within_radius(q, 1_000_000.0, false).len(), n);
+    }
+}
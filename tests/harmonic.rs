@@ -7,7 +7,7 @@ struct Point(f32);
 
 impl MetricItem<f32> for Point {
     fn distance(&self, a: &Self) -> f32 {
-        return (self.0 - a.0).abs()
+        (self.0 - a.0).abs()
     }
 }
 
@@ -17,17 +17,15 @@ fn test_harmonic() {
     for n in 10..101 {
         let points: Vec<_> = (1..n+1).map(|x| Point(1.0 / (x as f32))).collect();
 
-        let vp = VPTree::new(points.iter().cloned().collect());
-        assert!(vp.is_some());
-        let vp = vp.unwrap();
+        let vp = VPTree::new(points.to_vec());
 
         for p in &points {
             // each point should be nearest to itself.
-            if vp.nearest_neighbor(p) != p {
+            if vp.nearest_neighbor(p) != Some(p) {
                 print!("\n{}", vp.dump());
                 done = true;
             }
-            assert_eq!(vp.nearest_neighbor(p), p);
+            assert_eq!(vp.nearest_neighbor(p), Some(p));
          }
         if done {
             break;
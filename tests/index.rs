@@ -0,0 +1,50 @@
+extern crate rand;
+extern crate vptree;
+
+use rand::{Rng, SeedableRng, XorShiftRng};
+
+use vptree::{BruteForce, CompactVPTree, MetricItem, NearestNeighborIndex, PersistentVPTree, VPTree};
+
+#[derive(Debug, PartialEq, Clone)]
+struct Point(f64, f64);
+
+impl MetricItem<f64> for Point {
+    fn distance(&self, a: &Self) -> f64 {
+        (self.0 - a.0).hypot(self.1 - a.1)
+    }
+}
+
+/// Answer the same queries with index `I` over `points`.
+fn answers<I: NearestNeighborIndex<f64, Point>>(points: &[Point], queries: &[Point]) -> Vec<(Vec<Point>, Vec<Point>)> {
+    let index = I::build(points.to_vec());
+    assert_eq!(index.len(), points.len());
+    queries.iter().map(|q| {
+        let nearest = index.nearest_neighbors(q, 7, true).into_iter().cloned().collect();
+        let within = index.within_radius(q, 10.0, true).into_iter().cloned().collect();
+        (nearest, within)
+    }).collect()
+}
+
+#[test]
+fn indices_agree() {
+    let mut rng = XorShiftRng::from_seed([8, 6, 4, 2]);
+    let mut point = || Point(rng.gen_range(0.0, 100.0), rng.gen_range(0.0, 100.0));
+    let points: Vec<Point> = (0..400).map(|_| point()).collect();
+    let queries: Vec<Point> = (0..30).map(|_| point()).collect();
+
+    let expected = answers::<BruteForce<Point>>(&points, &queries);
+    assert_eq!(answers::<VPTree<f64, Point>>(&points, &queries), expected);
+    assert_eq!(answers::<PersistentVPTree<f64, Point>>(&points, &queries), expected);
+    assert_eq!(answers::<CompactVPTree<Point>>(&points, &queries), expected);
+}
+
+#[test]
+fn brute_force() {
+    let index = BruteForce::new(vec![Point(0.0, 0.0), Point(3.0, 0.0), Point(1.0, 0.0)]);
+    assert_eq!(index.nearest_neighbor(&Point(2.9, 0.0)), Some(&Point(3.0, 0.0)));
+    assert_eq!(index.nearest_neighbors(&Point(0.0, 0.0), 5, true).len(), 3);
+    assert_eq!(index.within_radius(&Point(0.0, 0.0), 1.0, true), vec![&Point(0.0, 0.0)]);
+    let empty: BruteForce<Point> = BruteForce::new(vec![]);
+    assert!(NearestNeighborIndex::<f64, Point>::is_empty(&empty));
+    assert_eq!(empty.nearest_neighbor(&Point(0.0, 0.0)), None);
+}
@@ -0,0 +1,118 @@
+extern crate vptree;
+
+use vptree::{MetricItem, VPTree};
+
+#[derive(Debug, PartialEq, Clone)]
+struct Point(f32);
+
+impl MetricItem<f32> for Point {
+    fn distance(&self, a: &Self) -> f32 {
+        (self.0 - a.0).abs()
+    }
+}
+
+fn check_neighbors(vp: &VPTree<f32, Point>, n: usize) {
+    assert_eq!(vp.len(), n);
+    for x in 0..n {
+        let q = Point(x as f32 + 0.2);
+        assert_eq!(vp.nearest_neighbor(&q), Some(&Point(x as f32)));
+        assert_eq!(vp.count_within_radius(&q, 2.5), vp.within_radius(&q, 2.5, false).len());
+    }
+}
+
+#[test]
+fn insert_one_at_a_time() {
+    let mut vp = VPTree::builder().leaf_size(2).build(Vec::new());
+    for x in 0..200 {
+        vp.insert(Point(((x * 71) % 200) as f32));
+    }
+    check_neighbors(&vp, 200);
+
+    // Inserted items are indexed in insertion order.
+    let items = vp.into_items();
+    assert_eq!(items[1], Point(71.0));
+}
+
+#[test]
+fn extend_with_batches() {
+    let mut vp = VPTree::builder().leaf_size(4).build((0..100).map(|x| Point(x as f32)).collect());
+    vp.extend((100..110).map(|x| Point(x as f32)));
+    check_neighbors(&vp, 110);
+
+    // A large batch triggers a rebuild.
+    vp.extend((110..300).map(|x| Point(x as f32)));
+    check_neighbors(&vp, 300);
+    assert!(vp.depth() < 12);
+
+    let more: Vec<_> = (300..310).map(|x| Point(x as f32)).collect();
+    vp.extend_from_slice(&more);
+    check_neighbors(&vp, 310);
+    assert_eq!(vp.knn_graph(1)[309], vec![(308, 1.0)]);
+}
+
+#[test]
+fn retain_subsets() {
+    let points: Vec<_> = (0..200).map(|x| Point(x as f32)).collect();
+
+    // Few removals patch the tree in place.
+    let mut vp = VPTree::builder().leaf_size(8).build(points.clone());
+    vp.retain(|p| p.0 as u32 % 40 != 7);
+    assert_eq!(vp.len(), 195);
+    assert_eq!(vp.nearest_neighbor(&Point(46.9)), Some(&Point(46.0)));
+    assert_eq!(vp.within_radius(&Point(87.1), 1.5, true), vec![&Point(88.0), &Point(86.0)]);
+
+    // Indices are dense again, so later inserts still line up.
+    vp.insert(Point(7.75));
+    assert_eq!(vp.knn_graph(1)[195], vec![(7, 0.25)]);
+
+    // Removing most items rebuilds.
+    vp.retain(|p| p.0 >= 150.0);
+    assert_eq!(vp.len(), 49);
+    for x in 150..200 {
+        let expected = if x == 167 { 166.0 } else { x as f32 };
+        assert_eq!(vp.nearest_neighbor(&Point(x as f32 - 0.2)), Some(&Point(expected)));
+    }
+    assert!(vp.depth() < 6);
+
+    vp.retain(|_| false);
+    assert!(vp.is_empty());
+    assert_eq!(vp.nearest_neighbor(&Point(1.0)), None);
+}
+
+#[test]
+fn explicit_rebuild() {
+    let vp = VPTree::builder().leaf_size(4).build((0..100).map(|x| Point(x as f32)).collect());
+    assert!(!vp.needs_rebuild());
+
+    // Inserting into an empty tree without rebuilding piles every item
+    // into a single leaf.
+    let mut vp = VPTree::builder().leaf_size(4).build(Vec::new());
+    for x in 0..100 {
+        vp.insert(Point(x as f32));
+    }
+    assert!(vp.needs_rebuild());
+    assert_eq!(vp.leaf_count(), 1);
+
+    vp.rebuild();
+    assert!(!vp.needs_rebuild());
+    assert!(vp.leaf_count() >= 25);
+    check_neighbors(&vp, 100);
+    assert_eq!(vp.into_items()[42], Point(42.0));
+}
+
+#[test]
+fn pop_nearest_matches_greedily() {
+    let mut vp = VPTree::builder().leaf_size(4).build((0..50).map(|x| Point(x as f32 * 2.0)).collect());
+
+    // Each query takes the closest point still available.
+    let taken: Vec<Point> = (0..50).map(|x| vp.pop_nearest(&Point(x as f32 * 2.0 + 0.3)).unwrap()).collect();
+    assert_eq!(taken, (0..50).map(|x| Point(x as f32 * 2.0)).collect::<Vec<_>>());
+    assert!(vp.is_empty());
+    assert_eq!(vp.pop_nearest(&Point(0.0)), None);
+
+    let mut vp = VPTree::new((0..100).map(|x| Point(x as f32)).collect());
+    assert_eq!(vp.pop_nearest(&Point(40.4)), Some(Point(40.0)));
+    assert_eq!(vp.pop_nearest(&Point(40.4)), Some(Point(41.0)));
+    assert_eq!(vp.len(), 98);
+    assert_eq!(vp.nearest_neighbor(&Point(40.4)), Some(&Point(39.0)));
+}
@@ -0,0 +1,93 @@
+extern crate vptree;
+
+use vptree::{MetricItem, VPTree};
+
+#[derive(Debug, PartialEq, Clone)]
+struct Point(f32);
+
+impl MetricItem<f32> for Point {
+    fn distance(&self, a: &Self) -> f32 {
+        (self.0 - a.0).abs()
+    }
+}
+
+#[test]
+fn into_items_restores_input_order() {
+    let points: Vec<_> = (0..100).map(|x| Point(((x * 37) % 100) as f32)).collect();
+    for &leaf_size in &[1, 16] {
+        let vp = VPTree::builder().leaf_size(leaf_size).build(points.clone());
+        assert_eq!(vp.into_items(), points);
+    }
+
+    let empty: VPTree<f32, Point> = VPTree::default();
+    assert!(empty.into_items().is_empty());
+}
+
+#[test]
+fn into_sorted_by_distance() {
+    let points: Vec<_> = (0..10).map(|x| Point(x as f32)).collect();
+    let vp = VPTree::new(points);
+    let sorted = vp.into_sorted_by_distance_from(&Point(6.2));
+    assert_eq!(&sorted[..4], &[Point(6.0), Point(7.0), Point(5.0), Point(8.0)]);
+    assert_eq!(sorted.len(), 10);
+}
+
+#[test]
+fn iterate_items() {
+    let points: Vec<_> = (0..100).map(|x| Point(x as f32)).collect();
+    let vp = VPTree::builder().leaf_size(4).build(points.clone());
+
+    assert_eq!(vp.iter().len(), 100);
+    let mut seen: Vec<f32> = vp.iter().map(|p| p.0).collect();
+    seen.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    assert_eq!(seen, points.iter().map(|p| p.0).collect::<Vec<_>>());
+
+    let mut total = 0.0;
+    for p in &vp {
+        total += p.0;
+    }
+    assert_eq!(total, 4950.0);
+
+    let empty: VPTree<f32, Point> = VPTree::default();
+    assert_eq!(empty.iter().next(), None);
+}
+
+#[test]
+fn collect_into_tree() {
+    let vp: VPTree<f32, Point> = (0..100).map(|x| Point(x as f32)).collect();
+    assert_eq!(vp.len(), 100);
+    assert_eq!(vp.nearest_neighbor(&Point(41.3)), Some(&Point(41.0)));
+
+    // Sources without an exact size work too.
+    let evens: VPTree<f32, Point> = (0..100).filter(|x| x % 2 == 0).map(|x| Point(x as f32)).collect();
+    assert_eq!(evens.len(), 50);
+    assert_eq!(evens.into_items()[3], Point(6.0));
+
+    let empty: VPTree<f32, Point> = Vec::new().into_iter().collect();
+    assert!(empty.is_empty());
+}
+
+#[test]
+fn split_into_shards() {
+    let vp = VPTree::new((0..103).map(|x| Point(x as f32)).collect());
+    let shards = vp.split_into(4);
+    assert_eq!(shards.iter().map(|s| s.len()).collect::<Vec<_>>(), vec![26, 26, 26, 25]);
+
+    // Every item ends up in exactly one shard, and the nearest
+    // neighbor over all shards is the true nearest neighbor.
+    let mut all: Vec<Point> = Vec::new();
+    for s in &shards {
+        all.extend(s.iter().cloned());
+    }
+    all.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+    assert_eq!(all, (0..103).map(|x| Point(x as f32)).collect::<Vec<_>>());
+
+    let q = Point(57.3);
+    let best = shards.iter().filter_map(|s| s.nearest_neighbor(&q)).min_by(|a, b| {
+        (a.0 - q.0).abs().partial_cmp(&(b.0 - q.0).abs()).unwrap()
+    });
+    assert_eq!(best, Some(&Point(57.0)));
+
+    let shards = VPTree::new(vec![Point(1.0), Point(2.0)]).split_into(3);
+    assert_eq!(shards.iter().map(|s| s.len()).collect::<Vec<_>>(), vec![1, 1, 0]);
+}
@@ -9,7 +9,7 @@ struct Point {
 }
 impl Point {
     fn new(x: f32, y: f32) -> Self {
-        Point { x: x, y: y }
+        Point { x, y }
     }
 }
 
@@ -41,7 +41,7 @@ fn lattice_points(n: usize) -> Vec<Point> {
 fn lattice_vpn() {
     let points: Vec<Point> = lattice_points(20);
 
-    let tree = VPTree::new(points).unwrap();
+    let tree = VPTree::new(points);
 
     let ps = tree.nearest_neighbors(&Point::new(4.46, 4.4), 4, true);
     assert_eq!(ps.len(), 4);
@@ -57,3 +57,23 @@ fn lattice_vpn() {
     assert_eq!(ps[3].x, 5.0);
     assert_eq!(ps[3].y, 5.0);
 }
+
+#[test]
+fn lattice_pairs_within() {
+    let points: Vec<Point> = lattice_points(12);
+    let mut expected = Vec::new();
+    for i in 0..points.len() {
+        for j in i+1..points.len() {
+            if points[i].distance(&points[j]) < 1.5 {
+                expected.push((i, j));
+            }
+        }
+    }
+
+    let tree = VPTree::new(points);
+    let mut pairs: Vec<(usize, usize)> = tree.pairs_within(1.5).into_iter().map(|(i, j, _)| (i, j)).collect();
+    pairs.sort();
+
+    assert_eq!(pairs, expected);
+    assert!(tree.pairs_within(0.5).is_empty());
+}
@@ -7,7 +7,7 @@ struct Point(f32);
 
 impl MetricItem<f32> for Point {
     fn distance(&self, a: &Self) -> f32 {
-        return (self.0 - a.0).abs()
+        (self.0 - a.0).abs()
     }
 }
 
@@ -18,20 +18,46 @@ fn test_linear() {
     for n in 10..101 {
         let points: Vec<_> = (0..n+1).map(|x| Point(x as f32/ n as f32)).collect();
 
-        let vp = VPTree::new(points.iter().cloned().collect());
-        assert!(vp.is_some());
-        let vp = vp.unwrap();
+        let vp = VPTree::new(points.to_vec());
 
         for p in &points {
             // each point should be nearest to itself.
-            if vp.nearest_neighbor(p) != p {
+            if vp.nearest_neighbor(p) != Some(p) {
                 print!("\n{}", vp.dump());
                 done = true;
             }
-            assert_eq!(vp.nearest_neighbor(p), p);
+            assert_eq!(vp.nearest_neighbor(p), Some(p));
          }
         if done {
             break;
         }
     }
 }
+
+#[test]
+fn test_kth_nearest_distance() {
+    let points: Vec<_> = (0..50).map(|x| Point(x as f32)).collect();
+    let vp = VPTree::new(points);
+
+    assert_eq!(vp.kth_nearest_distance(&Point(10.25), 1), Some(0.25));
+    assert_eq!(vp.kth_nearest_distance(&Point(10.25), 2), Some(0.75));
+    assert_eq!(vp.kth_nearest_distance(&Point(10.25), 5), Some(2.25));
+    assert_eq!(vp.kth_nearest_distance(&Point(0.0), 50), Some(49.0));
+    assert_eq!(vp.kth_nearest_distance(&Point(0.0), 51), None);
+    assert_eq!(vp.kth_nearest_distance(&Point(0.0), 0), None);
+}
+
+#[test]
+fn test_knn_graph() {
+    let points: Vec<_> = (0..30).map(|x| Point((x * x) as f32)).collect();
+    let vp = VPTree::new(points);
+
+    let graph = vp.knn_graph(2);
+    assert_eq!(graph.len(), 30);
+    assert_eq!(graph[0], vec![(1, 1.0), (2, 4.0)]);
+    assert_eq!(graph[10], vec![(9, 19.0), (11, 21.0)]);
+    assert_eq!(graph[29], vec![(28, 57.0), (27, 112.0)]);
+
+    let graph = vp.knn_graph(0);
+    assert!(graph.iter().all(|v| v.is_empty()));
+}
@@ -0,0 +1,95 @@
+extern crate vptree;
+
+use vptree::{BuildError, MetricItem, VPMap, VPTree};
+
+#[derive(Debug, PartialEq, Clone)]
+struct Point(f32);
+
+impl MetricItem<f32> for Point {
+    fn distance(&self, a: &Self) -> f32 {
+        (self.0 - a.0).abs()
+    }
+}
+
+#[test]
+fn values_follow_keys() {
+    let entries: Vec<_> = (0..100).map(|x| (Point(((x * 37) % 100) as f32), format!("v{}", (x * 37) % 100))).collect();
+    let mut map = VPMap::try_with_builder(&VPTree::builder().leaf_size(4), entries).unwrap();
+    assert_eq!(map.len(), 100);
+
+    for x in 0..100 {
+        let (k, v) = map.nearest_neighbor(&Point(x as f32 + 0.2)).unwrap();
+        assert_eq!(k, &Point(x as f32));
+        assert_eq!(v, &format!("v{}", x));
+    }
+
+    let near: Vec<&str> = map.within_radius(&Point(50.1), 1.5, true).into_iter().map(|(_, v)| v.as_str()).collect();
+    assert_eq!(near, vec!["v50", "v51", "v49"]);
+    let near: Vec<&str> = map.nearest_neighbors(&Point(-5.0), 2, true).into_iter().map(|(_, v)| v.as_str()).collect();
+    assert_eq!(near, vec!["v0", "v1"]);
+
+    for x in 100..150 {
+        map.insert(Point(x as f32), format!("v{}", x));
+    }
+    assert_eq!(map.nearest_neighbor(&Point(140.2)).map(|(_, v)| v.as_str()), Some("v140"));
+    assert!(map.iter().all(|(k, v)| *v == format!("v{}", k.0)));
+    assert_eq!(map.iter().count(), 150);
+}
+
+#[test]
+fn mutate_values_in_place() {
+    let mut map = VPMap::new((0..50).map(|x| (Point(x as f32), x)).collect());
+
+    *map.get_mut(&Point(7.0)).unwrap() += 100;
+    assert_eq!(map.get(&Point(7.0)), Some(&107));
+    assert_eq!(map.get_mut(&Point(7.5)), None);
+
+    {
+        let (k, v) = map.nearest_neighbor_mut(&Point(20.3)).unwrap();
+        *v = k.0 as i32 * 2;
+    }
+    assert_eq!(map.get(&Point(20.0)), Some(&40));
+
+    for v in map.values_mut() {
+        *v += 1;
+    }
+    for (k, v) in map.iter_mut() {
+        if k.0 == 3.0 {
+            *v = 0;
+        }
+    }
+    assert_eq!(map.get(&Point(3.0)), Some(&0));
+    assert_eq!(map.get(&Point(4.0)), Some(&5));
+    assert_eq!(map.get(&Point(7.0)), Some(&108));
+    assert_eq!(map.nearest_neighbors(&Point(20.0), 1, false), vec![(&Point(20.0), &41)]);
+}
+
+#[test]
+fn duplicate_keys_keep_their_values() {
+    let entries = vec![(Point(1.0), "a"), (Point(1.0), "b"), (Point(2.0), "c"), (Point(3.0), "d")];
+    match VPMap::try_with_builder(&VPTree::builder().collapse_duplicates(true), entries.clone()) {
+        Err(BuildError::InvalidParameter(_)) => {},
+        _ => panic!("maps should not collapse duplicate keys")
+    }
+
+    let mut map = VPMap::new(entries);
+    assert_eq!(map.len(), 4);
+    assert_eq!(map.get(&Point(2.0)), Some(&"c"));
+    assert_eq!(map.get(&Point(3.0)), Some(&"d"));
+
+    map.insert(Point(1.0), "dup");
+    map.insert(Point(5.0), "five");
+    assert_eq!(map.len(), 6);
+    assert_eq!(map.get(&Point(5.0)), Some(&"five"));
+    let mut ones: Vec<&str> = map.within_radius(&Point(1.0), 0.5, false).into_iter().map(|(_, v)| *v).collect();
+    ones.sort();
+    assert_eq!(ones, vec!["a", "b", "dup"]);
+}
+
+#[test]
+fn empty_maps_check_options() {
+    let err = VPMap::<f32, Point, ()>::try_with_builder(&VPTree::builder().leaf_size(0), Vec::new()).err();
+    assert_eq!(err, Some(BuildError::InvalidParameter("leaf size must be positive")));
+    let map = VPMap::<f32, Point, ()>::try_with_builder(&VPTree::builder(), Vec::new()).unwrap();
+    assert!(map.is_empty());
+}
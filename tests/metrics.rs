@@ -0,0 +1,239 @@
+extern crate rand;
+extern crate vptree;
+
+use rand::{Rng, SeedableRng, XorShiftRng};
+
+use vptree::{MetricItem, VPTree};
+use vptree::metrics::{Angular, Capped, Chebyshev, Euclidean, MahalanobisBuilder, Manhattan, MaxMetric, Minkowski, PeriodicBuilder,
+                      Scaled, Sqrt, SumMetric, WeightedEuclideanBuilder};
+
+#[test]
+fn distances() {
+    let (a, b) = ([1.0, 2.0, 3.0], [4.0, -2.0, 3.0]);
+    assert_eq!(Euclidean(a).distance(&Euclidean(b)), 5.0);
+    assert_eq!(Manhattan(a).distance(&Manhattan(b)), 7.0);
+    assert_eq!(Chebyshev(a).distance(&Chebyshev(b)), 4.0);
+
+    let (a, b): (Vec<f32>, Vec<f32>) = (vec![0.5], vec![-0.5]);
+    assert_eq!(Euclidean(&a[..]).distance(&Euclidean(&b[..])), 1.0);
+}
+
+#[test]
+fn f32_euclidean() {
+    let mut rng = XorShiftRng::from_seed([5, 6, 7, 8]);
+    for n in 0..40 {
+        let a: Vec<f32> = (0..n).map(|_| rng.gen_range(-1.0, 1.0)).collect();
+        let b: Vec<f32> = (0..n).map(|_| rng.gen_range(-1.0, 1.0)).collect();
+        let expected = a.iter().zip(&b).map(|(&x, &y)| (x as f64 - y as f64).powi(2)).sum::<f64>().sqrt();
+        let d = Euclidean(&a[..]).distance(&Euclidean(&b[..]));
+        assert!((d as f64 - expected).abs() <= 1e-5 * (1.0 + expected), "{} vs {} for length {}", d, expected, n);
+    }
+    assert_eq!([3.0f32, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 4.0].distance(&[0.0; 9]), 5.0);
+}
+
+/// Check that the nearest neighbors found match a brute-force scan.
+fn check_nearest<T: MetricItem<f64> + Clone>(points: Vec<T>, queries: &[T]) {
+    let vp = VPTree::builder().leaf_size(4).seed(7).build(points.clone());
+    for q in queries {
+        let mut expected: Vec<f64> = points.iter().map(|p| p.distance(q)).collect();
+        expected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let found: Vec<f64> = vp.nearest_neighbors(q, 5, true).iter().map(|p| p.distance(q)).collect();
+        assert_eq!(found, &expected[..5]);
+    }
+}
+
+#[test]
+fn trees_over_each_metric() {
+    let mut rng = XorShiftRng::from_seed([9, 8, 7, 6]);
+    let coords: Vec<Vec<f64>> = (0..300).map(|_| (0..4).map(|_| rng.gen_range(-1.0, 1.0)).collect()).collect();
+    let (points, queries) = coords.split_at(250);
+
+    check_nearest(points.iter().cloned().map(Euclidean).collect(),
+                  &queries.iter().cloned().map(Euclidean).collect::<Vec<_>>());
+    check_nearest(points.iter().cloned().map(Manhattan).collect(),
+                  &queries.iter().cloned().map(Manhattan).collect::<Vec<_>>());
+    check_nearest(points.iter().cloned().map(Chebyshev).collect(),
+                  &queries.iter().cloned().map(Chebyshev).collect::<Vec<_>>());
+}
+
+#[test]
+fn minkowski_family() {
+    let (a, b) = ([1.0, 2.0, 3.0], [4.0, -2.0, 3.0]);
+    assert_eq!(Minkowski::<_, 1>(a).distance(&Minkowski(b)), Manhattan(a).distance(&Manhattan(b)));
+    assert_eq!(Minkowski::<_, 2>(a).distance(&Minkowski(b)), Euclidean(a).distance(&Euclidean(b)));
+
+    let d: f64 = Minkowski::<_, 3>(a).distance(&Minkowski(b));
+    assert!((d - 91f64.cbrt()).abs() < 1e-12);
+    let d: f64 = Minkowski::<_, 64>(a).distance(&Minkowski(b));
+    assert!((d - Chebyshev(a).distance(&Chebyshev(b))).abs() < 0.05);
+
+    let mut rng = XorShiftRng::from_seed([4, 3, 2, 1]);
+    let coords: Vec<Vec<f64>> = (0..300).map(|_| (0..3).map(|_| rng.gen_range(-1.0, 1.0)).collect()).collect();
+    let (points, queries) = coords.split_at(250);
+    check_nearest(points.iter().cloned().map(Minkowski::<_, 3>).collect(),
+                  &queries.iter().cloned().map(Minkowski::<_, 3>).collect::<Vec<_>>());
+}
+
+#[test]
+fn angular() {
+    use std::f64::consts::{FRAC_PI_2, PI};
+
+    assert!(Angular::new(vec![0.0, 0.0]).is_none());
+    assert!(Angular::new([f32::NAN, 1.0]).is_none());
+
+    let x = Angular::new([3.0, 0.0]).unwrap();
+    assert_eq!(x.coords(), &[1.0, 0.0]);
+    assert_eq!(x.distance(&x), 0.0);
+    assert!((x.distance(&Angular::new([0.0, 0.5]).unwrap()) - FRAC_PI_2).abs() < 1e-12);
+    assert!((x.distance(&Angular::new([-1.0, 0.0]).unwrap()) - PI).abs() < 1e-12);
+
+    let mut rng = XorShiftRng::from_seed([3, 1, 4, 1]);
+    let mut direction = || Angular::new((0..5).map(|_| rng.gen_range(-1.0, 1.0)).collect::<Vec<f64>>()).unwrap();
+    let points: Vec<_> = (0..250).map(|_| direction()).collect();
+    let queries: Vec<_> = (0..50).map(|_| direction()).collect();
+    check_nearest(points, &queries);
+}
+
+#[test]
+fn mahalanobis() {
+    assert!(MahalanobisBuilder::from_covariance([[1.0, 2.0], [2.0, 1.0]]).is_err());
+    assert!(MahalanobisBuilder::from_cholesky([[1.0, 0.0], [0.5, 0.0]]).is_err());
+
+    // Build the covariance from a known factor, and check both ways of
+    // describing it agree with the inverse covariance.
+    let l: [[f64; 3]; 3] = [[2.0, 0.0, 0.0], [1.0, 1.0, 0.0], [-0.5, 0.5, 3.0]];
+    let mut cov = [[0.0; 3]; 3];
+    for i in 0..3 {
+        for j in 0..3 {
+            cov[i][j] = (0..3).map(|k| l[i][k] * l[j][k]).sum();
+        }
+    }
+    let from_cov = MahalanobisBuilder::from_covariance(cov).unwrap();
+    let from_l = MahalanobisBuilder::from_cholesky(l).unwrap();
+    for (a, b) in from_cov.cholesky().iter().flatten().zip(l.iter().flatten()) {
+        assert!((a - b).abs() < 1e-12);
+    }
+
+    // For a single axis of the factor, L e = x gives distance |e|.
+    let (x, y) = (from_l.point([2.0, 1.0, -0.5]), from_l.point([0.0, 0.0, 0.0]));
+    assert!((x.distance(&y) - 1.0).abs() < 1e-12);
+    assert_eq!(x.coords(), &[2.0, 1.0, -0.5]);
+
+    let mut rng = XorShiftRng::from_seed([2, 7, 1, 8]);
+    let mut point = || from_cov.point([rng.gen_range(-5.0, 5.0), rng.gen_range(-5.0, 5.0), rng.gen_range(-5.0, 5.0)]);
+    let points: Vec<_> = (0..250).map(|_| point()).collect();
+    let queries: Vec<_> = (0..50).map(|_| point()).collect();
+    check_nearest(points, &queries);
+}
+
+#[test]
+fn periodic() {
+    assert!(PeriodicBuilder::new([1.0, 0.0]).is_err());
+    assert!(PeriodicBuilder::new([1.0, f64::INFINITY]).is_err());
+
+    let space = PeriodicBuilder::new([10.0, 4.0]).unwrap();
+    assert_eq!(space.point([-1.0, 9.0]).coords(), &[9.0, 1.0]);
+    assert_eq!(space.point([0.5, 0.5]).distance(&space.point([9.5, 3.5])), 2f64.sqrt());
+    assert_eq!(space.point([2.0, 0.0]).distance(&space.point([7.0, 2.0])), 29f64.sqrt());
+
+    // Brute force over the nearby periodic images agrees.
+    let mut rng = XorShiftRng::from_seed([1, 4, 1, 4]);
+    let mut point = || space.point([rng.gen_range(0.0, 10.0), rng.gen_range(0.0, 4.0)]);
+    let points: Vec<_> = (0..250).map(|_| point()).collect();
+    let queries: Vec<_> = (0..50).map(|_| point()).collect();
+    for (p, q) in points.iter().zip(&queries) {
+        let (p, q) = (p.coords(), q.coords());
+        let images = (-1..2).flat_map(|i| (-1..2).map(move |j| (i as f64 * 10.0, j as f64 * 4.0)));
+        let expected = images.map(|(dx, dy)| ((p[0] - q[0] + dx).powi(2) + (p[1] - q[1] + dy).powi(2)).sqrt())
+            .fold(f64::INFINITY, f64::min);
+        let d = space.point(*p).distance(&space.point(*q));
+        assert!((d - expected).abs() < 1e-12);
+    }
+    check_nearest(points, &queries);
+}
+
+#[test]
+fn weighted_euclidean() {
+    assert!(WeightedEuclideanBuilder::new([1.0, -1.0]).is_err());
+    assert!(WeightedEuclideanBuilder::new([f64::NAN, 1.0]).is_err());
+
+    let space = WeightedEuclideanBuilder::new([9.0, 0.25, 0.0]).unwrap();
+    let (a, b) = (space.point([1.0, 2.0, 3.0]), space.point([2.0, 6.0, -3.0]));
+    assert_eq!(a.coords(), &[1.0, 2.0, 3.0]);
+    assert_eq!(a.distance(&b), 13f64.sqrt());
+
+    let mut rng = XorShiftRng::from_seed([5, 5, 5, 5]);
+    let mut point = || space.point([rng.gen_range(0.0, 1.0), rng.gen_range(0.0, 10.0), rng.gen_range(0.0, 1.0)]);
+    let points: Vec<_> = (0..250).map(|_| point()).collect();
+    let queries: Vec<_> = (0..50).map(|_| point()).collect();
+    check_nearest(points, &queries);
+}
+
+#[test]
+fn combinators() {
+    let (a, b) = (([0.0, 0.0], [1.0]), ([3.0, 4.0], [-1.0]));
+    let sum = |p: ([f64; 2], [f64; 1])| SumMetric(Euclidean(p.0), Scaled::new(Manhattan(p.1), 2.0));
+    let max = |p: ([f64; 2], [f64; 1])| MaxMetric(Euclidean(p.0), Scaled::new(Manhattan(p.1), 3.0));
+    assert_eq!(sum(a).distance(&sum(b)), 9.0);
+    assert_eq!(max(a).distance(&max(b)), 6.0);
+    assert_eq!(sum(a).1.factor(), 2.0);
+
+    let mut rng = XorShiftRng::from_seed([8, 6, 7, 5]);
+    let records: Vec<([f64; 2], [f64; 1])> = (0..300).map(|_| {
+        ([rng.gen_range(0.0, 1.0), rng.gen_range(0.0, 1.0)], [rng.gen_range(0.0, 100.0)])
+    }).collect();
+    let (points, queries) = records.split_at(250);
+    check_nearest(points.iter().cloned().map(sum).collect(), &queries.iter().cloned().map(sum).collect::<Vec<_>>());
+    check_nearest(points.iter().cloned().map(max).collect(), &queries.iter().cloned().map(max).collect::<Vec<_>>());
+}
+
+#[test]
+#[should_panic(expected = "scale factors must be positive and finite")]
+fn zero_scale() {
+    Scaled::new(Euclidean([1.0]), 0.0);
+}
+
+/// The squared Euclidean distance, which is not a metric.
+#[derive(Debug, Clone)]
+struct Squared([f64; 2]);
+
+impl MetricItem<f64> for Squared {
+    fn distance(&self, b: &Self) -> f64 {
+        (self.0[0] - b.0[0]).powi(2) + (self.0[1] - b.0[1]).powi(2)
+    }
+}
+
+#[test]
+fn transforms() {
+    assert_eq!(Sqrt(Squared([0.0, 0.0])).distance(&Sqrt(Squared([3.0, 4.0]))), 5.0);
+    let capped = |x: f64| Capped::new(Manhattan([x]), 2.5);
+    assert_eq!(capped(0.0).distance(&capped(1.0)), 1.0);
+    assert_eq!(capped(0.0).distance(&capped(10.0)), 2.5);
+    assert_eq!(capped(0.0).cap(), 2.5);
+
+    let mut rng = XorShiftRng::from_seed([9, 9, 9, 9]);
+    let coords: Vec<[f64; 2]> = (0..300).map(|_| [rng.gen_range(0.0, 10.0), rng.gen_range(0.0, 10.0)]).collect();
+    let (points, queries) = coords.split_at(250);
+    check_nearest(points.iter().map(|&p| Sqrt(Squared(p))).collect(),
+                  &queries.iter().map(|&p| Sqrt(Squared(p))).collect::<Vec<_>>());
+
+    let capped = |p: &[f64; 2]| Capped::new(Euclidean(*p), 3.0);
+    let vp = VPTree::new(points.iter().map(capped).collect());
+    for q in queries.iter().map(capped) {
+        let n = points.iter().filter(|p| Euclidean(**p).distance(q.item()) < 2.0).count();
+        assert_eq!(vp.within_radius(&q, 2.0, false).len(), n);
+    }
+}
+
+#[test]
+fn arrays_and_tuples() {
+    assert_eq!([0.0f32, 3.0].distance(&[4.0, 0.0]), 5.0);
+    assert_eq!((1.0, 1.0).distance(&(4.0, 5.0)), 5.0);
+    assert_eq!((0.0f32, 0.0, 0.0).distance(&(2.0, 3.0, 6.0)), 7.0);
+    assert_eq!((1.0, 1.0, 1.0, 1.0).distance(&(0.0, 0.0, 0.0, 0.0)), 2.0);
+
+    let tree = VPTree::new((0..10).map(|x| [x as f64, 0.0]).collect());
+    assert_eq!(tree.nearest_neighbor(&[3.4, 1.0]), Some(&[3.0, 0.0]));
+    let tree = VPTree::new((0..10).map(|x| (x as f32, 0.0)).collect());
+    assert_eq!(tree.nearest_neighbor(&(6.6, -1.0)), Some(&(7.0, 0.0)));
+}
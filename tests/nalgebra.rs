@@ -0,0 +1,45 @@
+#![cfg(feature = "nalgebra")]
+
+extern crate nalgebra;
+extern crate rand;
+extern crate vptree;
+
+use nalgebra::{DVector, Point3, SVector};
+use rand::{Rng, SeedableRng, XorShiftRng};
+
+use vptree::{MetricItem, VPTree};
+
+#[test]
+fn distances() {
+    let (a, b) = (SVector::<f64, 2>::new(0.0, 0.0), SVector::<f64, 2>::new(3.0, 4.0));
+    assert_eq!(a.distance(&b), 5.0);
+    let (a, b) = (DVector::<f32>::from_vec(vec![1.0, 1.0]), DVector::<f32>::from_vec(vec![4.0, 5.0]));
+    assert_eq!(a.distance(&b), 5.0);
+    assert_eq!(Point3::new(1.0, 2.0, 3.0).distance(&Point3::new(1.0, 2.0, 3.0)), 0.0);
+    assert_eq!(Point3::new(0.0, 0.0, 0.0).distance(&Point3::new(2.0, 3.0, 6.0)), 7.0);
+}
+
+#[test]
+fn trees_over_vectors_and_points() {
+    let mut rng = XorShiftRng::from_seed([2, 4, 6, 8]);
+    let points: Vec<Point3<f64>> = (0..300)
+        .map(|_| Point3::new(rng.gen_range(-1.0, 1.0), rng.gen_range(-1.0, 1.0), rng.gen_range(-1.0, 1.0)))
+        .collect();
+    let vectors: Vec<DVector<f64>> = points.iter().map(|p| DVector::from_column_slice(p.coords.as_slice())).collect();
+    let point_tree = VPTree::builder().leaf_size(4).seed(7).build(points.clone());
+    let vector_tree = VPTree::builder().leaf_size(4).seed(7).build(vectors);
+
+    for _ in 0..20 {
+        let q = Point3::new(rng.gen_range(-1.0, 1.0), rng.gen_range(-1.0, 1.0), rng.gen_range(-1.0, 1.0));
+        let mut expected = points.clone();
+        expected.sort_by(|a, b| a.distance(&q).partial_cmp(&b.distance(&q)).unwrap());
+
+        let found: Vec<Point3<f64>> = point_tree.nearest_neighbors(&q, 5, true).into_iter().cloned().collect();
+        assert_eq!(found, &expected[..5]);
+        let dq = DVector::from_column_slice(q.coords.as_slice());
+        let found: Vec<Point3<f64>> = vector_tree.nearest_neighbors(&dq, 5, true).into_iter()
+            .map(|v| Point3::new(v[0], v[1], v[2]))
+            .collect();
+        assert_eq!(found, &expected[..5]);
+    }
+}
@@ -0,0 +1,55 @@
+#![cfg(feature = "ordered-float")]
+
+extern crate ordered_float;
+extern crate vptree;
+
+use ordered_float::{NotNan, OrderedFloat};
+use vptree::{Distance, MetricItem, VPTree};
+use vptree::metrics::{Euclidean, Ordered};
+
+#[derive(Debug, PartialEq, Clone)]
+struct Point(f64);
+
+impl MetricItem<NotNan<f64>> for Point {
+    fn distance(&self, a: &Self) -> NotNan<f64> {
+        NotNan::new((self.0 - a.0).abs()).unwrap()
+    }
+}
+
+#[test]
+fn not_nan_distances() {
+    let tree = VPTree::new((0..200).map(|x| Point(x as f64 / 2.0)).collect());
+    assert_eq!(tree.nearest_neighbors(&Point(10.1), 3, true), vec![&Point(10.0), &Point(10.5), &Point(9.5)]);
+    assert_eq!(tree.within_radius(&Point(-1.0), NotNan::new(1.6).unwrap(), true), vec![&Point(0.0), &Point(0.5)]);
+    assert_eq!(tree.kth_nearest_distance(&Point(0.0), 3), Some(NotNan::new(1.0).unwrap()));
+}
+
+#[test]
+fn nan_distances_are_furthest() {
+    let mut points: Vec<_> = (0..100).map(|x| Ordered(Euclidean(vec![x as f64, 0.0]))).collect();
+    points.push(Ordered(Euclidean(vec![f64::NAN, 0.0])));
+    let tree = VPTree::new(points);
+
+    let query = Ordered(Euclidean(vec![50.2, 0.0]));
+    let nearest = tree.nearest_neighbors(&query, 101, true);
+    assert_eq!(nearest[0].0, Euclidean(vec![50.0, 0.0]));
+    assert!(nearest[100].0 .0[0].is_nan());
+    assert_eq!(tree.within_radius(&query, OrderedFloat(1.0), false).len(), 2);
+    assert_eq!(tree.within_radius(&query, OrderedFloat::infinity(), false).len(), 100);
+
+    // A NaN query is equally far from everything.
+    let nan = Ordered(Euclidean(vec![f64::NAN, f64::NAN]));
+    assert_eq!(tree.nearest_neighbors(&nan, 5, true).len(), 5);
+    assert!(tree.within_radius(&nan, OrderedFloat(1e9), true).is_empty());
+}
+
+#[test]
+fn saturating_bounds() {
+    let inf = OrderedFloat::<f32>::infinity();
+    assert_eq!(inf.saturating_sub(inf), OrderedFloat(f32::NEG_INFINITY));
+    assert_eq!(OrderedFloat(f32::NAN).saturating_add(OrderedFloat(1.0)), inf);
+
+    let inf = NotNan::<f64>::infinity();
+    assert_eq!(inf.saturating_sub(inf).into_inner(), f64::NEG_INFINITY);
+    assert_eq!(inf.abs_diff(NotNan::new(3.0).unwrap()), inf);
+}
@@ -0,0 +1,55 @@
+extern crate vptree;
+
+use vptree::{MetricItem, PersistentVPTree, VPTree};
+
+#[derive(Debug, PartialEq, Clone)]
+struct Point(f32);
+
+impl MetricItem<f32> for Point {
+    fn distance(&self, a: &Self) -> f32 {
+        (self.0 - a.0).abs()
+    }
+}
+
+#[test]
+fn versions_are_independent() {
+    let mut tree = PersistentVPTree::from(VPTree::builder().leaf_size(4).build((0..100).map(|x| Point(x as f32 * 2.0)).collect()));
+    let mut versions = vec![tree.clone()];
+    for x in 0..100 {
+        tree.insert(Point(x as f32 * 2.0 + 1.0));
+        if x % 25 == 24 {
+            versions.push(tree.clone());
+        }
+    }
+
+    assert_eq!(tree.len(), 200);
+    for x in 0..200 {
+        assert_eq!(tree.nearest_neighbor(&Point(x as f32 + 0.2)), Some(&Point(x as f32)));
+    }
+
+    // Each snapshot only has the odd points inserted before it was taken.
+    for (i, v) in versions.iter().enumerate() {
+        assert_eq!(v.len(), 100 + 25 * i);
+        assert_eq!(v.within_radius(&Point(1.0), 0.5, false).len(), (i > 0) as usize);
+        assert_eq!(v.nearest_neighbors(&Point(1000.0), 1, false), vec![&Point(198.0 + (i == 4) as usize as f32)]);
+    }
+
+    assert_eq!(tree.within_radius(&Point(10.1), 2.0, true), vec![&Point(10.0), &Point(11.0), &Point(9.0), &Point(12.0)]);
+}
+
+#[test]
+fn deep_persistent_tree() {
+    let mut tree = PersistentVPTree::new(Vec::new());
+    assert!(tree.is_empty());
+    assert_eq!(tree.nearest_neighbor(&Point(0.0)), None);
+
+    // Identical points make a chain as deep as the tree is large.
+    let chain = VPTree::builder().leaf_size(1).median_sample_size(1).build(vec![Point(1.0); 10000]);
+    let chain = PersistentVPTree::from(chain);
+    let copy = chain.clone();
+    drop(chain);
+    assert_eq!(copy.nearest_neighbors(&Point(0.0), 3, false).len(), 3);
+
+    tree.insert(Point(3.0));
+    assert_eq!(tree.nearest_neighbor(&Point(0.0)), Some(&Point(3.0)));
+}
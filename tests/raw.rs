@@ -0,0 +1,96 @@
+#![cfg(feature = "raw")]
+
+extern crate vptree;
+
+use vptree::{MetricItem, VPTree};
+use vptree::raw::{item_bound, RawTree};
+
+#[derive(Debug, PartialEq, Clone)]
+struct Point(f32, f32);
+
+impl MetricItem<f32> for Point {
+    fn distance(&self, a: &Self) -> f32 {
+        ((self.0 - a.0).powi(2) + (self.1 - a.1).powi(2)).sqrt()
+    }
+}
+
+fn points() -> Vec<Point> {
+    (0..500).map(|i| Point((i % 23) as f32 * 1.3, (i / 23) as f32 * 0.7 + (i % 5) as f32 * 0.01)).collect()
+}
+
+/// Exact nearest neighbor search through the raw API, pruning with
+/// its bounds.
+fn nearest<'a>(tree: RawTree<'a, f32, Point>, q: &Point) -> Option<(f32, &'a Point)> {
+    let mut best: Option<(f32, &Point)> = None;
+    let mut stack = tree.root().map(|r| (r, 0.0)).into_iter().collect::<Vec<_>>();
+    while let Some((node, bound)) = stack.pop() {
+        if best.is_some_and(|(b, _)| bound >= b) {
+            continue;
+        }
+        let d_center = q.distance(node.center().item);
+        for item in node.items() {
+            let lower = if item.index == node.center().index { d_center } else { item_bound(item.dist, d_center) };
+            if best.is_some_and(|(b, _)| lower >= b) {
+                continue;
+            }
+            let d = q.distance(item.item);
+            if best.is_none_or(|(b, _)| d < b) {
+                best = Some((d, item.item));
+            }
+        }
+        if let Some(split) = node.split() {
+            let (inner, outer) = split.bounds(d_center);
+            let inner_node = tree.node(split.inner);
+            stack.push((inner_node, inner.max(inner_node.parent_bound(d_center))));
+            if let Some(o) = split.outer {
+                let outer_node = tree.node(o);
+                stack.push((outer_node, outer.max(outer_node.parent_bound(d_center))));
+            }
+        }
+    }
+    best
+}
+
+#[test]
+fn raw_structure() {
+    let empty: VPTree<f32, Point> = VPTree::new(Vec::new());
+    assert!(empty.raw().root().is_none());
+    assert_eq!(empty.raw().node_count(), 0);
+
+    let tree = VPTree::builder().leaf_size(4).build(points());
+    let raw = tree.raw();
+    assert_eq!(raw.root().unwrap().size(), 500);
+    assert_eq!(raw.nodes().map(|n| n.items().count()).sum::<usize>(), 500);
+    assert!(raw.get(raw.node_count()).is_none());
+
+    for node in raw.nodes() {
+        let center = node.center().item;
+        for item in node.bucket() {
+            assert_eq!(item.dist, center.distance(item.item));
+            assert_eq!(item.count, 1);
+        }
+        if let Some(split) = node.split() {
+            assert!(node.bucket().next().is_none());
+            let inner = raw.node(split.inner);
+            assert!(inner.id() > node.id());
+            let d = center.distance(inner.center().item);
+            assert_eq!(inner.center().dist, d);
+            assert!(split.inner_min <= d && d <= split.mu);
+            if let Some(o) = split.outer {
+                let d = center.distance(raw.node(o).center().item);
+                assert!(split.outer_min <= d && d <= split.outer_max);
+                assert_eq!(node.size(), 1 + inner.size() + raw.node(o).size());
+            }
+        }
+    }
+}
+
+#[test]
+fn raw_search() {
+    let tree = VPTree::builder().leaf_size(4).build(points());
+    for i in 0..50 {
+        let q = Point(i as f32 * 0.61, i as f32 * 0.37);
+        let (d, _) = nearest(tree.raw(), &q).unwrap();
+        assert_eq!(d, q.distance(tree.nearest_neighbor(&q).unwrap()));
+    }
+}
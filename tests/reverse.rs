@@ -0,0 +1,39 @@
+extern crate vptree;
+
+use vptree::{MetricItem, VPTree};
+
+#[derive(Debug, PartialEq, Clone)]
+struct Point(f32);
+
+impl MetricItem<f32> for Point {
+    fn distance(&self, a: &Self) -> f32 {
+        (self.0 - a.0).abs()
+    }
+}
+
+/// Reverse k-nearest neighbors by exhaustive search.
+fn brute_force_rnn(points: &[Point], query: &Point, k: usize) -> Vec<Point> {
+    points.iter().filter(|p| {
+        let mut ds: Vec<f32> = points.iter().map(|x| x.distance(p)).collect();
+        ds.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        query.distance(p) <= ds[k]
+    }).cloned().collect()
+}
+
+#[test]
+fn reverse_matches_brute_force() {
+    let points: Vec<_> = (1..60).map(|x| Point(1.0 / (x as f32))).collect();
+    let vp = VPTree::new(points.clone());
+
+    for &q in &[0.0, 0.02, 0.3, 0.75, 2.0] {
+        for k in 1..4 {
+            let q = Point(q);
+            let mut rnn: Vec<Point> = vp.reverse_nearest_neighbors(&q, k).into_iter().cloned().collect();
+            rnn.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+            let mut expected = brute_force_rnn(&points, &q, k);
+            expected.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+            assert_eq!(rnn, expected);
+        }
+    }
+}
@@ -0,0 +1,48 @@
+extern crate vptree;
+
+use vptree::{MetricItem, QueryScratch, VPTree};
+
+#[derive(Debug, PartialEq, Clone)]
+struct Point(f32);
+
+impl MetricItem<f32> for Point {
+    fn distance(&self, a: &Self) -> f32 {
+        (self.0 - a.0).abs()
+    }
+}
+
+#[test]
+fn scratch_matches_allocating_queries() {
+    let vp = VPTree::builder().leaf_size(4).build((0..300).map(|x| Point(x as f32)).collect());
+    let mut scratch = QueryScratch::new();
+
+    for x in 0..100 {
+        let q = Point(x as f32 * 3.1 + 0.05);
+        let nearest = vp.nearest_neighbors_with(&mut scratch, &q, 5, true).to_vec();
+        assert_eq!(nearest, vp.nearest_neighbors(&q, 5, true));
+
+        let within = vp.within_radius_with(&mut scratch, &q, 7.5, true).to_vec();
+        assert_eq!(within, vp.within_radius(&q, 7.5, true));
+        assert_eq!(vp.within_radius_with(&mut scratch, &q, 7.5, false).len(), within.len());
+    }
+
+    let empty: VPTree<f32, Point> = VPTree::default();
+    let mut scratch = QueryScratch::new();
+    assert!(empty.nearest_neighbors_with(&mut scratch, &Point(0.0), 3, true).is_empty());
+    assert!(empty.within_radius_with(&mut scratch, &Point(0.0), 3.0, true).is_empty());
+}
+
+#[test]
+fn into_caller_buffer() {
+    let vp = VPTree::new((0..100).map(|x| Point(x as f32)).collect());
+    let mut out = vec![&Point(-1.0); 10];
+
+    vp.nearest_neighbors_into(&Point(40.2), 3, true, &mut out);
+    assert_eq!(out, vec![&Point(40.0), &Point(41.0), &Point(39.0)]);
+
+    vp.within_radius_into(&Point(10.2), 1.5, true, &mut out);
+    assert_eq!(out, vec![&Point(10.0), &Point(11.0), &Point(9.0)]);
+
+    vp.within_radius_into(&Point(500.0), 1.0, false, &mut out);
+    assert!(out.is_empty());
+}
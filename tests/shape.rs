@@ -0,0 +1,261 @@
+extern crate vptree;
+
+use std::ops::ControlFlow;
+
+use vptree::{MetricItem, NodeLayout, VPTree, VantageStrategy};
+
+#[derive(Debug, PartialEq, Clone)]
+struct Point(f32);
+
+impl MetricItem<f32> for Point {
+    fn distance(&self, a: &Self) -> f32 {
+        (self.0 - a.0).abs()
+    }
+}
+
+#[test]
+fn shape_accessors() {
+    let empty: VPTree<f32, Point> = VPTree::new(Vec::new());
+    assert_eq!(empty.len(), 0);
+    assert!(empty.is_empty());
+    assert_eq!(empty.depth(), 0);
+    assert_eq!(empty.leaf_count(), 0);
+
+    let single = VPTree::new(vec![Point(1.0)]);
+    assert_eq!(single.len(), 1);
+    assert!(!single.is_empty());
+    assert_eq!(single.depth(), 1);
+    assert_eq!(single.leaf_count(), 1);
+
+    // Splits are balanced, so 2^k - 1 items fill a complete tree.
+    let full = VPTree::builder().leaf_size(1).build((0..127).map(|x| Point(x as f32)).collect());
+    assert_eq!(full.len(), 127);
+    assert_eq!(full.depth(), 7);
+    assert_eq!(full.leaf_count(), 64);
+}
+
+#[test]
+fn tree_stats() {
+    let empty: VPTree<f32, Point> = VPTree::new(Vec::new());
+    assert_eq!(empty.stats().node_count, 0);
+
+    let full = VPTree::builder().leaf_size(1).build((0..127).map(|x| Point(x as f32)).collect());
+    let stats = full.stats();
+    assert_eq!(stats.node_count, 127);
+    assert_eq!(stats.leaf_count, 64);
+    assert_eq!(stats.max_depth, 7);
+    assert_eq!(stats.mean_leaf_depth, 7.0);
+    assert_eq!(stats.balance, 1.0);
+    assert_eq!(stats.mu_values.len(), 63);
+    assert!(stats.mu_values.windows(2).all(|w| w[0] <= w[1]));
+}
+
+#[test]
+fn dot_export() {
+    let empty: VPTree<f32, Point> = VPTree::new(Vec::new());
+    assert_eq!(empty.to_dot(), "digraph vptree {\n}\n");
+
+    let vp = VPTree::builder().leaf_size(1).build((0..7).map(|x| Point(x as f32)).collect());
+    let dot = vp.to_dot();
+    assert!(dot.starts_with("digraph vptree {\n"));
+    assert_eq!(dot.matches("[label=\"Point(").count(), 7);
+    assert_eq!(dot.matches(" -> ").count(), 6);
+
+    // Bucketed items share their leaf's label.
+    let bucketed = VPTree::builder().leaf_size(4).build((0..7).map(|x| Point(x as f32)).collect());
+    let dot = bucketed.to_dot();
+    assert_eq!(dot.matches("[label=\"Point(").count(), 3);
+    assert_eq!(dot.matches("Point(").count(), 7);
+}
+
+#[test]
+fn debug_format() {
+    let empty: VPTree<f32, Point> = VPTree::new(Vec::new());
+    assert_eq!(format!("{:?}", empty), "VPTree { len: 0, depth: 0, root: None }");
+
+    let small = VPTree::builder().leaf_size(4).build((0..3).map(|x| Point(x as f32)).collect());
+    let s = format!("{:?}", small);
+    assert!(s.starts_with("VPTree { len: 3, depth: 1, root: VPNode { center: Point("));
+    assert_eq!(s.matches("Point(").count(), 3);
+
+    // Only the top levels are shown, and only some of each bucket.
+    let big = VPTree::builder().leaf_size(1).build((0..10000).map(|x| Point(x as f32)).collect());
+    let s = format!("{:?}", big);
+    assert_eq!(s.matches("VPNode {").count(), 7);
+    assert_eq!(s.matches(", .. }").count(), 4);
+    let flat = VPTree::builder().leaf_size(100).build((0..100).map(|x| Point(x as f32)).collect());
+    assert!(format!("{:?}", flat).contains(", ... 91 more]"));
+    assert!(format!("{:#?}", big).lines().count() < 100);
+}
+
+#[test]
+fn tree_view() {
+    let empty: VPTree<f32, Point> = VPTree::new(Vec::new());
+    assert!(empty.view().is_none());
+
+    let tree = VPTree::builder().leaf_size(3).build((0..100).map(|x| Point(x as f32)).collect());
+    let root = tree.view().unwrap();
+    assert_eq!(root.size(), 100);
+    assert_eq!(root.items().count(), 100);
+
+    // Every subtree's items lie within the ranges stored for it.
+    let mut stack = vec![root];
+    let mut leaves = 0;
+    while let Some(node) = stack.pop() {
+        let c = node.center().0;
+        assert!(node.items().all(|p| (p.0 - c).abs() <= node.radius()));
+        if node.is_leaf() {
+            leaves += 1;
+            assert_eq!(node.bucket().count() + 1, node.size());
+            assert_eq!(node.mu(), None);
+            assert!(node.inner().is_none() && node.outer().is_none());
+            continue;
+        }
+
+        assert_eq!(node.bucket().count(), 0);
+        let inner = node.inner().unwrap();
+        let (lo, hi) = node.inner_range().unwrap();
+        assert_eq!(Some(hi), node.mu());
+        assert!(inner.items().all(|p| (lo..=hi).contains(&(p.0 - c).abs())));
+        let mut size = 1 + inner.size();
+        if let Some(outer) = node.outer() {
+            let (lo, hi) = node.outer_range().unwrap();
+            assert!(outer.items().all(|p| (lo..=hi).contains(&(p.0 - c).abs())));
+            size += outer.size();
+            stack.push(outer);
+        }
+        assert_eq!(size, node.size());
+        stack.push(inner);
+    }
+    assert_eq!(leaves, tree.leaf_count());
+
+    assert_eq!(root.dump(""), tree.dump());
+    assert!(format!("{:?}", tree).ends_with(&format!("root: {:?} }}", root)));
+}
+
+#[test]
+fn visit_nodes() {
+    let empty: VPTree<f32, Point> = VPTree::new(Vec::new());
+    assert_eq!(empty.visit(|_| ControlFlow::Break(())), ControlFlow::Continue(()));
+
+    let tree = VPTree::builder().leaf_size(1).build((0..127).map(|x| Point(x as f32)).collect());
+    let mut visited = Vec::new();
+    let done = tree.visit(|info| -> ControlFlow<()> {
+        visited.push((info.depth, info.size, info.mu.is_some()));
+        ControlFlow::Continue(())
+    });
+    assert_eq!(done, ControlFlow::Continue(()));
+    assert_eq!(visited.len(), 127);
+
+    // In pre-order, each node is followed by its inner subtree, whose
+    // root is one level deeper.
+    assert_eq!(visited[0], (1, 127, true));
+    assert_eq!(&visited[1..7], &[(2, 63, true), (3, 31, true), (4, 15, true), (5, 7, true), (6, 3, true), (7, 1, false)]);
+    assert_eq!(visited.iter().filter(|v| v.0 == 7).count(), 64);
+
+    // Stop at the first leaf.
+    let mut count = 0;
+    let leaf = tree.visit(|info| {
+        count += 1;
+        if info.node.is_leaf() { ControlFlow::Break(info.node.center().0) } else { ControlFlow::Continue(()) }
+    });
+    assert!(matches!(leaf, ControlFlow::Break(_)));
+    assert_eq!(count, 7);
+}
+
+#[test]
+fn leaf_buckets() {
+    let points: Vec<_> = (0..127).map(|x| Point(x as f32)).collect();
+
+    // A single bucket holds everything.
+    let flat = VPTree::builder().leaf_size(127).build(points.clone());
+    assert_eq!(flat.depth(), 1);
+    assert_eq!(flat.leaf_count(), 1);
+
+    let bucketed = VPTree::builder().leaf_size(8).build(points.clone());
+    assert_eq!(bucketed.len(), 127);
+    assert!(bucketed.depth() < 7);
+    assert!(VPTree::builder().leaf_size(0).try_build(points.clone()).is_err());
+
+    for tree in &[flat, bucketed] {
+        assert_eq!(tree.nearest_neighbors(&Point(40.2), 3, true), vec![&Point(40.0), &Point(41.0), &Point(39.0)]);
+        assert_eq!(tree.within_radius(&Point(10.2), 1.5, true), vec![&Point(10.0), &Point(11.0), &Point(9.0)]);
+        assert_eq!(tree.count_within_radius(&Point(10.0), 20.5), 31);
+        assert_eq!(tree.farthest_neighbor(&Point(100.0)), Some(&Point(0.0)));
+        assert_eq!(tree.pairs_within(1.5).len(), 126);
+        assert_eq!(tree.knn_graph(1)[126], vec![(125, 1.0)]);
+    }
+}
+
+#[test]
+fn depth_limit() {
+    let points: Vec<_> = (0..127).map(|x| Point(x as f32)).collect();
+    assert!(VPTree::builder().max_depth(0).try_build(points.clone()).is_err());
+
+    let shallow = VPTree::builder().leaf_size(1).max_depth(3).build(points.clone());
+    assert_eq!(shallow.len(), 127);
+    assert_eq!(shallow.depth(), 3);
+    assert_eq!(shallow.leaf_count(), 4);
+    assert_eq!(shallow.nearest_neighbors(&Point(40.2), 3, true), vec![&Point(40.0), &Point(41.0), &Point(39.0)]);
+    assert_eq!(shallow.count_within_radius(&Point(10.0), 20.5), 31);
+
+    // A limit deeper than the tree has no effect.
+    let deep = VPTree::builder().leaf_size(1).max_depth(20).build(points);
+    assert_eq!(deep.depth(), 7);
+}
+
+#[test]
+fn duplicated_distances() {
+    // Exact median splits divide runs of equal distances by position,
+    // so heavily duplicated distances still give a balanced tree.
+    let points: Vec<Point> = (0..100_000).map(|i| Point((i % 3) as f32)).collect();
+    let tree = VPTree::builder().leaf_size(1).seed(5).build(points);
+    assert_eq!(tree.len(), 100_000);
+    assert!(tree.depth() <= 18, "depth {}", tree.depth());
+    assert_eq!(tree.within_radius(&Point(2.0), 0.5, false).len(), 33_333);
+
+    // The same holds for sampled medians, which split three ways.
+    let points: Vec<Point> = (0..10_000).map(|i| Point((i % 4) as f32)).collect();
+    let tree = VPTree::builder().leaf_size(1).median_sample_size(15).seed(5).build(points);
+    assert!(tree.depth() <= 16, "depth {}", tree.depth());
+    for x in 0..4 {
+        assert_eq!(tree.within_radius(&Point(x as f32), 0.5, false).len(), 2500);
+        assert_eq!(tree.nearest_neighbors(&Point(x as f32 + 0.1), 3000, true)[2499], &Point(x as f32));
+    }
+}
+
+#[test]
+fn identical_points() {
+    // Every distance ties with a sampled median, and the ties are
+    // divided evenly between the subtrees.
+    let points = vec![Point(0.0); 10000];
+    let tree = VPTree::builder()
+        .leaf_size(1)
+        .median_sample_size(1)
+        .vantage_strategy(VantageStrategy::Random)
+        .build(points);
+    assert_eq!(tree.len(), 10000);
+    assert_eq!(tree.depth(), 14);
+    assert_eq!(tree.nearest_neighbors(&Point(1.0), 3, false).len(), 3);
+    assert_eq!(tree.within_radius(&Point(1.0), 1.5, false).len(), 10000);
+}
+
+#[test]
+fn node_layouts() {
+    let points: Vec<_> = (0..200).map(|x| Point(x as f32)).collect();
+    let reference = VPTree::builder().leaf_size(3).seed(7).build(points.clone());
+
+    for &layout in &[NodeLayout::PreOrder, NodeLayout::BreadthFirst, NodeLayout::VanEmdeBoas] {
+        let tree = VPTree::builder().leaf_size(3).seed(7).layout(layout).build(points.clone());
+        assert_eq!(tree.stats(), reference.stats());
+        assert_eq!(tree.dump(), reference.dump());
+        assert_eq!(tree.nearest_neighbors(&Point(50.3), 5, true),
+                   reference.nearest_neighbors(&Point(50.3), 5, true));
+        assert_eq!(tree.knn_graph(2), reference.knn_graph(2));
+
+        // Reverting to pre-order restores the original layout.
+        let mut relaid = tree;
+        relaid.relayout(NodeLayout::PreOrder);
+        assert_eq!(relaid.to_dot(), reference.to_dot());
+    }
+}
@@ -0,0 +1,41 @@
+extern crate vptree;
+
+use vptree::{MetricItem, ShardedVPTree, VPTree};
+
+#[derive(Debug, PartialEq, Clone)]
+struct Point(f32);
+
+impl MetricItem<f32> for Point {
+    fn distance(&self, a: &Self) -> f32 {
+        (self.0 - a.0).abs()
+    }
+}
+
+#[test]
+fn sharded_matches_single_tree() {
+    let points: Vec<_> = (0..500).map(|x| Point(((x * 193) % 500) as f32)).collect();
+    let tree = VPTree::new(points.clone());
+    let index = ShardedVPTree::new(points, 3);
+    assert_eq!(index.shards().iter().map(|s| s.len()).collect::<Vec<_>>(), vec![167, 167, 166]);
+    assert_eq!(index.len(), 500);
+
+    for x in 0..100 {
+        let q = Point(x as f32 * 5.1 + 0.05);
+        assert_eq!(index.nearest_neighbors(&q, 5, true), tree.nearest_neighbors(&q, 5, true));
+        assert_eq!(index.within_radius(&q, 3.5, true), tree.within_radius(&q, 3.5, true));
+        assert_eq!(index.count_within_radius(&q, 3.5), tree.count_within_radius(&q, 3.5));
+        assert_eq!(index.any_within_radius(&q, 0.1), tree.any_within_radius(&q, 0.1));
+    }
+}
+
+#[test]
+fn sharded_from_split_tree() {
+    let tree = VPTree::new((0..50).map(|x| Point(x as f32)).collect());
+    let index = ShardedVPTree::from_shards(tree.split_into(5));
+    assert_eq!(index.nearest_neighbor(&Point(12.8)), Some(&Point(13.0)));
+    assert_eq!(index.nearest_neighbors(&Point(100.0), 100, false).len(), 50);
+
+    let empty: ShardedVPTree<f32, Point> = ShardedVPTree::new(Vec::new(), 2);
+    assert!(empty.is_empty());
+    assert_eq!(empty.nearest_neighbor(&Point(0.0)), None);
+}
@@ -0,0 +1,84 @@
+extern crate vptree;
+
+use std::time::Duration;
+
+use vptree::{MetricItem, SlidingVPTree, TtlVPTree, VPTree};
+
+#[derive(Debug, PartialEq, Clone)]
+struct Point(f32);
+
+impl MetricItem<f32> for Point {
+    fn distance(&self, a: &Self) -> f32 {
+        (self.0 - a.0).abs()
+    }
+}
+
+#[test]
+fn window_moves_forward() {
+    let mut window = SlidingVPTree::with_builder(&VPTree::builder().leaf_size(4), 0u32);
+    // Point x arrives at time x and lives for 50 ticks.
+    for t in 0..200 {
+        window.advance(t);
+        window.insert(Point(t as f32), t + 50);
+        assert_eq!(window.len(), (t as usize + 1).min(50));
+
+        let oldest = t.saturating_sub(49) as f32;
+        assert_eq!(window.nearest_neighbor(&Point(-10.0)), Some(&Point(oldest)));
+        assert_eq!(window.nearest_neighbors(&Point(t as f32 + 1.0), 2, true).len(), 2.min(t as usize + 1));
+        assert_eq!(window.within_radius(&Point(oldest), 1.5, true)[0], &Point(oldest));
+    }
+
+    // Time only moves forward.
+    window.advance(10);
+    assert_eq!(window.now(), 199);
+
+    window.advance(1000);
+    assert!(window.is_empty());
+    assert_eq!(window.nearest_neighbor(&Point(0.0)), None);
+
+    window.insert(Point(5.0), 999);
+    window.insert(Point(6.0), 1001);
+    window.compact();
+    assert_eq!(window.len(), 1);
+    assert_eq!(window.within_radius(&Point(5.0), 2.0, false), vec![&Point(6.0)]);
+}
+
+#[test]
+fn items_outlive_their_ttl() {
+    let mut cache = TtlVPTree::with_builder(&VPTree::builder().leaf_size(4));
+    for x in 0..100 {
+        // Even points expire immediately, odd points never do.
+        let ttl = if x % 2 == 0 { Some(Duration::from_secs(0)) } else { None };
+        cache.insert(Point(x as f32), ttl);
+    }
+    cache.insert(Point(100.0), Some(Duration::from_secs(3600)));
+    assert_eq!(cache.len(), 51);
+
+    assert_eq!(cache.nearest_neighbor(&Point(50.2)), Some(&Point(51.0)));
+    assert_eq!(cache.nearest_neighbors(&Point(-1.0), 3, true), vec![&Point(1.0), &Point(3.0), &Point(5.0)]);
+    assert_eq!(cache.within_radius(&Point(99.0), 1.5, true), vec![&Point(99.0), &Point(100.0)]);
+
+    cache.purge_expired();
+    assert_eq!(cache.len(), 51);
+    assert_eq!(cache.nearest_neighbor(&Point(0.0)), Some(&Point(1.0)));
+}
+
+
+#[test]
+fn duplicates_expire_separately() {
+    let mut window = SlidingVPTree::new(0u32);
+    window.insert(Point(1.0), 10);
+    window.insert(Point(1.0), 20);
+    window.insert(Point(5.0), 30);
+    window.advance(15);
+    assert_eq!(window.len(), 2);
+    assert_eq!(window.within_radius(&Point(1.0), 0.5, false), vec![&Point(1.0)]);
+    window.advance(25);
+    assert_eq!(window.nearest_neighbor(&Point(1.0)), Some(&Point(5.0)));
+}
+
+#[test]
+#[should_panic(expected = "windows cannot collapse duplicate items")]
+fn windows_reject_collapsing_builders() {
+    SlidingVPTree::<f32, Point, u32>::with_builder(&VPTree::builder().collapse_duplicates(true), 0);
+}
@@ -0,0 +1,139 @@
+extern crate rand;
+extern crate vptree;
+
+use rand::{Rng, SeedableRng, XorShiftRng};
+use vptree::{BuildError, MetricItem, SpillTree};
+use vptree::metrics::Euclidean;
+
+#[derive(Debug, PartialEq, Clone)]
+struct Point(f32);
+
+impl MetricItem<f32> for Point {
+    fn distance(&self, a: &Self) -> f32 {
+        (self.0 - a.0).abs()
+    }
+}
+
+fn random_points<R: Rng>(n: usize, dim: usize, rng: &mut R) -> Vec<Euclidean<Vec<f64>>> {
+    (0..n).map(|_| Euclidean((0..dim).map(|_| rng.gen::<f64>()).collect())).collect()
+}
+
+/// Return the fraction of the true `k` nearest neighbors a spill tree
+/// with the given overlap finds, over many queries.
+fn recall(overlap: f64) -> f64 {
+    let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+    let points = random_points(2000, 8, &mut rng);
+    let queries = random_points(100, 8, &mut rng);
+    let tree = SpillTree::try_new_with_rng(points.clone(), overlap, 16, &mut rng).unwrap();
+
+    let k = 5;
+    let mut found = 0;
+    for q in &queries {
+        let mut exact: Vec<_> = points.iter().map(|p| p.distance(q)).collect();
+        exact.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let approx = tree.nearest_neighbors(q, k, true);
+        assert_eq!(approx.len(), k);
+        found += approx.iter().filter(|p| p.distance(q) <= exact[k - 1]).count();
+    }
+    found as f64 / (k * queries.len()) as f64
+}
+
+#[test]
+fn overlap_improves_recall() {
+    let plain = recall(0.0);
+    let spilled = recall(0.1);
+    assert!(spilled > plain, "recall {} with overlap, {} without", spilled, plain);
+    assert!(spilled > 1.2 * plain);
+}
+
+#[test]
+fn single_leaf_is_exact() {
+    let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+    let points: Vec<_> = (0..10).map(|x| Point(x as f32)).collect();
+    let tree = SpillTree::try_new_with_rng(points, 0.1, 16, &mut rng).unwrap();
+    assert_eq!(tree.len(), 10);
+    assert_eq!(tree.entry_count(), 10);
+
+    assert_eq!(tree.nearest_neighbor(&Point(3.2)), Some(&Point(3.0)));
+    assert_eq!(tree.nearest_neighbors(&Point(3.2), 3, true), vec![&Point(3.0), &Point(4.0), &Point(2.0)]);
+    assert_eq!(tree.nearest_neighbors(&Point(3.2), 20, false).len(), 10);
+    assert!(tree.nearest_neighbors(&Point(3.2), 0, true).is_empty());
+    assert_eq!(tree.within_radius(&Point(3.2), 1.5, true), vec![&Point(3.0), &Point(4.0), &Point(2.0)]);
+}
+
+#[test]
+fn paths_never_repeat_items() {
+    let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+    let points: Vec<_> = (0..500).map(|x| Point(x as f32)).collect();
+    let tree = SpillTree::try_new_with_rng(points, 0.15, 4, &mut rng).unwrap();
+    for i in 0..50 {
+        let q = Point(i as f32 * 10.0 + 0.3);
+        let mut found: Vec<_> = tree.within_radius(&q, 1000.0, false).into_iter().map(|p| p.0 as i32).collect();
+        let n = found.len();
+        found.sort();
+        found.dedup();
+        assert_eq!(found.len(), n);
+    }
+}
+
+#[test]
+fn overlap_costs_entries() {
+    let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+    let points = random_points(1000, 4, &mut rng);
+    let mut counts = Vec::new();
+    for &overlap in &[0.0, 0.05, 0.1] {
+        let tree = SpillTree::try_new_with_rng(points.clone(), overlap, 16, &mut rng).unwrap();
+        assert_eq!(tree.len(), 1000);
+        counts.push(tree.entry_count());
+    }
+    assert_eq!(counts[0], 1000);
+    assert!(counts[0] < counts[1] && counts[1] < counts[2]);
+}
+
+#[test]
+fn storage_is_bounded() {
+    let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+    let points = || (0..10000).map(|x| Point(x as f32)).collect::<Vec<_>>();
+
+    let tree = SpillTree::try_new_with_rng(points(), 0.1, 16, &mut rng).unwrap();
+    assert!(tree.entry_count() > 5 * tree.len());
+    assert!(tree.entry_count() < 20 * tree.len());
+
+    // Some 400 copies of each item.
+    match SpillTree::try_new_with_rng(points(), 0.2, 16, &mut rng) {
+        Err(BuildError::InvalidParameter(_)) => {},
+        _ => panic!("trees storing too many copies should be rejected")
+    }
+    let small = SpillTree::try_new_with_rng(points(), 0.2, 2000, &mut rng).unwrap();
+    assert!(small.entry_count() < 8 * small.len());
+}
+
+#[test]
+fn invalid_parameters() {
+    let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+    let points = || (0..10).map(|x| Point(x as f32)).collect::<Vec<_>>();
+    for &overlap in &[-0.1, 0.25, 0.5, f64::NAN] {
+        match SpillTree::try_new_with_rng(points(), overlap, 16, &mut rng) {
+            Err(BuildError::InvalidParameter(_)) => {},
+            _ => panic!("overlap {} should be rejected", overlap)
+        }
+    }
+    match SpillTree::try_new_with_rng(points(), 0.1, 0, &mut rng) {
+        Err(BuildError::InvalidParameter(_)) => {},
+        _ => panic!("leaf size 0 should be rejected")
+    }
+    match SpillTree::try_new_with_rng(vec![Point(0.0), Point(f32::NAN)], 0.1, 1, &mut rng) {
+        Err(BuildError::InvalidDistance) => {},
+        _ => panic!("NaN distances should be rejected")
+    }
+}
+
+#[test]
+fn empty_tree() {
+    let tree: SpillTree<f32, Point> = SpillTree::new(Vec::new(), 0.2);
+    assert!(tree.is_empty());
+    assert_eq!(tree.entry_count(), 0);
+    assert_eq!(tree.nearest_neighbor(&Point(1.0)), None);
+    assert!(tree.nearest_neighbors(&Point(1.0), 3, true).is_empty());
+    assert!(tree.within_radius(&Point(1.0), 10.0, true).is_empty());
+}
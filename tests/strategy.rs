@@ -0,0 +1,103 @@
+extern crate rand;
+extern crate vptree;
+
+use rand::{Rng, SeedableRng, StdRng};
+
+use vptree::{MetricItem, VPTree, VantageStrategy};
+
+#[derive(Debug, PartialEq, Clone)]
+struct Point(f32);
+
+impl MetricItem<f32> for Point {
+    fn distance(&self, a: &Self) -> f32 {
+        (self.0 - a.0).abs()
+    }
+}
+
+#[test]
+fn every_strategy_finds_neighbors() {
+    let points: Vec<_> = (1..200).map(|x| Point(1.0 / (x as f32))).collect();
+    let strategies = [VantageStrategy::Random, VantageStrategy::FurthestFromRandom,
+                      VantageStrategy::MaxSpread, VantageStrategy::Sampled(4),
+                      VantageStrategy::Yianilos { candidates: 5, sample_size: 10 }];
+
+    for &strategy in &strategies {
+        let vp = VPTree::builder().vantage_strategy(strategy).build(points.clone());
+        assert_eq!(vp.len(), points.len());
+        for p in &points {
+            assert_eq!(vp.nearest_neighbor(p), Some(p));
+        }
+    }
+}
+
+#[test]
+fn custom_selector() {
+    let points: Vec<_> = (0..100).map(|x| Point(x as f32)).collect();
+
+    // Always split on the smallest point.
+    let vp = VPTree::builder()
+        .vantage_selector(|items: &[&Point]| {
+            (0..items.len()).fold(0, |best, i| if items[i].0 < items[best].0 { i } else { best })
+        })
+        .build(points.clone());
+    for p in &points {
+        assert_eq!(vp.nearest_neighbor(p), Some(p));
+    }
+
+    let bad = VPTree::builder()
+        .vantage_selector(|items: &[&Point]| items.len())
+        .try_build(points);
+    assert!(bad.is_err());
+}
+
+#[test]
+fn seeded_construction_is_reproducible() {
+    let points: Vec<_> = (1..300).map(|x| Point(1.0 / (x as f32))).collect();
+
+    let a = VPTree::new_seeded(points.clone(), 42);
+    let b = VPTree::new_seeded(points.clone(), 42);
+    assert_eq!(a.dump(), b.dump());
+
+    let strategy = VantageStrategy::Yianilos { candidates: 3, sample_size: 8 };
+    let c = VPTree::builder().vantage_strategy(strategy).seed(0).build(points.clone());
+    let d = VPTree::builder().vantage_strategy(strategy).seed(0).build(points);
+    assert_eq!(c.dump(), d.dump());
+}
+
+/// A generator that always returns zero, so every random choice picks
+/// the first candidate.
+struct ZeroRng;
+
+impl Rng for ZeroRng {
+    fn next_u32(&mut self) -> u32 {
+        0
+    }
+}
+
+#[test]
+fn construction_with_rng() {
+    let points: Vec<_> = (1..300).map(|x| Point(1.0 / (x as f32))).collect();
+
+    let a = VPTree::new_with_rng(points.clone(), &mut StdRng::from_seed(&[7usize][..]));
+    let b = VPTree::new_with_rng(points.clone(), &mut StdRng::from_seed(&[7usize][..]));
+    assert_eq!(a.dump(), b.dump());
+
+    let c = VPTree::builder().vantage_strategy(VantageStrategy::Random).build_with_rng(points.clone(), &mut ZeroRng);
+    for p in &points {
+        assert_eq!(c.nearest_neighbor(p), Some(p));
+    }
+}
+
+#[test]
+fn sampled_median_construction() {
+    let points: Vec<_> = (0..2000).map(|x| Point(x as f32)).collect();
+
+    let vp = VPTree::builder().median_sample_size(64).seed(3).build(points.clone());
+    assert_eq!(vp.len(), points.len());
+    for p in points.iter().step_by(7) {
+        assert_eq!(vp.nearest_neighbor(p), Some(p));
+    }
+    assert_eq!(vp.within_radius(&Point(1000.0), 10.5, false).len(), 21);
+
+    assert!(VPTree::builder().median_sample_size(0).try_build(points).is_err());
+}
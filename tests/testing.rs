@@ -0,0 +1,36 @@
+#![cfg(feature = "testing")]
+
+#[macro_use]
+extern crate proptest;
+extern crate vptree;
+
+use proptest::test_runner::Config as ProptestConfig;
+use vptree::VPTree;
+use vptree::metrics::{Chebyshev, Euclidean};
+use vptree::testing::{assert_matches_brute_force, clusters, manifold, uniform};
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn uniform_points(points in uniform(3, 0..300), queries in uniform(3, 5)) {
+        let points = points.into_iter().map(Euclidean).collect();
+        let queries: Vec<_> = queries.into_iter().map(Euclidean).collect();
+        assert_matches_brute_force(&VPTree::builder().leaf_size(2), points, &queries, 7, 0.5);
+    }
+
+    #[test]
+    fn clustered_points(points in clusters(2, 3, 1..300), queries in clusters(2, 3, 5)) {
+        let points = points.into_iter().map(Chebyshev).collect();
+        let queries: Vec<_> = queries.into_iter().map(Chebyshev).collect();
+        assert_matches_brute_force(&VPTree::builder().median_sample_size(5), points, &queries, 3, 1.0);
+    }
+
+    #[test]
+    fn manifold_points(points in manifold(8, 2, 1..300), queries in manifold(8, 2, 5)) {
+        prop_assert!(points.iter().all(|p| p.len() == 8));
+        let points = points.into_iter().map(Euclidean).collect();
+        let queries: Vec<_> = queries.into_iter().map(Euclidean).collect();
+        assert_matches_brute_force(&VPTree::builder(), points, &queries, 10, 1.0);
+    }
+}
@@ -0,0 +1,79 @@
+extern crate vptree;
+
+use vptree::{DistanceCounter, MetricItem, TraceEvent, VPTree};
+
+#[derive(Debug, PartialEq, Clone)]
+struct Point(f32, f32);
+
+impl MetricItem<f32> for Point {
+    fn distance(&self, a: &Self) -> f32 {
+        ((self.0 - a.0).powi(2) + (self.1 - a.1).powi(2)).sqrt()
+    }
+}
+
+fn points() -> Vec<Point> {
+    (0..1000).map(|i| Point((i % 37) as f32 * 0.9, (i / 37) as f32 * 1.1)).collect()
+}
+
+/// Return the number of items the events account for.
+fn accounted<F: vptree::Distance, T: MetricItem<F>>(events: &[TraceEvent<F, T>]) -> usize {
+    events.iter().map(|e| match *e {
+        TraceEvent::Prune { subtree, .. } => subtree.size(),
+        _ => 1,
+    }).sum()
+}
+
+#[test]
+fn traced_nearest_neighbors() {
+    let counter = DistanceCounter::new();
+    let tree = VPTree::builder().leaf_size(8).seed(3).build(points().into_iter().map(|p| counter.wrap(p)).collect());
+
+    for i in 0..20 {
+        let q = counter.wrap(Point(i as f32 * 1.7, i as f32 * 1.3));
+        counter.reset();
+        let expected = tree.nearest_neighbors(&q, 5, true);
+        let evaluations = counter.count();
+
+        let (found, trace) = tree.nearest_neighbors_traced(&q, 5, true);
+        assert_eq!(found, expected);
+        assert_eq!(trace.distance_evaluations(), evaluations);
+        assert_eq!(accounted(&trace.events), 1000);
+        assert_eq!(trace.visited().next(), tree.view().map(|v| v.center()));
+
+        for e in &trace.events {
+            match *e {
+                TraceEvent::Prune { bound, limit, subtree } => {
+                    assert!(bound >= limit);
+                    assert!(subtree.items().all(|p| q.distance(p) >= bound));
+                },
+                TraceEvent::Skip { item, bound, limit } => {
+                    assert!(bound >= limit);
+                    assert!(q.distance(item) >= bound);
+                },
+                TraceEvent::Visit { center: item, distance } | TraceEvent::Evaluate { item, distance } => {
+                    assert_eq!(q.distance(item), distance);
+                },
+            }
+        }
+    }
+}
+
+#[test]
+fn traced_within_radius() {
+    let tree = VPTree::builder().leaf_size(8).seed(3).build(points());
+
+    for i in 0..20 {
+        let q = Point(i as f32 * 1.7, i as f32 * 1.3);
+        let (found, trace) = tree.within_radius_traced(&q, 2.5, false);
+        assert_eq!(found, tree.within_radius(&q, 2.5, false));
+        assert_eq!(accounted(&trace.events), 1000);
+        assert!(trace.events.iter().all(|e| match *e {
+            TraceEvent::Prune { bound, limit, .. } | TraceEvent::Skip { bound, limit, .. } => bound >= limit && limit == 2.5,
+            _ => true,
+        }));
+    }
+
+    let empty: VPTree<f32, Point> = VPTree::new(Vec::new());
+    assert!(empty.within_radius_traced(&Point(0.0, 0.0), 1.0, true).1.events.is_empty());
+    assert!(empty.nearest_neighbors_traced(&Point(0.0, 0.0), 1, true).1.events.is_empty());
+}
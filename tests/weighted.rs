@@ -0,0 +1,63 @@
+extern crate rand;
+extern crate vptree;
+
+use rand::{Rng, SeedableRng, XorShiftRng};
+use vptree::{BuildError, MetricItem, VPTree, WeightedVPTree};
+
+#[derive(Debug, PartialEq, Clone)]
+struct Point(f32, f32);
+
+impl MetricItem<f32> for Point {
+    fn distance(&self, a: &Self) -> f32 {
+        ((self.0 - a.0).powi(2) + (self.1 - a.1).powi(2)).sqrt()
+    }
+}
+
+#[test]
+fn weighted_queries_match_brute_force() {
+    let mut rng = XorShiftRng::from_seed([5, 6, 7, 8]);
+    let items: Vec<(Point, f32)> = (0..500).map(|_| (Point(rng.gen(), rng.gen()), rng.gen_range(0.1, 5.0))).collect();
+    for &leaf_size in &[1, 8] {
+        let tree = WeightedVPTree::try_with_builder(&VPTree::builder().leaf_size(leaf_size), items.clone()).unwrap();
+        assert_eq!(tree.len(), 500);
+        let total: f32 = items.iter().map(|x| x.1).sum();
+        assert!((tree.total_weight() - total).abs() < 1e-2);
+
+        for _ in 0..50 {
+            let q = Point(rng.gen(), rng.gen());
+            let mut scores: Vec<(f32, f32)> = items.iter().map(|&(ref p, w)| (p.distance(&q) / w, w)).collect();
+            scores.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+            let found: Vec<f32> = tree.weighted_nearest_neighbors(&q, 5, true).into_iter().map(|(p, w)| p.distance(&q) / w).collect();
+            let expected: Vec<f32> = scores[..5].iter().map(|x| x.0).collect();
+            assert_eq!(found, expected);
+
+            let r = rng.gen_range(0.05, 0.5);
+            let expected: f32 = items.iter().filter(|&(p, _)| p.distance(&q) < r).map(|x| x.1).sum();
+            assert!((tree.weight_within_radius(&q, r) - expected).abs() < 1e-3);
+        }
+    }
+}
+
+#[test]
+fn invalid_weights() {
+    let err = WeightedVPTree::try_with_builder(&VPTree::builder(), vec![(Point(0.0, 0.0), 0.0)]).err();
+    assert_eq!(err, Some(BuildError::InvalidParameter("weights must be positive and finite")));
+
+    let empty: WeightedVPTree<f32, Point> = WeightedVPTree::new(Vec::new());
+    assert_eq!(empty.weighted_nearest_neighbor(&Point(0.0, 0.0)), None);
+    assert_eq!(empty.weight_within_radius(&Point(0.0, 0.0), 1.0), 0.0);
+
+    let err = WeightedVPTree::<f32, Point>::try_with_builder(&VPTree::builder().leaf_size(0), Vec::new()).err();
+    assert_eq!(err, Some(BuildError::InvalidParameter("leaf size must be positive")));
+}
+
+#[test]
+fn duplicates_keep_their_weights() {
+    let items = vec![(Point(1.0, 0.0), 1.0), (Point(1.0, 0.0), 2.0), (Point(2.0, 0.0), 4.0), (Point(3.0, 0.0), 8.0)];
+    let err = WeightedVPTree::try_with_builder(&VPTree::builder().collapse_duplicates(true), items.clone()).err();
+    assert_eq!(err, Some(BuildError::InvalidParameter("weighted trees cannot collapse duplicate items")));
+
+    let tree = WeightedVPTree::new(items);
+    assert_eq!(tree.weight_within_radius(&Point(1.0, 0.0), 0.5), 3.0);
+    assert_eq!(tree.weight_within_radius(&Point(3.0, 0.0), 0.5), 8.0);
+}